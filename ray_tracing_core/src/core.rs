@@ -3,12 +3,21 @@ pub mod object;
 mod hit_record;
 pub use self::hit_record::HitRecord;
 
+mod aov_record;
+pub use self::aov_record::AovRecord;
+
+mod sample_diagnostics;
+pub use self::sample_diagnostics::{NonFiniteCount, SampleDiagnostics, SampleSource};
+
 mod scatter_record;
 pub use self::scatter_record::ScatterRecord;
 
 mod configuration;
 pub use self::configuration::Configuration;
 
+mod light_sampler;
+pub use self::light_sampler::{LightSample, LightSampler, LightSelection};
+
 mod scene;
 pub use self::scene::Scene;
 
@@ -0,0 +1,104 @@
+use crate::core::{HitRecord, SampleDiagnostics, SampleSource};
+use crate::types::{ColorRGB, FSize, Point3, Vector3};
+
+/// Arbitrary output variables of a camera ray, which are written in addition to the color.
+///
+/// The geometric values belong to the first hit of the ray. If the ray misses the world,
+/// the albedo is the color of the sky and the other values are 0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AovRecord {
+    /// Color of the surface at the first hit (`HitRecord::color_channels`)
+    pub albedo: ColorRGB,
+
+    /// Shading normal at the first hit
+    pub normal: Vector3,
+
+    /// Ray parameter `t` of the first hit
+    pub depth: FSize,
+
+    /// World position of the first hit
+    pub position: Point3,
+
+    /// Id of the geometry at the first hit
+    pub object_id: usize,
+
+    /// Id of the material at the first hit
+    pub material_id: usize,
+
+    /// Light, which is emitted by the first hit or reaches the camera after 1 bounce
+    pub direct: ColorRGB,
+
+    /// Light, which reaches the camera after 2 or more bounces
+    pub indirect: ColorRGB,
+
+    /// Light, which is emitted by the first hit or by the sky, if the ray misses the world.
+    /// The light is part of `direct`, but it isn't clamped by [`crate::core::Configuration::clamp_direct`].
+    pub emitted: ColorRGB,
+
+    /// Hit, at which the light or the throughput of the path became NaN or infinite
+    pub non_finite_source: Option<SampleSource>,
+}
+
+impl AovRecord {
+    pub fn empty() -> AovRecord {
+        AovRecord {
+            albedo: ColorRGB::new(0.0, 0.0, 0.0),
+            normal: Vector3::new(0.0, 0.0, 0.0),
+            depth: 0.0,
+            position: Point3::new(0.0, 0.0, 0.0),
+            object_id: 0,
+            material_id: 0,
+            direct: ColorRGB::new(0.0, 0.0, 0.0),
+            indirect: ColorRGB::new(0.0, 0.0, 0.0),
+            emitted: ColorRGB::new(0.0, 0.0, 0.0),
+            non_finite_source: None,
+        }
+    }
+
+    /// Records the geometric values of the first hit
+    pub fn set_first_hit(&mut self, hit_record: &HitRecord) {
+        self.albedo = hit_record.color_channels.truncate(3);
+        self.normal = hit_record.normal;
+        self.depth = hit_record.t;
+        self.position = hit_record.position;
+        self.object_id = hit_record.object_id;
+        self.material_id = hit_record.material.get_id();
+    }
+
+    /// Linear interpolation of the values with the weight `w` of `other`.
+    /// The ids can't be interpolated, the ids of `self` are kept.
+    pub fn mix(&self, other: &AovRecord, w: FSize) -> AovRecord {
+        AovRecord {
+            albedo: self.albedo * (1.0 - w) + other.albedo * w,
+            normal: self.normal * (1.0 - w) + other.normal * w,
+            depth: self.depth * (1.0 - w) + other.depth * w,
+            position: self.position * (1.0 - w) + other.position * w,
+            object_id: self.object_id,
+            material_id: self.material_id,
+            direct: self.direct * (1.0 - w) + other.direct * w,
+            indirect: self.indirect * (1.0 - w) + other.indirect * w,
+            emitted: self.emitted * (1.0 - w) + other.emitted * w,
+            non_finite_source: self.non_finite_source,
+        }
+    }
+
+    /// Records the hit as source of the non-finite values, if `color` isn't finite.
+    /// Only the first source of a path is kept.
+    pub fn check_finite(&mut self, hit_record: &HitRecord, color: &ColorRGB) {
+        if self.non_finite_source.is_none() && !SampleDiagnostics::is_finite(color) {
+            self.non_finite_source = Some(SampleSource::from_hit_record(hit_record));
+        }
+    }
+
+    /// Adds light, which reaches the camera after `depth` bounces
+    pub fn add_light(&mut self, depth: usize, color: ColorRGB) {
+        if depth == 0 {
+            self.emitted = self.emitted + color;
+        }
+        if depth < 2 {
+            self.direct = self.direct + color;
+        } else {
+            self.indirect = self.indirect + color;
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use crate::core::object::Object;
 use crate::math::Ray;
 use crate::random;
+use crate::sampler;
 use crate::types::{FSize, Vector3};
 use glm;
 use std::f64::consts::PI;
@@ -137,8 +138,10 @@ impl Camera {
     }
 
     pub fn get(&self, u: FSize, v: FSize) -> Ray {
-        let rd = random::generate_unit_sphere() * self.lense_radius;
+        random::start_dimension(sampler::DIMENSION_LENS);
+        let rd = random::generate_unit_sphere_xy() * self.lense_radius;
         let offset = u * rd.x + v * rd.y;
+        random::start_dimension(sampler::DIMENSION_TIME);
         let time = if self.time.start == self.time.end {
             self.time.start
         } else {
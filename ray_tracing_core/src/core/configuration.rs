@@ -1,9 +1,40 @@
 use crate::core::object::Object;
+use crate::core::LightSelection;
+use crate::integrator::{IntegratorType, RussianRoulette};
+use crate::types::FSize;
 
 #[derive(Clone)]
 pub struct Configuration {
     pub id: usize,
     pub maximum_depth: usize,
+
+    /// Seed of the random number generation. If set, renders are reproducible.
+    pub seed: Option<u64>,
+
+    /// Selection of the light, which is sampled at a scattering event, if the scene has multiple lights
+    pub light_selection: LightSelection,
+
+    /// If set, a light is sampled at each diffuse scattering event and connected by a shadow ray
+    pub next_event_estimation: bool,
+
+    /// Rendering algorithm of the scene
+    pub integrator: IntegratorType,
+
+    /// Strategy, which terminates paths randomly, before they reach the maximum depth.
+    /// Disabled by default, scenes opt in with [`RussianRoulette::Throughput`].
+    pub russian_roulette: RussianRoulette,
+
+    /// Number of bounces of a path, before Russian roulette is applied
+    pub russian_roulette_minimum_depth: usize,
+
+    /// Maximum component of the direct light of a sample, larger values are scaled down (firefly suppression)
+    pub clamp_direct: Option<FSize>,
+
+    /// Maximum component of the indirect light of a sample, larger values are scaled down (firefly suppression)
+    pub clamp_indirect: Option<FSize>,
+
+    /// If set, the NaN and infinite samples are counted per material and geometry in [`crate::core::Scene::diagnostics`]
+    pub diagnostics: bool,
 }
 
 impl Configuration {
@@ -11,6 +42,15 @@ impl Configuration {
         Configuration {
             id: Object::new_id(),
             maximum_depth: 50,
+            seed: None,
+            light_selection: LightSelection::SolidAngle,
+            next_event_estimation: false,
+            integrator: IntegratorType::PathTracer,
+            russian_roulette: RussianRoulette::Disabled,
+            russian_roulette_minimum_depth: 5,
+            clamp_direct: None,
+            clamp_indirect: None,
+            diagnostics: false,
         }
     }
 
@@ -18,6 +58,15 @@ impl Configuration {
         Configuration {
             id: Object::new_id(),
             maximum_depth,
+            seed: None,
+            light_selection: LightSelection::SolidAngle,
+            next_event_estimation: false,
+            integrator: IntegratorType::PathTracer,
+            russian_roulette: RussianRoulette::Disabled,
+            russian_roulette_minimum_depth: 5,
+            clamp_direct: None,
+            clamp_indirect: None,
+            diagnostics: false,
         }
     }
 }
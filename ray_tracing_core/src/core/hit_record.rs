@@ -13,6 +13,9 @@ pub struct HitRecord {
     pub normal: Vector3,
     pub material: Arc<dyn Material>,
     pub color_channels: ColorRGBA,
+
+    /// Id of the geometry, which was hit
+    pub object_id: usize,
 }
 
 impl HitRecord {
@@ -24,6 +27,7 @@ impl HitRecord {
             normal: Vector3::new(0.0, 0.0, 0.0),
             material: Arc::new(NoMaterial::new()),
             color_channels: ColorRGBA::new(0.0, 0.0, 0.0, 0.0),
+            object_id: 0,
         }
     }
 
@@ -42,18 +46,21 @@ impl HitRecord {
             normal,
             material,
             color_channels,
+            object_id: 0,
         }
     }
 
     pub fn from_hit_record(hit_record: &HitRecord) -> HitRecord {
-        HitRecord::new(
+        let mut new_hit_record = HitRecord::new(
             hit_record.t,
             TextureCoordinate::from_uv(hit_record.uv.u, hit_record.uv.v),
             hit_record.position,
             hit_record.normal,
             hit_record.material.clone(),
             hit_record.color_channels,
-        )
+        );
+        new_hit_record.object_id = hit_record.object_id;
+        new_hit_record
     }
 
     pub fn check_alpha_and_create(
@@ -63,6 +70,7 @@ impl HitRecord {
         position: Point3,
         normal: Vector3,
         material: Arc<dyn Material>,
+        object_id: usize,
     ) -> Option<HitRecord> {
         let selected_material = match material.material() {
             Some(m) => m,
@@ -72,17 +80,28 @@ impl HitRecord {
         if selected_material.has_alpha() && random::generate_size() > color_channels.w {
             None
         } else {
-            Some(HitRecord::new(
-                t,
-                uv,
-                position,
-                normal,
-                selected_material,
-                color_channels,
-            ))
+            let mut hit_record =
+                HitRecord::new(t, uv, position, normal, selected_material, color_channels);
+            hit_record.object_id = object_id;
+            Some(hit_record)
         }
     }
 
+    /// Checks whether the material is opaque at a surface point, without creating a hit record.
+    /// The texture is only evaluated if the material has an alpha channel.
+    pub fn check_alpha(
+        uv: &TextureCoordinate,
+        position: &Point3,
+        material: &Arc<dyn Material>,
+    ) -> bool {
+        let selected_material = match material.material() {
+            Some(m) => m,
+            None => material.clone(),
+        };
+        !selected_material.has_alpha()
+            || random::generate_size() <= selected_material.color_channels(uv, position).w
+    }
+
     pub fn invert_normal(&mut self) {
         self.normal = -self.normal;
     }
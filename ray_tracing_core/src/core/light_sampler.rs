@@ -0,0 +1,299 @@
+use crate::geometry::Geometry;
+use crate::math::Ray;
+use crate::probability_density_function::{GeometryPdf, MixturePdf, ProbabilityDensityFunction};
+use crate::random;
+use crate::types;
+use crate::types::{FSize, Point3, Vector3};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// Strategy, which selects the light, that is sampled at a scattering event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightSelection {
+    /// All lights have the same probability
+    Uniform,
+
+    /// The probability is proportional to the solid angle of the bounding sphere of the light,
+    /// seen from the scattering position
+    SolidAngle,
+
+    /// The probability is proportional to the estimated power of the light
+    Power,
+}
+
+/// Direction from a scattering position to a light
+pub struct LightSample {
+    pub light: Arc<dyn Geometry>,
+    pub direction: Vector3,
+
+    /// Probability density of the direction, including the probability of the selection of the light
+    pub pdf: FSize,
+}
+
+/// Lights of a scene, which are sampled by the light density function at scattering events
+#[derive(Clone)]
+pub struct LightSampler {
+    pub lights: Vec<Arc<dyn Geometry>>,
+    pub selection: LightSelection,
+
+    /// Estimated power of the lights, if the lights are selected by power
+    pub power: Vec<FSize>,
+}
+
+impl LightSampler {
+    /// Number of samples per direction of the estimation of the power of a light
+    const POWER_SAMPLES: usize = 16;
+
+    pub fn new(lights: Vec<Arc<dyn Geometry>>, selection: LightSelection) -> LightSampler {
+        let power = match selection {
+            LightSelection::Power => lights
+                .iter()
+                .map(|light| LightSampler::estimate_power(light.as_ref()))
+                .collect(),
+            _ => Vec::default(),
+        };
+        LightSampler {
+            lights,
+            selection,
+            power,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    /// Probabilities of the selection of the lights at a scattering position
+    pub fn weights(&self, position: &Point3) -> Vec<FSize> {
+        let weights: Vec<FSize> = match self.selection {
+            LightSelection::Uniform => vec![1.0; self.lights.len()],
+            LightSelection::SolidAngle => self
+                .lights
+                .iter()
+                .map(|light| LightSampler::solid_angle(light.as_ref(), position))
+                .collect(),
+            LightSelection::Power => self.power.clone(),
+        };
+        let sum: FSize = weights.iter().sum();
+        if sum > 0.0 {
+            weights.iter().map(|w| w / sum).collect()
+        } else {
+            vec![1.0 / self.lights.len() as FSize; self.lights.len()]
+        }
+    }
+
+    /// Mixture of the density functions of the lights, weighted by the selection probabilities
+    pub fn pdf(&self, position: &Point3) -> Option<Arc<dyn ProbabilityDensityFunction>> {
+        match self.lights.len() {
+            0 => None,
+            1 => Some(Arc::new(GeometryPdf::new(position, self.lights[0].clone()))),
+            _ => {
+                let mut pdfs: Vec<Arc<dyn ProbabilityDensityFunction>> = Vec::default();
+                let mut weights = Vec::default();
+                for (light, weight) in self.lights.iter().zip(self.weights(position)) {
+                    if weight > 0.0 {
+                        pdfs.push(Arc::new(GeometryPdf::new(position, light.clone())));
+                        weights.push(weight);
+                    }
+                }
+                Some(Arc::new(MixturePdf::new_weighted(pdfs, weights)))
+            }
+        }
+    }
+
+    /// Selects a light by the selection probabilities and generates a direction to it
+    pub fn sample(&self, position: &Point3) -> Option<LightSample> {
+        if self.lights.is_empty() {
+            return None;
+        }
+        let weights = self.weights(position);
+        let x = random::generate_size();
+        let mut sum = 0.0;
+        let mut index = weights.len() - 1;
+        for (i, weight) in weights.iter().enumerate() {
+            sum += weight;
+            if x < sum {
+                index = i;
+                break;
+            }
+        }
+        let light = self.lights[index].clone();
+        let direction = light.random(position);
+        let pdf = weights[index] * light.pdf_value(position, &direction);
+        Some(LightSample {
+            light,
+            direction,
+            pdf,
+        })
+    }
+
+    /// Probability density, with which [`LightSampler::sample`] generates the direction of `ray`
+    /// to the emitter, which is hit by `ray` at `t`.
+    /// Lights behind the emitter are occluded, they don't contribute to the density.
+    pub fn emitter_pdf_value(&self, ray: &Ray, t: FSize) -> FSize {
+        let mut pdf = 0.0;
+        for (light, weight) in self.lights.iter().zip(self.weights(&ray.origin)) {
+            if weight > 0.0 {
+                if let Some(hit_record) = light.hit(ray, 0.001..FSize::MAX) {
+                    if FSize::abs(hit_record.t - t) <= 1.0e-6 * FSize::max(1.0, t) {
+                        pdf += weight * light.pdf_value(&ray.origin, &ray.direction);
+                    }
+                }
+            }
+        }
+        pdf
+    }
+
+    /// Solid angle of the bounding sphere of the bounding box of the light
+    fn solid_angle(light: &dyn Geometry, position: &Point3) -> FSize {
+        match light.bounding_box(0.0..0.0) {
+            Some(bounding_box) => {
+                let radius_squared =
+                    types::distance_square(bounding_box.max, bounding_box.min) / 4.0;
+                let distance_squared = types::distance_square(bounding_box.centroid(), *position);
+                if distance_squared <= radius_squared {
+                    4.0 * PI
+                } else {
+                    2.0 * PI * (1.0 - FSize::sqrt(1.0 - radius_squared / distance_squared))
+                }
+            }
+            None => 4.0 * PI,
+        }
+    }
+
+    /// Estimates the power of a light by the emitted radiance, which reaches 6 points around the light.
+    /// The radiance is integrated over the solid angle of the light and scaled by the squared distance,
+    /// so the result is proportional to the power. The estimation is seeded, to get reproducible results.
+    fn estimate_power(light: &dyn Geometry) -> FSize {
+        let bounding_box = match light.bounding_box(0.0..0.0) {
+            Some(bounding_box) => bounding_box,
+            None => return 1.0,
+        };
+        let center = bounding_box.centroid();
+        let distance = FSize::max(glm::length(bounding_box.max - bounding_box.min), 0.001) * 2.0;
+        random::with_seed(Some(0), || {
+            let mut sum = 0.0;
+            for axis in 0..3 {
+                for sign in [-1.0, 1.0] {
+                    let mut offset = Vector3::new(0.0, 0.0, 0.0);
+                    offset[axis] = sign * distance;
+                    let origin = center + offset;
+                    for _ in 0..LightSampler::POWER_SAMPLES {
+                        let direction = light.random(&origin);
+                        let pdf = light.pdf_value(&origin, &direction);
+                        if pdf <= 0.0 {
+                            continue;
+                        }
+                        let ray = Ray::new_ray(origin, direction);
+                        if let Some(hit_record) = light.hit(&ray, 0.001..FSize::MAX) {
+                            let emitted = hit_record.material.emitted(&ray, &hit_record);
+                            let luminance =
+                                0.2126 * emitted.x + 0.7152 * emitted.y + 0.0722 * emitted.z;
+                            sum += FSize::max(0.0, luminance) / pdf;
+                        }
+                    }
+                }
+            }
+            sum * distance * distance / (6 * LightSampler::POWER_SAMPLES) as FSize
+        })
+    }
+}
+
+#[cfg(test)]
+mod light_sampler_test {
+    use super::*;
+    use crate::geometry::shape::Sphere;
+    use crate::material::DiffuseLight;
+    use crate::test;
+    use crate::texture::ConstantTexture;
+    use crate::types::ColorRGBA;
+
+    fn light(center: Point3, radius: FSize, intensity: FSize) -> Arc<dyn Geometry> {
+        let texture = Arc::new(ConstantTexture::new(ColorRGBA::new(
+            intensity, intensity, intensity, 1.0,
+        )));
+        Arc::new(Sphere::new(
+            center,
+            radius,
+            Arc::new(DiffuseLight::new(texture)),
+        ))
+    }
+
+    fn lights() -> Vec<Arc<dyn Geometry>> {
+        vec![
+            light(Point3::new(-10.0, 0.0, 0.0), 1.0, 1.0),
+            light(Point3::new(10.0, 0.0, 0.0), 1.0, 4.0),
+            light(Point3::new(0.0, 10.0, 0.0), 2.0, 1.0),
+        ]
+    }
+
+    #[test]
+    fn uniform_test() {
+        let sampler = LightSampler::new(lights(), LightSelection::Uniform);
+        let weights = sampler.weights(&Point3::new(0.0, 0.0, 0.0));
+        for w in weights {
+            test::assert_eq_float(w, 1.0 / 3.0, 1.0e-9);
+        }
+        assert!(sampler.power.is_empty());
+    }
+
+    #[test]
+    fn solid_angle_test() {
+        let sampler = LightSampler::new(lights(), LightSelection::SolidAngle);
+        // the bounding box of the third light is twice as big, the solid angle is about 4 times as big
+        let weights = sampler.weights(&Point3::new(0.0, 0.0, 0.0));
+        test::assert_eq_float(weights[0], weights[1], 1.0e-9);
+        test::assert_in_range(weights[2] / weights[0], 3.5..4.5);
+        // the nearer light is preferred
+        let weights = sampler.weights(&Point3::new(-8.0, 0.0, 0.0));
+        assert!(weights[0] > 0.9);
+    }
+
+    #[test]
+    fn power_test() {
+        let sampler = LightSampler::new(lights(), LightSelection::Power);
+        // power is proportional to the radiance and the surface area
+        test::assert_in_range(sampler.power[1] / sampler.power[0], 3.5..4.5);
+        test::assert_in_range(sampler.power[2] / sampler.power[0], 3.5..4.5);
+        let weights = sampler.weights(&Point3::new(-8.0, 0.0, 0.0));
+        test::assert_in_range(weights[0], 0.08..0.15);
+    }
+
+    #[test]
+    fn pdf_test() {
+        let position = Point3::new(0.0, 0.0, 0.0);
+        assert!(LightSampler::new(Vec::default(), LightSelection::Power)
+            .pdf(&position)
+            .is_none());
+        let sampler = LightSampler::new(lights(), LightSelection::Uniform);
+        let pdf = sampler.pdf(&position).unwrap();
+        for _ in 0..10 {
+            let direction = pdf.generate();
+            assert!(pdf.value(&direction) > 0.0);
+        }
+        assert_eq!(pdf.value(&Vector3::new(0.0, 0.0, 1.0)), 0.0);
+    }
+
+    #[test]
+    fn sample_test() {
+        let position = Point3::new(0.0, 0.0, 0.0);
+        assert!(LightSampler::new(Vec::default(), LightSelection::Uniform)
+            .sample(&position)
+            .is_none());
+        let sampler = LightSampler::new(lights(), LightSelection::SolidAngle);
+        let pdf = sampler.pdf(&position).unwrap();
+        for _ in 0..10 {
+            let sample = sampler.sample(&position).unwrap();
+            let ray = Ray::new_ray(position, sample.direction);
+            let hit_record = sample.light.hit(&ray, 0.001..FSize::MAX).unwrap();
+            // the lights don't overlap, the densities of all strategies are equal
+            test::assert_eq_float(sample.pdf, pdf.value(&sample.direction), 1.0e-9);
+            test::assert_eq_float(
+                sampler.emitter_pdf_value(&ray, hit_record.t),
+                sample.pdf,
+                1.0e-9,
+            );
+            assert_eq!(sampler.emitter_pdf_value(&ray, hit_record.t + 1.0), 0.0);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use crate::core::HitRecord;
+use crate::types::ColorRGB;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Hit, at which the light or the throughput of a path became NaN or infinite
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SampleSource {
+    pub material_id: usize,
+    pub object_id: usize,
+}
+
+impl SampleSource {
+    pub fn from_hit_record(hit_record: &HitRecord) -> SampleSource {
+        SampleSource {
+            material_id: hit_record.material.get_id(),
+            object_id: hit_record.object_id,
+        }
+    }
+}
+
+/// Number of the samples, which are NaN or infinite
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NonFiniteCount {
+    /// Samples with at least one NaN component
+    pub nan: usize,
+
+    /// Samples with infinite, but no NaN components
+    pub infinite: usize,
+}
+
+impl NonFiniteCount {
+    pub fn total(&self) -> usize {
+        self.nan + self.infinite
+    }
+}
+
+/// Counts the NaN and infinite samples of a scene per source, see [`crate::core::Configuration::diagnostics`].
+///
+/// The counters are shared by all threads, which render the scene.
+/// They are only locked, when a non-finite sample is added.
+#[derive(Debug, Default)]
+pub struct SampleDiagnostics {
+    counts: Mutex<HashMap<Option<SampleSource>, NonFiniteCount>>,
+}
+
+impl SampleDiagnostics {
+    pub fn new() -> SampleDiagnostics {
+        SampleDiagnostics::default()
+    }
+
+    pub fn is_finite(color: &ColorRGB) -> bool {
+        color.x.is_finite() && color.y.is_finite() && color.z.is_finite()
+    }
+
+    /// Counts the sample `color`, if it isn't finite.
+    /// The source is `None`, if the integrator didn't record where the sample became non-finite.
+    pub fn add(&self, color: &ColorRGB, source: Option<SampleSource>) {
+        if SampleDiagnostics::is_finite(color) {
+            return;
+        }
+        let mut counts = self.counts.lock().unwrap();
+        let count = counts.entry(source).or_default();
+        if color.x.is_nan() || color.y.is_nan() || color.z.is_nan() {
+            count.nan += 1;
+        } else {
+            count.infinite += 1;
+        }
+    }
+
+    /// Counts of all sources, the source with the most non-finite samples first
+    pub fn report(&self) -> Vec<(Option<SampleSource>, NonFiniteCount)> {
+        let mut report: Vec<_> = self
+            .counts
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(source, count)| (*source, *count))
+            .collect();
+        report.sort_by(|a, b| b.1.total().cmp(&a.1.total()).then(a.0.cmp(&b.0)));
+        report
+    }
+
+    pub fn reset(&self) {
+        self.counts.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod sample_diagnostics_test {
+    use super::*;
+    use crate::types::FSize;
+
+    #[test]
+    fn add_test() {
+        let diagnostics = SampleDiagnostics::new();
+        let source_1 = SampleSource {
+            material_id: 1,
+            object_id: 2,
+        };
+        let source_2 = SampleSource {
+            material_id: 3,
+            object_id: 4,
+        };
+        diagnostics.add(&ColorRGB::new(1.0, 2.0, 3.0), Some(source_1));
+        diagnostics.add(
+            &ColorRGB::new(FSize::NAN, FSize::INFINITY, 0.0),
+            Some(source_1),
+        );
+        diagnostics.add(&ColorRGB::new(0.0, FSize::INFINITY, 0.0), Some(source_2));
+        diagnostics.add(
+            &ColorRGB::new(0.0, FSize::NEG_INFINITY, 0.0),
+            Some(source_2),
+        );
+        diagnostics.add(&ColorRGB::new(FSize::NAN, 0.0, 0.0), None);
+        assert_eq!(
+            diagnostics.report(),
+            vec![
+                (
+                    Some(source_2),
+                    NonFiniteCount {
+                        nan: 0,
+                        infinite: 2
+                    }
+                ),
+                (
+                    None,
+                    NonFiniteCount {
+                        nan: 1,
+                        infinite: 0
+                    }
+                ),
+                (
+                    Some(source_1),
+                    NonFiniteCount {
+                        nan: 1,
+                        infinite: 0
+                    }
+                ),
+            ]
+        );
+        diagnostics.reset();
+        assert!(diagnostics.report().is_empty());
+    }
+}
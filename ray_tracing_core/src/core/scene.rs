@@ -1,8 +1,10 @@
-use crate::core::{Camera, Configuration};
+use crate::core::{
+    AovRecord, Camera, Configuration, LightSampler, LightSelection, SampleDiagnostics,
+};
 use crate::environment::Environment;
 use crate::geometry::Geometry;
+use crate::integrator::{Integrator, IntegratorType, PathTracer, RecursivePathTracer};
 use crate::math::Ray;
-use crate::probability_density_function::{GeometryPdf, MixturePdf, ProbabilityDensityFunction};
 use crate::types::{ColorRGB, FSize};
 use std::error::Error;
 use std::sync::Arc;
@@ -12,7 +14,16 @@ pub struct Scene {
     pub camera: Arc<Camera>,
     pub sky: Arc<dyn Environment>,
     pub world: Arc<dyn Geometry>,
-    pub light: Option<Arc<dyn Geometry>>,
+
+    /// Lights, which are sampled explicitly at scattering events.
+    /// The lights have to be part of the world, too.
+    pub lights: LightSampler,
+
+    /// NaN and infinite samples, which are counted if [`Configuration::diagnostics`] is set
+    pub diagnostics: Arc<SampleDiagnostics>,
+
+    /// Rendering algorithm and the type, from which it was created
+    integrator: (IntegratorType, Arc<dyn Integrator>),
 }
 
 impl Scene {
@@ -21,136 +32,124 @@ impl Scene {
         camera: Arc<Camera>,
         sky: Arc<dyn Environment>,
         world: Arc<dyn Geometry>,
-        light: Option<Arc<dyn Geometry>>,
+        lights: Vec<Arc<dyn Geometry>>,
     ) -> Scene {
+        let lights = LightSampler::new(lights, configuration.light_selection);
+        let integrator = (
+            configuration.integrator,
+            configuration.integrator.to_integrator(),
+        );
         Scene {
             configuration,
             camera,
             sky,
             world,
-            light,
+            lights,
+            diagnostics: Arc::new(SampleDiagnostics::new()),
+            integrator,
         }
     }
 
-    pub fn ray_trace_color(&self, u: FSize, v: FSize) -> ColorRGB {
-        let color = self.ray_trace_color_loop(u, v, self.light.clone());
-        if color.x.is_nan() || color.y.is_nan() || color.z.is_nan() {
-            ColorRGB::new(0.0, 0.0, 0.0)
+    /// Changes the rendering algorithm of the scene
+    pub fn set_integrator(&mut self, integrator: IntegratorType) {
+        self.configuration.integrator = integrator;
+        self.integrator = (integrator, integrator.to_integrator());
+    }
+
+    /// Traces `ray` with the integrator of the configuration.
+    /// The integrator is created once, by [`Scene::new`] or [`Scene::set_integrator`].
+    /// If [`Configuration::integrator`] was changed directly, the integrator is created for each ray.
+    fn trace(&self, ray: &Ray, aov_record: Option<&mut AovRecord>) -> ColorRGB {
+        if self.integrator.0 == self.configuration.integrator {
+            self.integrator.1.trace(self, ray, aov_record)
         } else {
+            self.configuration
+                .integrator
+                .to_integrator()
+                .trace(self, ray, aov_record)
+        }
+    }
+
+    pub fn ray_trace_color(&self, u: FSize, v: FSize) -> ColorRGB {
+        if self.configuration.clamp_direct.is_some()
+            || self.configuration.clamp_indirect.is_some()
+            || self.configuration.diagnostics
+        {
+            // the clamping and the diagnostics need the arbitrary output variables
+            return self.ray_trace_color_with_aovs(u, v).0;
+        }
+        let ray = self.camera.get(u, v);
+        let color = self.trace(&ray, None);
+        if SampleDiagnostics::is_finite(&color) {
             color
+        } else {
+            ColorRGB::new(0.0, 0.0, 0.0)
         }
     }
 
+    /// Traces a camera ray and returns the color and the arbitrary output variables of the ray
+    pub fn ray_trace_color_with_aovs(&self, u: FSize, v: FSize) -> (ColorRGB, AovRecord) {
+        let ray = self.camera.get(u, v);
+        let mut aov_record = AovRecord::empty();
+        let color = self.trace(&ray, Some(&mut aov_record));
+        if !SampleDiagnostics::is_finite(&color) {
+            if self.configuration.diagnostics {
+                self.diagnostics.add(&color, aov_record.non_finite_source);
+            }
+            aov_record.direct = ColorRGB::new(0.0, 0.0, 0.0);
+            aov_record.indirect = ColorRGB::new(0.0, 0.0, 0.0);
+            aov_record.emitted = ColorRGB::new(0.0, 0.0, 0.0);
+            return (ColorRGB::new(0.0, 0.0, 0.0), aov_record);
+        }
+        // the light, which is emitted by the first hit, is visible and isn't clamped
+        let direct = aov_record.emitted
+            + Scene::clamp_radiance(
+                aov_record.direct - aov_record.emitted,
+                self.configuration.clamp_direct,
+            );
+        let indirect =
+            Scene::clamp_radiance(aov_record.indirect, self.configuration.clamp_indirect);
+        let color = color + (direct - aov_record.direct) + (indirect - aov_record.indirect);
+        aov_record.direct = direct;
+        aov_record.indirect = indirect;
+        (color, aov_record)
+    }
+
+    /// Traces a camera ray with the iterative path tracer.
+    /// If `light_shape` is set, it is sampled instead of the lights of the scene.
+    #[deprecated(note = "use `ray_trace_color` with `IntegratorType::PathTracer`")]
     pub fn ray_trace_color_loop(
         &self,
         u: FSize,
         v: FSize,
         light_shape: Option<Arc<dyn Geometry>>,
     ) -> ColorRGB {
-        let mut ray = self.camera.get(u, v);
-        let mut color = ColorRGB::new(0.0, 0.0, 0.0);
-        let mut attenuation = ColorRGB::new(1.0, 1.0, 1.0);
-        for _ in 0..self.configuration.maximum_depth {
-            if let Some(hit_record) = self.world.hit(&ray, 0.001..FSize::MAX) {
-                let material = hit_record.material.clone();
-                let emitted = material.emitted(&ray, &hit_record);
-                color = color + attenuation * emitted;
-                if let Some(scatter_record) = hit_record.scatter(&ray) {
-                    if scatter_record.is_specular {
-                        attenuation = attenuation * scatter_record.attenuation;
-                        ray = scatter_record.ray;
-                    } else {
-                        let pdf: Option<Arc<dyn ProbabilityDensityFunction>> =
-                            match scatter_record.pdf {
-                                Some(pdf) => match light_shape {
-                                    Some(ref light_shape) => Some(Arc::new(MixturePdf::new(
-                                        pdf.clone(),
-                                        Arc::new(GeometryPdf::new(
-                                            &hit_record.position,
-                                            light_shape.clone(),
-                                        )),
-                                    ))),
-                                    None => Some(pdf.clone()),
-                                },
-                                None => None,
-                            };
-                        match pdf {
-                            Some(pdf) => {
-                                let scattered = Ray::new_ray_with_attributes(
-                                    hit_record.position,
-                                    pdf.generate(),
-                                    &ray,
-                                );
-                                let s_pdf = scatter_record.material.scattering_pdf(
-                                    &ray,
-                                    &hit_record,
-                                    &scattered,
-                                );
-                                let pdf_value = pdf.value(&scattered.direction);
-                                attenuation =
-                                    attenuation * scatter_record.attenuation * s_pdf / pdf_value;
-                                ray = scattered;
-                            }
-                            None => {
-                                let s_pdf = scatter_record.material.scattering_pdf(
-                                    &ray,
-                                    &hit_record,
-                                    &scatter_record.ray,
-                                );
-                                attenuation = attenuation * scatter_record.attenuation * s_pdf;
-                                ray = scatter_record.ray;
-                            }
-                        }
-                    }
-                    //if attenuation.x + attenuation.y + attenuation.z < 0.00001 {
-                    //    break;
-                    //}
-                } else {
-                    break;
-                }
-            } else {
-                color = color + attenuation * self.sky.get(&ray);
-                break;
-            }
-        }
-        color
+        let mut scene = self.clone_with_camera(self.camera.clone());
+        // a single light doesn't have to be selected
+        scene.lights =
+            LightSampler::new(light_shape.into_iter().collect(), LightSelection::Uniform);
+        PathTracer::new().trace(&scene, &self.camera.get(u, v), None)
     }
 
+    /// Traces a camera ray with the recursive path tracer
+    #[deprecated(note = "use `ray_trace_color` with `IntegratorType::RecursivePathTracer`")]
     pub fn ray_trace_color_recursive(&self, u: FSize, v: FSize) -> ColorRGB {
-        let ray = self.camera.get(u, v);
-        self.get_ray_trace_color(&ray, 0)
-    }
-
-    fn get_ray_trace_color(&self, ray: &Ray, depth: usize) -> ColorRGB {
-        match self.world.hit(ray, 0.001..FSize::MAX) {
-            Some(hit_record) => {
-                let material = hit_record.material.clone();
-                let mut emitted = material.emitted(&ray, &hit_record);
-                if depth < self.configuration.maximum_depth {
-                    match hit_record.scatter(ray) {
-                        Some(scatter_record) => {
-                            if scatter_record.is_specular {
-                                emitted = emitted
-                                    + scatter_record.attenuation
-                                        * self.get_ray_trace_color(&scatter_record.ray, depth + 1);
-                            } else {
-                                let s_pdf = scatter_record.material.scattering_pdf(
-                                    &ray,
-                                    &hit_record,
-                                    &scatter_record.ray,
-                                );
-                                emitted = emitted
-                                    + scatter_record.attenuation
-                                        * self.get_ray_trace_color(&scatter_record.ray, depth + 1)
-                                        * s_pdf;
-                            }
-                        }
-                        _ => (),
-                    }
+        RecursivePathTracer::new().trace(self, &self.camera.get(u, v), None)
+    }
+
+    /// Scales `color` down, so that its maximum component doesn't exceed `maximum`.
+    /// Clamping loses energy, but it keeps the hue of the light.
+    fn clamp_radiance(color: ColorRGB, maximum: Option<FSize>) -> ColorRGB {
+        match maximum {
+            Some(maximum) => {
+                let component = color.x.max(color.y).max(color.z);
+                if component > maximum {
+                    color * (maximum / component)
+                } else {
+                    color
                 }
-                emitted
             }
-            None => self.sky.get(ray),
+            None => color,
         }
     }
 
@@ -163,22 +162,48 @@ impl Scene {
     pub fn from_scene_and_aspect(&self, aspect: FSize) -> Result<Scene, Box<dyn Error>> {
         let mut c = (*self.camera).clone();
         c.change_aspect(aspect);
-        Ok(Scene::new(
-            self.configuration.clone(),
-            Arc::new(c),
-            self.sky.clone(),
-            self.world.clone(),
-            self.light.clone(),
-        ))
+        Ok(self.clone_with_camera(Arc::new(c)))
+    }
+
+    fn clone_with_camera(&self, camera: Arc<Camera>) -> Scene {
+        Scene {
+            configuration: self.configuration.clone(),
+            camera,
+            sky: self.sky.clone(),
+            world: self.world.clone(),
+            lights: self.lights.clone(),
+            diagnostics: self.diagnostics.clone(),
+            integrator: self.integrator.clone(),
+        }
     }
 }
 
 #[cfg(test)]
 mod scene_test {
     use super::*;
+    use crate::core::HitRecord;
+    use crate::core::{NonFiniteCount, SampleSource};
+    use crate::display::DisplayPipeline;
+    use crate::environment::Sky;
+    use crate::geometry::collection::GeometryList;
+    use crate::geometry::shape::Sphere;
+    use crate::geometry::volume::ConstantMedium;
+    use crate::geometry::Visitor;
+    use crate::integrator::{IntegratorType, RussianRoulette};
+    use crate::material::{
+        DiffuseLight, Isotropic, Lambertian, Material, MaterialBlend, Metal, NoMaterial,
+    };
+    use crate::math::{Ray, AABB};
     use crate::random;
-    use crate::test::TestSceneSimple;
+    use crate::sampler;
+    use crate::sampler::Sampler;
+    use crate::test;
+    use crate::test::{SampleStatistics, TestSceneSimple};
+    use crate::texture::ConstantTexture;
+    use crate::types::{ColorRGBA, Point3, Vector3};
     use std::ops::Range;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
 
     fn assert_in_range(
         pixel_data: &Vec<u8>,
@@ -204,12 +229,14 @@ mod scene_test {
         let cy = 10;
         let samples = 1000;
         let scene = TestSceneSimple::new().scene;
+        let display = DisplayPipeline::default();
 
         let mut pixel_data: Vec<u8> = Vec::with_capacity(cx * cy * 4);
         pixel_data.resize(cx * cy * 4, 0);
 
         for x in 0..cx {
             for y in 0..cy {
+                random::set_seed(Some(random::derive_seed(1, x, y, 0)));
                 let mut c = ColorRGB::new(0.0, 0.0, 0.0);
                 for _ in 0..samples {
                     let u = (x as FSize + random::generate_size()) / cx as FSize;
@@ -219,15 +246,521 @@ mod scene_test {
                 c = c / samples as FSize;
 
                 let i = (y * cx) + x;
-                pixel_data[i * 4] = (c[0].sqrt() * 255.0).round() as u8;
-                pixel_data[i * 4 + 1] = (c[1].sqrt() * 255.0).round() as u8;
-                pixel_data[i * 4 + 2] = (c[2].sqrt() * 255.0).round() as u8;
-                pixel_data[i * 4 + 3] = 255;
+                pixel_data[i * 4..i * 4 + 4].copy_from_slice(&display.to_rgba8(&c, x, y));
             }
         }
+        random::set_seed(None);
 
         assert_in_range(&pixel_data, cx, cy, 3, 1, 2, 245..256);
-        assert_in_range(&pixel_data, cx, cy, 10, 3, 0, 110..150);
-        assert_in_range(&pixel_data, cx, cy, 17, 8, 1, 60..75);
+        assert_in_range(&pixel_data, cx, cy, 10, 3, 0, 118..160);
+        assert_in_range(&pixel_data, cx, cy, 17, 8, 1, 66..84);
+    }
+
+    #[test]
+    fn ray_trace_color_with_aovs_test() {
+        let scene = TestSceneSimple::new().scene;
+        let (color, sphere) = scene.ray_trace_color_with_aovs(0.5, 0.5);
+        test::assert_eq_vector3(&sphere.albedo, &ColorRGB::new(0.5, 0.1, 0.1), 0.000001);
+        test::assert_eq_vector3(&sphere.normal, &Vector3::new(0.0, 0.0, 1.0), 0.000001);
+        test::assert_eq_vector3(&sphere.position, &Point3::new(0.0, 0.0, -0.5), 0.000001);
+        test::assert_eq_float(sphere.depth, 0.5, 0.000001);
+        test::assert_eq_vector3(&(sphere.direct + sphere.indirect), &color, 0.000001);
+        assert_ne!(sphere.object_id, 0);
+        assert_ne!(sphere.material_id, 0);
+
+        let (_, ground) = scene.ray_trace_color_with_aovs(0.5, 0.1);
+        test::assert_eq_vector3(&ground.albedo, &ColorRGB::new(0.1, 0.1, 0.1), 0.000001);
+        assert_ne!(ground.object_id, 0);
+        assert_ne!(ground.object_id, sphere.object_id);
+        assert_ne!(ground.material_id, sphere.material_id);
+
+        let (color, sky) = scene.ray_trace_color_with_aovs(0.5, 0.99);
+        test::assert_eq_vector3(&sky.albedo, &color, 0.000001);
+        test::assert_eq_vector3(&sky.direct, &color, 0.000001);
+        assert_eq!((sky.depth, sky.object_id, sky.material_id), (0.0, 0, 0));
+    }
+
+    #[test]
+    fn render_seeded_scene_test() {
+        let cx = 8;
+        let cy = 4;
+        let samples = 10;
+        let scene = TestSceneSimple::new().scene;
+        let render = |seed: u64| {
+            let mut colors = Vec::default();
+            for y in 0..cy {
+                for x in 0..cx {
+                    random::set_seed(Some(random::derive_seed(seed, x, y, 0)));
+                    let mut c = ColorRGB::new(0.0, 0.0, 0.0);
+                    for _ in 0..samples {
+                        let u = (x as FSize + random::generate_size()) / cx as FSize;
+                        let v = 1.0 - (y as FSize + random::generate_size()) / cy as FSize;
+                        c = c + scene.ray_trace_color(u, v);
+                    }
+                    colors.push(c / samples as FSize);
+                }
+            }
+            random::set_seed(None);
+            colors
+        };
+        let image_1 = render(1);
+        let image_2 = render(1);
+        let image_3 = render(2);
+        assert!(image_1 == image_2);
+        assert!(image_1 != image_3);
+    }
+
+    /// Scene with a single object in a uniform white environment.
+    /// An object, which doesn't absorb light, is invisible in a white furnace.
+    fn furnace_scene(world: Arc<dyn Geometry>) -> Scene {
+        let camera = Camera::new(
+            Vector3::new(-1.0, -1.0, -1.0),
+            Vector3::new(2.0, 0.0, 0.0),
+            Vector3::new(0.0, 2.0, 0.0),
+            Vector3::new(0.0, 0.0, 0.0),
+            0.0,
+            0.0..0.0,
+        );
+        let white = ColorRGB::new(1.0, 1.0, 1.0);
+        Scene::new(
+            Configuration::default(),
+            Arc::new(camera),
+            Arc::new(Sky::new(white, white)),
+            world,
+            Vec::default(),
+        )
+    }
+
+    fn furnace_statistics(scene: &Scene, samples: usize) -> SampleStatistics {
+        random::set_seed(Some(1));
+        let mut statistics = SampleStatistics::new();
+        for _ in 0..samples {
+            let (u, v) = (random::generate_size(), random::generate_size());
+            statistics.add(scene.ray_trace_color(u, v));
+        }
+        random::set_seed(None);
+        statistics
+    }
+
+    /// Sphere, which covers the whole view of the furnace camera
+    fn furnace_sphere(material: Arc<dyn Material>) -> Arc<dyn Geometry> {
+        Arc::new(Sphere::new(Point3::new(0.0, 0.0, -3.0), 2.9, material))
+    }
+
+    #[test]
+    fn white_furnace_lambertian_test() {
+        for albedo in [1.0, 0.5] {
+            let texture = Arc::new(ConstantTexture::new(ColorRGBA::new(
+                albedo, albedo, albedo, 1.0,
+            )));
+            let scene = furnace_scene(furnace_sphere(Arc::new(Lambertian::new(texture))));
+            let statistics = furnace_statistics(&scene, 2000);
+            // the light, which is reflected by a convex object, doesn't hit the object again
+            test::assert_in_confidence_interval(
+                &statistics,
+                ColorRGB::new(albedo, albedo, albedo),
+                4.0,
+                1.0e-9,
+            );
+        }
+    }
+
+    #[test]
+    fn white_furnace_isotropic_test() {
+        let texture = Arc::new(ConstantTexture::new(ColorRGBA::new(1.0, 1.0, 1.0, 1.0)));
+        let medium = Arc::new(ConstantMedium::new(
+            2.0,
+            furnace_sphere(Arc::new(NoMaterial::new())),
+            Arc::new(Isotropic::new(texture)),
+        ));
+        let scene = furnace_scene(medium);
+        let statistics = furnace_statistics(&scene, 2000);
+        // the paths, which are terminated at the maximum depth, are missing
+        test::assert_in_confidence_interval(&statistics, ColorRGB::new(1.0, 1.0, 1.0), 4.0, 1.0e-3);
+    }
+
+    /// Geometry, which counts the intersection tests with the wrapped geometry
+    struct CountingGeometry {
+        geometry: Arc<dyn Geometry>,
+        hits: AtomicUsize,
+    }
+
+    impl Geometry for CountingGeometry {
+        fn get_id(&self) -> usize {
+            self.geometry.get_id()
+        }
+
+        fn bounding_box(&self, t_range: Range<FSize>) -> Option<AABB> {
+            self.geometry.bounding_box(t_range)
+        }
+
+        fn hit(&self, ray: &Ray, t_range: Range<FSize>) -> Option<HitRecord> {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            self.geometry.hit(ray, t_range)
+        }
+
+        fn accept(&self, visitor: &mut dyn Visitor) -> Result<(), Box<dyn Error>> {
+            self.geometry.accept(visitor)
+        }
+    }
+
+    #[test]
+    fn russian_roulette_test() {
+        let texture = Arc::new(ConstantTexture::new(ColorRGBA::new(0.8, 0.8, 0.8, 1.0)));
+        let mut results = Vec::default();
+        for russian_roulette in [RussianRoulette::Disabled, RussianRoulette::Throughput] {
+            let boundary = Arc::new(CountingGeometry {
+                geometry: furnace_sphere(Arc::new(Lambertian::new(texture.clone()))),
+                hits: AtomicUsize::new(0),
+            });
+            let medium: Arc<dyn Geometry> = Arc::new(ConstantMedium::new(
+                2.0,
+                boundary.clone(),
+                Arc::new(Isotropic::new(texture.clone())),
+            ));
+            let mut scene = furnace_scene(medium);
+            scene.configuration.russian_roulette = russian_roulette;
+            let statistics = furnace_statistics(&scene, 20000);
+            results.push((statistics, boundary.hits.load(Ordering::Relaxed)));
+        }
+        let (disabled, disabled_tests) = &results[0];
+        let (throughput, throughput_tests) = &results[1];
+
+        // Russian roulette doesn't change the expected value, but it terminates the dark paths
+        let standard_error = glm::sqrt(
+            disabled.standard_error() * disabled.standard_error()
+                + throughput.standard_error() * throughput.standard_error(),
+        );
+        let difference = glm::abs(disabled.mean - throughput.mean);
+        for c in 0..3 {
+            assert!(
+                difference[c] <= 4.0 * standard_error[c],
+                "{} > 4 * {}",
+                difference[c],
+                standard_error[c]
+            );
+        }
+        assert!(throughput_tests * 4 < disabled_tests * 3);
+    }
+
+    #[test]
+    fn clamp_direct_test() {
+        // the emitter, which is seen by the camera, isn't clamped
+        let texture = Arc::new(ConstantTexture::new(ColorRGBA::new(10.0, 5.0, 1.0, 1.0)));
+        let mut scene = furnace_scene(furnace_sphere(Arc::new(DiffuseLight::new(texture))));
+        scene.configuration.clamp_direct = Some(2.0);
+        let (color, aov_record) = scene.ray_trace_color_with_aovs(0.5, 0.5);
+        assert_eq!(color, ColorRGB::new(10.0, 5.0, 1.0));
+        assert_eq!(aov_record.emitted, color);
+
+        // the light, which reaches the first hit, is clamped
+        let texture = Arc::new(ConstantTexture::new(ColorRGBA::new(1.0, 0.5, 0.1, 1.0)));
+        let mut scene = furnace_scene(furnace_sphere(Arc::new(Lambertian::new(texture))));
+        test::assert_eq_vector3(
+            &scene.ray_trace_color(0.5, 0.5),
+            &ColorRGB::new(1.0, 0.5, 0.1),
+            1.0e-9,
+        );
+        scene.configuration.clamp_direct = Some(0.5);
+        let (color, aov_record) = scene.ray_trace_color_with_aovs(0.5, 0.5);
+        test::assert_eq_vector3(&color, &ColorRGB::new(0.5, 0.25, 0.05), 1.0e-9);
+        test::assert_eq_vector3(&aov_record.direct, &color, 1.0e-9);
+    }
+
+    #[test]
+    fn clamp_indirect_test() {
+        for integrator in [
+            IntegratorType::PathTracer,
+            IntegratorType::RecursivePathTracer,
+        ] {
+            let texture = Arc::new(ConstantTexture::new(ColorRGBA::new(0.8, 0.8, 0.8, 1.0)));
+            let medium: Arc<dyn Geometry> = Arc::new(ConstantMedium::new(
+                2.0,
+                furnace_sphere(Arc::new(Lambertian::new(texture.clone()))),
+                Arc::new(Isotropic::new(texture)),
+            ));
+            let mut scene = furnace_scene(medium);
+            scene.configuration.integrator = integrator;
+            random::with_seed(Some(1), || {
+                let mut indirect = ColorRGB::new(0.0, 0.0, 0.0);
+                for _ in 0..100 {
+                    let (color, aov_record) = scene.ray_trace_color_with_aovs(0.5, 0.5);
+                    test::assert_eq_vector3(
+                        &color,
+                        &(aov_record.direct + aov_record.indirect),
+                        1.0e-9,
+                    );
+                    indirect = indirect + aov_record.indirect;
+                }
+                assert!(indirect.x > 0.0);
+            });
+            scene.configuration.clamp_indirect = Some(0.0);
+            random::with_seed(Some(1), || {
+                for _ in 0..100 {
+                    let (color, aov_record) = scene.ray_trace_color_with_aovs(0.5, 0.5);
+                    assert_eq!(aov_record.indirect, ColorRGB::new(0.0, 0.0, 0.0));
+                    test::assert_eq_vector3(&color, &aov_record.direct, 1.0e-9);
+                }
+            });
+        }
+    }
+
+    #[test]
+    fn diagnostics_test() {
+        for integrator in [
+            IntegratorType::PathTracer,
+            IntegratorType::RecursivePathTracer,
+        ] {
+            for (emit, nan, infinite) in [(FSize::NAN, 10, 0), (FSize::INFINITY, 0, 10)] {
+                let texture = Arc::new(ConstantTexture::new(ColorRGBA::new(emit, 1.0, 1.0, 1.0)));
+                let light: Arc<dyn Material> = Arc::new(DiffuseLight::new(texture));
+                let sphere = furnace_sphere(light.clone());
+                let mut scene = furnace_scene(sphere.clone());
+                scene.configuration.integrator = integrator;
+                for _ in 0..10 {
+                    assert_eq!(
+                        scene.ray_trace_color(0.5, 0.5),
+                        ColorRGB::new(0.0, 0.0, 0.0)
+                    );
+                }
+                assert!(scene.diagnostics.report().is_empty());
+
+                scene.configuration.diagnostics = true;
+                for _ in 0..10 {
+                    assert_eq!(
+                        scene.ray_trace_color(0.5, 0.5),
+                        ColorRGB::new(0.0, 0.0, 0.0)
+                    );
+                }
+                let source = SampleSource {
+                    material_id: light.get_id(),
+                    object_id: sphere.get_id(),
+                };
+                assert_eq!(
+                    scene.diagnostics.report(),
+                    vec![(Some(source), NonFiniteCount { nan, infinite })]
+                );
+            }
+        }
+    }
+
+    /// Sampler, which records the dimensions, that are read
+    struct RecordingSampler {
+        dimensions: Mutex<Vec<usize>>,
+    }
+
+    impl Sampler for RecordingSampler {
+        fn sample(&self, seed: u64, index: usize, dimension: usize) -> FSize {
+            self.dimensions.lock().unwrap().push(dimension);
+            sampler::hash_to_size(sampler::hash(seed, &[index as u64, dimension as u64]))
+        }
+    }
+
+    #[test]
+    fn sampler_dimensions_test() {
+        let constant = |c: FSize| Arc::new(ConstantTexture::new(ColorRGBA::new(c, c, c, 1.0)));
+        let fuzzy_metal: Arc<dyn Material> = Arc::new(MaterialBlend::new(vec![
+            (0.5, Arc::new(Metal::new(0.5, constant(0.9)))),
+            (0.5, Arc::new(Lambertian::new(constant(0.8)))),
+        ]));
+        let light: Arc<dyn Geometry> = Arc::new(Sphere::new(
+            Point3::new(0.0, 2.0, -3.0),
+            0.5,
+            Arc::new(DiffuseLight::new(constant(4.0))),
+        ));
+        let fog: Arc<dyn Geometry> = Arc::new(ConstantMedium::new(
+            1.0,
+            Arc::new(Sphere::new(
+                Point3::new(0.0, 0.0, -3.0),
+                1.0,
+                Arc::new(Lambertian::new(constant(1.0))),
+            )),
+            Arc::new(Isotropic::new(constant(0.9))),
+        ));
+        let ground: Arc<dyn Geometry> = Arc::new(Sphere::new(
+            Point3::new(0.0, -101.0, -3.0),
+            100.0,
+            fuzzy_metal,
+        ));
+        let mut configuration = Configuration::default();
+        configuration.next_event_estimation = true;
+        configuration.russian_roulette = RussianRoulette::Throughput;
+        configuration.russian_roulette_minimum_depth = 0;
+        let white = ColorRGB::new(1.0, 1.0, 1.0);
+        let scene = Scene::new(
+            configuration,
+            furnace_scene(ground.clone()).camera,
+            Arc::new(Sky::new(white, white)),
+            Arc::new(GeometryList::new(&vec![ground, fog, light.clone()])),
+            vec![light],
+        );
+
+        let sampler = Arc::new(RecordingSampler {
+            dimensions: Mutex::new(Vec::default()),
+        });
+        random::set_sampler(Some(sampler.clone()), 1);
+        let mut maximum_dimension = 0;
+        for index in 0..2000 {
+            sampler.dimensions.lock().unwrap().clear();
+            random::start_sample(index);
+            let (u, v) = (random::generate_size(), random::generate_size());
+            scene.ray_trace_color(u, v);
+            let mut dimensions = sampler.dimensions.lock().unwrap().clone();
+            dimensions.sort();
+            let count = dimensions.len();
+            dimensions.dedup();
+            // no dimension of a path is read twice
+            assert_eq!(dimensions.len(), count, "{:?}", dimensions);
+            maximum_dimension = usize::max(maximum_dimension, *dimensions.last().unwrap());
+        }
+        random::set_sampler(None, 0);
+        assert!(maximum_dimension > sampler::bounce_dimension(3, 0));
+    }
+
+    /// Diffuse sphere in a black environment, which is lit by 2 spherical lights of different power.
+    /// The first light is hidden by a black sphere, if `occluded` is set.
+    fn two_lights_scene(
+        sample_lights: bool,
+        light_selection: LightSelection,
+        occluded: bool,
+    ) -> Scene {
+        let constant = |c: FSize| Arc::new(ConstantTexture::new(ColorRGBA::new(c, c, c, 1.0)));
+        let light_0: Arc<dyn Geometry> = Arc::new(Sphere::new(
+            Point3::new(1.5, 0.0, -1.5),
+            0.2,
+            Arc::new(DiffuseLight::new(constant(4.0))),
+        ));
+        let light_1: Arc<dyn Geometry> = Arc::new(Sphere::new(
+            Point3::new(-1.5, 0.5, -1.5),
+            0.3,
+            Arc::new(DiffuseLight::new(constant(1.0))),
+        ));
+        let sphere: Arc<dyn Geometry> = Arc::new(Sphere::new(
+            Point3::new(0.0, 0.0, -3.0),
+            1.0,
+            Arc::new(Lambertian::new(constant(0.5))),
+        ));
+        let mut objects = vec![sphere, light_0.clone(), light_1.clone()];
+        if occluded {
+            objects.push(Arc::new(Sphere::new(
+                Point3::new(0.75, 0.0, -1.75),
+                0.15,
+                Arc::new(Lambertian::new(constant(0.0))),
+            )));
+        }
+        let camera = Camera::new(
+            Vector3::new(-1.0, -1.0, -1.0),
+            Vector3::new(2.0, 0.0, 0.0),
+            Vector3::new(0.0, 2.0, 0.0),
+            Vector3::new(0.0, 0.0, 0.0),
+            0.0,
+            0.0..0.0,
+        );
+        let black = ColorRGB::new(0.0, 0.0, 0.0);
+        let mut configuration = Configuration::default();
+        configuration.light_selection = light_selection;
+        Scene::new(
+            configuration,
+            Arc::new(camera),
+            Arc::new(Sky::new(black, black)),
+            Arc::new(GeometryList::new(&objects)),
+            if sample_lights {
+                vec![light_0, light_1]
+            } else {
+                Vec::default()
+            },
+        )
+    }
+
+    fn center_statistics(scene: &Scene, samples: usize) -> SampleStatistics {
+        random::with_seed(Some(1), || {
+            let mut statistics = SampleStatistics::new();
+            for _ in 0..samples {
+                statistics.add(scene.ray_trace_color(0.5, 0.5));
+            }
+            statistics
+        })
+    }
+
+    /// Sampling the lights doesn't change the expected value, but it reduces the variance
+    fn assert_light_sampling(bsdf: &SampleStatistics, sampled: &SampleStatistics, name: &str) {
+        let standard_error = glm::sqrt(
+            bsdf.standard_error() * bsdf.standard_error()
+                + sampled.standard_error() * sampled.standard_error(),
+        );
+        let difference = glm::abs(bsdf.mean - sampled.mean);
+        for c in 0..3 {
+            assert!(
+                difference[c] <= 4.0 * standard_error[c],
+                "{}: {} > 4 * {}",
+                name,
+                difference[c],
+                standard_error[c]
+            );
+            assert!(sampled.variance()[c] < bsdf.variance()[c] * 0.5);
+        }
+    }
+
+    #[test]
+    fn multiple_lights_test() {
+        let scene = two_lights_scene(false, LightSelection::Uniform, false);
+        let bsdf = center_statistics(&scene, 20000);
+        for light_selection in [
+            LightSelection::Uniform,
+            LightSelection::SolidAngle,
+            LightSelection::Power,
+        ] {
+            let scene = two_lights_scene(true, light_selection, false);
+            let mis = center_statistics(&scene, 20000);
+            assert_light_sampling(&bsdf, &mis, &format!("{:?}", light_selection));
+        }
+    }
+
+    #[test]
+    fn set_integrator_test() {
+        let texture = Arc::new(ConstantTexture::new(ColorRGBA::new(0.5, 0.5, 0.5, 1.0)));
+        let mut scene = furnace_scene(furnace_sphere(Arc::new(Lambertian::new(texture))));
+        let trace = |scene: &Scene| random::with_seed(Some(1), || scene.ray_trace_color(0.5, 0.5));
+        #[allow(deprecated)]
+        let recursive = random::with_seed(Some(1), || scene.ray_trace_color_recursive(0.5, 0.5));
+        #[allow(deprecated)]
+        let path_tracer = random::with_seed(Some(1), || scene.ray_trace_color_loop(0.5, 0.5, None));
+        assert_eq!(trace(&scene), path_tracer);
+
+        scene.set_integrator(IntegratorType::RecursivePathTracer);
+        assert_eq!(
+            scene.configuration.integrator,
+            IntegratorType::RecursivePathTracer
+        );
+        assert_eq!(trace(&scene), recursive);
+        let scene = scene.from_scene_and_aspect(2.0).unwrap();
+        assert_eq!(trace(&scene), recursive);
+    }
+
+    #[test]
+    fn direct_lighting_test() {
+        // the light, which is reflected by the convex sphere, doesn't hit the sphere again,
+        // all light is direct light
+        let scene = two_lights_scene(false, LightSelection::Uniform, true);
+        let bsdf = center_statistics(&scene, 20000);
+        let mut scene = two_lights_scene(true, LightSelection::Uniform, true);
+        scene.configuration.integrator = IntegratorType::DirectLighting;
+        let direct = center_statistics(&scene, 20000);
+        assert_light_sampling(&bsdf, &direct, "direct lighting");
+    }
+
+    #[test]
+    fn next_event_estimation_test() {
+        for occluded in [false, true] {
+            let scene = two_lights_scene(false, LightSelection::Uniform, occluded);
+            let bsdf = center_statistics(&scene, 20000);
+            for light_selection in [LightSelection::Uniform, LightSelection::Power] {
+                let mut scene = two_lights_scene(true, light_selection, occluded);
+                scene.configuration.next_event_estimation = true;
+                let nee = center_statistics(&scene, 20000);
+                let name = format!("{:?}, occluded {}", light_selection, occluded);
+                assert_light_sampling(&bsdf, &nee, &name);
+            }
+        }
     }
 }
@@ -0,0 +1,194 @@
+use crate::sampler;
+use crate::types::{ColorRGB, FSize};
+
+/// Operator, which maps the linear radiance to the displayable range [0.0, 1.0]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ToneMapping {
+    /// Values above 1.0 are clipped
+    #[default]
+    Clamp,
+
+    /// x / (1 + x)
+    Reinhard,
+
+    /// ACES filmic curve (Krzysztof Narkowicz, "ACES Filmic Tone Mapping Curve")
+    AcesFilmic,
+
+    /// Filmic curve of John Hable ("Uncharted 2 Tone Mapping")
+    Hable,
+}
+
+impl ToneMapping {
+    /// Maps a linear value to the range [0.0, 1.0]
+    pub fn map(&self, x: FSize) -> FSize {
+        let y = match self {
+            ToneMapping::Clamp => x,
+            ToneMapping::Reinhard => x / (1.0 + x),
+            ToneMapping::AcesFilmic => (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14),
+            ToneMapping::Hable => {
+                let white = 11.2;
+                ToneMapping::hable_curve(2.0 * x) / ToneMapping::hable_curve(white)
+            }
+        };
+        FSize::clamp(y, 0.0, 1.0)
+    }
+
+    fn hable_curve(x: FSize) -> FSize {
+        let (a, b, c, d, e, f) = (0.15, 0.50, 0.10, 0.20, 0.02, 0.30);
+        ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f
+    }
+}
+
+/// sRGB transfer function, encodes a linear value in the range [0.0, 1.0]
+pub fn linear_to_srgb(x: FSize) -> FSize {
+    if x <= 0.0031308 {
+        12.92 * x
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Inverse sRGB transfer function, decodes an sRGB value in the range [0.0, 1.0]
+pub fn srgb_to_linear(x: FSize) -> FSize {
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Conversion of the linear radiance of the pixels to 8 bit sRGB pixel data
+///
+/// The radiance is scaled by the exposure, mapped by the tone mapping operator and encoded with
+/// the sRGB transfer function. Optionally a deterministic noise of +/- 0.5 quantization steps is added
+/// before the quantization, to avoid banding in smooth gradients.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DisplayPipeline {
+    /// Exposure in stops, the radiance is scaled by 2^exposure
+    pub exposure: FSize,
+    pub tone_mapping: ToneMapping,
+    pub dithering: bool,
+}
+
+impl DisplayPipeline {
+    pub fn new(exposure: FSize, tone_mapping: ToneMapping, dithering: bool) -> DisplayPipeline {
+        DisplayPipeline {
+            exposure,
+            tone_mapping,
+            dithering,
+        }
+    }
+
+    /// Maps a linear color to a sRGB encoded color in the range [0.0, 1.0]
+    pub fn map_color(&self, color: &ColorRGB) -> ColorRGB {
+        let scale = FSize::powf(2.0, self.exposure);
+        let mut display_color = ColorRGB::new(0.0, 0.0, 0.0);
+        for i in 0..3 {
+            let x = if color[i].is_finite() {
+                color[i] * scale
+            } else {
+                0.0
+            };
+            display_color[i] = linear_to_srgb(self.tone_mapping.map(x));
+        }
+        display_color
+    }
+
+    /// Converts the linear color of the pixel (`x`, `y`) to RGBA8
+    pub fn to_rgba8(&self, color: &ColorRGB, x: usize, y: usize) -> [u8; 4] {
+        let display_color = self.map_color(color);
+        let mut rgba = [255; 4];
+        for i in 0..3 {
+            let noise = if self.dithering {
+                sampler::hash_to_size(sampler::hash(0, &[x as u64, y as u64, i as u64])) - 0.5
+            } else {
+                0.0
+            };
+            rgba[i] = FSize::clamp((display_color[i] * 255.0 + noise).round(), 0.0, 255.0) as u8;
+        }
+        rgba
+    }
+
+    /// Converts the linear colors of an image to RGBA8 pixel data
+    pub fn to_pixel_data(&self, cx: usize, pixel_color: &[ColorRGB]) -> Vec<u8> {
+        let mut pixel_data = Vec::with_capacity(pixel_color.len() * 4);
+        for (i, color) in pixel_color.iter().enumerate() {
+            pixel_data.extend_from_slice(&self.to_rgba8(color, i % cx, i / cx));
+        }
+        pixel_data
+    }
+}
+
+#[cfg(test)]
+mod display_test {
+    use super::*;
+    use crate::test;
+
+    #[test]
+    fn srgb_test() {
+        test::assert_eq_float(linear_to_srgb(0.0), 0.0, 0.000001);
+        test::assert_eq_float(linear_to_srgb(1.0), 1.0, 0.000001);
+        test::assert_eq_float(linear_to_srgb(0.5), 0.735357, 0.00001);
+        for i in 0..=10 {
+            let x = i as FSize / 10.0;
+            test::assert_eq_float(srgb_to_linear(linear_to_srgb(x)), x, 0.000001);
+        }
+    }
+
+    #[test]
+    fn tone_mapping_test() {
+        for tone_mapping in [
+            ToneMapping::Clamp,
+            ToneMapping::Reinhard,
+            ToneMapping::AcesFilmic,
+            ToneMapping::Hable,
+        ] {
+            test::assert_eq_float(tone_mapping.map(0.0), 0.0, 0.001);
+            let mut previous = 0.0;
+            for i in 1..100 {
+                let y = tone_mapping.map(i as FSize * 0.2);
+                test::assert_in_range(y, 0.0..1.000001);
+                assert!(y >= previous);
+                previous = y;
+            }
+        }
+        test::assert_eq_float(ToneMapping::Clamp.map(2.0), 1.0, 0.000001);
+        test::assert_eq_float(ToneMapping::Reinhard.map(1.0), 0.5, 0.000001);
+        assert!(ToneMapping::Reinhard.map(100.0) < 1.0);
+        test::assert_eq_float(ToneMapping::Hable.map(11.2 / 2.0), 1.0, 0.000001);
+    }
+
+    #[test]
+    fn to_rgba8_test() {
+        let display = DisplayPipeline::default();
+        assert_eq!(
+            display.to_rgba8(&ColorRGB::new(0.0, 0.5, 2.0), 0, 0),
+            [0, 188, 255, 255]
+        );
+        assert_eq!(
+            display.to_rgba8(&ColorRGB::new(FSize::NAN, FSize::INFINITY, -1.0), 0, 0),
+            [0, 0, 0, 255]
+        );
+        let display = DisplayPipeline::new(1.0, ToneMapping::Clamp, false);
+        assert_eq!(
+            display.to_rgba8(&ColorRGB::new(0.25, 0.5, 2.0), 0, 0),
+            [188, 255, 255, 255]
+        );
+    }
+
+    #[test]
+    fn dithering_test() {
+        let display = DisplayPipeline::new(0.0, ToneMapping::Clamp, true);
+        let color = ColorRGB::new(0.2, 0.2, 0.2);
+        let reference = DisplayPipeline::default().to_rgba8(&color, 0, 0)[0] as i32;
+        let pixel_data = display.to_pixel_data(8, &vec![color; 64]);
+        assert!(pixel_data
+            .chunks(4)
+            .all(|p| (p[0] as i32 - reference).abs() <= 1));
+        assert!(pixel_data.chunks(4).any(|p| p[0] as i32 != reference));
+        assert_eq!(
+            display.to_rgba8(&color, 3, 4),
+            display.to_rgba8(&color, 3, 4)
+        );
+    }
+}
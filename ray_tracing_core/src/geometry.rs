@@ -24,6 +24,17 @@ pub mod volume;
 /// Implementation of hit able instancing objects
 pub mod instancing;
 
+/// Constructive solid geometry
+///
+/// Implementation of boolean operations on closed geometries
+pub mod csg;
+
+mod traversal_statistics;
+pub use self::traversal_statistics::TraversalStatistics;
+
+/// Minimum distance between 2 consecutive intersections listed by `Geometry::hit_all`
+pub const HIT_ALL_EPSILON: FSize = 0.0001;
+
 pub trait Geometry: Sync + Send {
     fn get_id(&self) -> usize;
 
@@ -31,6 +42,25 @@ pub trait Geometry: Sync + Send {
 
     fn hit(&self, ray: &Ray, t_range: Range<FSize>) -> Option<HitRecord>;
 
+    /// Any hit query for visibility tests. Returns `true` at the first opaque intersection in `t_range`.
+    /// No hit record is created, but the alpha channel of the material is still respected.
+    fn occluded(&self, ray: &Ray, t_range: Range<FSize>) -> bool {
+        self.hit(ray, t_range).is_some()
+    }
+
+    /// All intersections of the ray with the surface in `t_range`, sorted by `t`.
+    /// A hit, where the normal vector points against the ray direction, enters a closed geometry,
+    /// otherwise it exits the geometry.
+    fn hit_all(&self, ray: &Ray, t_range: Range<FSize>) -> Vec<HitRecord> {
+        let mut hits = Vec::default();
+        let mut t_min = t_range.start;
+        while let Some(hit_record) = self.hit(ray, t_min..t_range.end) {
+            t_min = hit_record.t + HIT_ALL_EPSILON / glm::length(ray.direction);
+            hits.push(hit_record);
+        }
+        hits
+    }
+
     fn pdf_value(&self, _o: &Vector3, _v: &Vector3) -> FSize {
         0.0
     }
@@ -52,6 +82,10 @@ pub trait Visitor {
         &mut self,
         n: &collection::LeafNode,
     ) -> Result<(), Box<dyn Error>>;
+    fn visit_collection_linear_bvh(
+        &mut self,
+        l: &collection::LinearBVH,
+    ) -> Result<(), Box<dyn Error>>;
     fn visit_shape_sphere(&mut self, s: &shape::Sphere) -> Result<(), Box<dyn Error>>;
     fn visit_shape_movable_sphere(
         &mut self,
@@ -61,6 +95,8 @@ pub trait Visitor {
     fn visit_shape_xz_rect(&mut self, r: &shape::XZRect) -> Result<(), Box<dyn Error>>;
     fn visit_shape_yz_rect(&mut self, r: &shape::YZRect) -> Result<(), Box<dyn Error>>;
     fn visit_shape_cuboid(&mut self, c: &shape::Cuboid) -> Result<(), Box<dyn Error>>;
+    fn visit_shape_triangle(&mut self, t: &shape::Triangle) -> Result<(), Box<dyn Error>>;
+    fn visit_shape_triangle_mesh(&mut self, m: &shape::TriangleMesh) -> Result<(), Box<dyn Error>>;
     fn visit_volume_constant_medium(
         &mut self,
         v: &volume::ConstantMedium,
@@ -76,6 +112,11 @@ pub trait Visitor {
         &mut self,
         i: &instancing::Translate,
     ) -> Result<(), Box<dyn Error>>;
+    fn visit_instancing_transform(
+        &mut self,
+        i: &instancing::Transform,
+    ) -> Result<(), Box<dyn Error>>;
+    fn visit_csg_node(&mut self, n: &csg::CSGNode) -> Result<(), Box<dyn Error>>;
 }
 
 #[cfg(test)]
@@ -92,7 +133,7 @@ mod test_visitor {
     impl TestVisitor {
         pub fn default() -> TestVisitor {
             TestVisitor {
-                count: vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+                count: vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
             }
         }
 
@@ -126,6 +167,15 @@ mod test_visitor {
         ) -> Result<(), Box<dyn Error>> {
             n.node.accept(self)
         }
+        fn visit_collection_linear_bvh(
+            &mut self,
+            l: &collection::LinearBVH,
+        ) -> Result<(), Box<dyn Error>> {
+            for n in l.primitives.iter().chain(l.unbounded.iter()) {
+                n.accept(self)?;
+            }
+            Ok(())
+        }
         fn visit_shape_sphere(&mut self, _: &shape::Sphere) -> Result<(), Box<dyn Error>> {
             self.count[0] += 1;
             Ok(())
@@ -195,6 +245,28 @@ mod test_visitor {
             self.count[11] += 1;
             Ok(())
         }
+        fn visit_shape_triangle(&mut self, _: &shape::Triangle) -> Result<(), Box<dyn Error>> {
+            self.count[12] += 1;
+            Ok(())
+        }
+        fn visit_shape_triangle_mesh(
+            &mut self,
+            _: &shape::TriangleMesh,
+        ) -> Result<(), Box<dyn Error>> {
+            self.count[13] += 1;
+            Ok(())
+        }
+        fn visit_instancing_transform(
+            &mut self,
+            _: &instancing::Transform,
+        ) -> Result<(), Box<dyn Error>> {
+            self.count[14] += 1;
+            Ok(())
+        }
+        fn visit_csg_node(&mut self, _: &csg::CSGNode) -> Result<(), Box<dyn Error>> {
+            self.count[15] += 1;
+            Ok(())
+        }
     }
 
     #[test]
@@ -253,6 +325,28 @@ mod test_visitor {
         v.evaluate(0, 1);
     }
 
+    #[test]
+    pub fn test_visitor_linear_bvh() {
+        let l = collection::LinearBVH::new(
+            &vec![
+                Arc::new(shape::Sphere::new(
+                    Point3::new(0.0, 0.0, 0.0),
+                    1.0,
+                    Arc::new(NoMaterial::new()),
+                )),
+                Arc::new(shape::Sphere::new(
+                    Point3::new(0.0, 0.0, 0.0),
+                    1.0,
+                    Arc::new(NoMaterial::new()),
+                )),
+            ],
+            0.0..0.0,
+        );
+        let mut v = TestVisitor::default();
+        l.accept(&mut v).unwrap();
+        v.evaluate(0, 2);
+    }
+
     #[test]
     pub fn test_visitor_sphere() {
         let s = shape::Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, Arc::new(NoMaterial::new()));
@@ -385,4 +479,57 @@ mod test_visitor {
         i.accept(&mut v).unwrap();
         v.evaluate(11, 1);
     }
+
+    #[test]
+    pub fn test_visitor_triangle() {
+        let t = shape::Triangle::new(
+            [
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+            ],
+            Arc::new(NoMaterial::new()),
+        );
+        let mut v = TestVisitor::default();
+        t.accept(&mut v).unwrap();
+        v.evaluate(12, 1);
+    }
+
+    #[test]
+    pub fn test_visitor_triangle_mesh() {
+        let m = shape::TriangleMesh::new(
+            vec![
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+            ],
+            Vec::default(),
+            Vec::default(),
+            vec![[0, 1, 2]],
+            Arc::new(NoMaterial::new()),
+        );
+        let mut v = TestVisitor::default();
+        m.accept(&mut v).unwrap();
+        v.evaluate(13, 1);
+    }
+
+    #[test]
+    pub fn test_visitor_csg_node() {
+        let n = csg::CSGNode::new(
+            csg::CSGOperation::Difference,
+            Arc::new(shape::Sphere::new(
+                Point3::new(0.0, 0.0, 0.0),
+                1.0,
+                Arc::new(NoMaterial::new()),
+            )),
+            Arc::new(shape::Sphere::new(
+                Point3::new(1.0, 0.0, 0.0),
+                1.0,
+                Arc::new(NoMaterial::new()),
+            )),
+        );
+        let mut v = TestVisitor::default();
+        n.accept(&mut v).unwrap();
+        v.evaluate(15, 1);
+    }
 }
@@ -2,7 +2,10 @@ mod geometry_list;
 pub use self::geometry_list::GeometryList;
 
 mod bvh_node;
-pub use self::bvh_node::BVHNode;
+pub use self::bvh_node::{BVHBuilder, BVHNode, BVH_INTERSECTION_COST, BVH_TRAVERSAL_COST};
+
+mod linear_bvh;
+pub use self::linear_bvh::{LinearBVH, LINEAR_BVH_MAX_LEAF_PRIMITIVES};
 
 mod leaf_node;
 pub use self::leaf_node::LeafNode;
@@ -10,13 +10,34 @@ use std::error::Error;
 use std::ops::Range;
 use std::sync::Arc;
 
+/// Construction strategy of a bounding volume hierarchy
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BVHBuilder {
+    /// Split the list at the median along a random axis
+    RandomMedian,
+
+    /// Deterministic binned surface area heuristic
+    SAH,
+}
+
+/// Cost of a traversal step, relative to the cost of an intersection test
+pub const BVH_TRAVERSAL_COST: FSize = 1.0;
+
+/// Cost of an intersection test with a leaf of the hierarchy
+pub const BVH_INTERSECTION_COST: FSize = 1.0;
+
+/// Number of bins per axis used by the binned surface area heuristic
+const SAH_BINS: usize = 16;
+
 /// Bounding volume hierarchy node  
 pub struct BVHNode {
     pub id: usize,
     pub left: Arc<dyn Geometry>,
     pub right: Arc<dyn Geometry>,
+    pub builder: BVHBuilder,
     bounding_box: Option<AABB>,
     axis: usize,
+    cost: FSize,
 }
 
 impl BVHNode {
@@ -29,8 +50,87 @@ impl BVHNode {
         list: &Vec<Arc<dyn Geometry>>,
         t_range: Range<FSize>,
     ) -> Arc<dyn Geometry> {
+        BVHNode::build_random_median(id, list, t_range).0
+    }
+
+    pub fn new_sah(list: &Vec<Arc<dyn Geometry>>, t_range: Range<FSize>) -> Arc<dyn Geometry> {
+        BVHNode::new_id_sah(Object::new_id(), list, t_range)
+    }
+
+    pub fn new_id_sah(
+        id: usize,
+        list: &Vec<Arc<dyn Geometry>>,
+        t_range: Range<FSize>,
+    ) -> Arc<dyn Geometry> {
+        BVHNode::build_sah(id, list, t_range).0
+    }
+
+    pub fn new_id_with_builder(
+        id: usize,
+        builder: BVHBuilder,
+        list: &Vec<Arc<dyn Geometry>>,
+        t_range: Range<FSize>,
+    ) -> Arc<dyn Geometry> {
+        match builder {
+            BVHBuilder::RandomMedian => BVHNode::new_id(id, list, t_range),
+            BVHBuilder::SAH => BVHNode::new_id_sah(id, list, t_range),
+        }
+    }
+
+    /// Expected cost of a ray, which hits the root bounding box.
+    ///
+    /// Sum of the costs of all inner nodes and leaves, weighted by the ratio of their surface area
+    /// to the surface area of the root (surface area heuristic).
+    pub fn traversal_cost(&self) -> FSize {
+        match &self.bounding_box {
+            Some(b) if b.surface_area() > 0.0 => self.cost / b.surface_area(),
+            _ => 0.0,
+        }
+    }
+
+    fn leaf_node(node: Arc<dyn Geometry>, t_range: Range<FSize>) -> (Arc<dyn Geometry>, FSize) {
+        let cost = BVHNode::area(&node.bounding_box(t_range.clone())) * BVH_INTERSECTION_COST;
+        (Arc::new(LeafNode::new(node, t_range)), cost)
+    }
+
+    fn area(b: &Option<AABB>) -> FSize {
+        match b {
+            Some(b) => b.surface_area(),
+            None => 0.0,
+        }
+    }
+
+    fn inner_node(
+        id: usize,
+        builder: BVHBuilder,
+        left: (Arc<dyn Geometry>, FSize),
+        right: (Arc<dyn Geometry>, FSize),
+        axis: usize,
+        t_range: Range<FSize>,
+    ) -> (Arc<dyn Geometry>, FSize) {
+        let bounding_box = BVHNode::new_bounding_box(&left.0, &right.0, t_range);
+        let cost = BVHNode::area(&bounding_box) * BVH_TRAVERSAL_COST + left.1 + right.1;
+        (
+            Arc::new(BVHNode {
+                id,
+                left: left.0,
+                right: right.0,
+                builder,
+                bounding_box,
+                axis,
+                cost,
+            }),
+            cost,
+        )
+    }
+
+    fn build_random_median(
+        id: usize,
+        list: &Vec<Arc<dyn Geometry>>,
+        t_range: Range<FSize>,
+    ) -> (Arc<dyn Geometry>, FSize) {
         if list.len() == 1 {
-            return Arc::new(LeafNode::new(list[0].clone(), t_range));
+            return BVHNode::leaf_node(list[0].clone(), t_range);
         }
 
         let axis = random::generate_axis();
@@ -51,21 +151,141 @@ impl BVHNode {
                 (_, _) => Ordering::Equal,
             }
         });
-        let left = BVHNode::new(&list[0..list.len() / 2].to_vec(), t_range.clone());
-        let right = BVHNode::new(
+        let left = BVHNode::build_random_median(
+            Object::new_id(),
+            &list[0..list.len() / 2].to_vec(),
+            t_range.clone(),
+        );
+        let right = BVHNode::build_random_median(
+            Object::new_id(),
             &list[(list.len() / 2)..list.len()].to_vec(),
             t_range.clone(),
         );
-        let bounding_box = BVHNode::new_bounding_box(&left, &right, t_range);
-        Arc::new(BVHNode {
-            id,
-            left,
-            right,
-            bounding_box: bounding_box,
-            axis,
+        BVHNode::inner_node(id, BVHBuilder::RandomMedian, left, right, axis, t_range)
+    }
+
+    /// Binned SAH split: The centroids of the bounding boxes are sorted into bins along each axis
+    /// and the list is split between the bins where `area(L) * count(L) + area(R) * count(R)` is minimal.
+    /// If all centroids coincide, the list is split in the middle.
+    fn build_sah(
+        id: usize,
+        list: &Vec<Arc<dyn Geometry>>,
+        t_range: Range<FSize>,
+    ) -> (Arc<dyn Geometry>, FSize) {
+        if list.len() == 1 {
+            return BVHNode::leaf_node(list[0].clone(), t_range);
+        }
+
+        let boxes: Vec<AABB> = list
+            .iter()
+            .map(|g| match g.bounding_box(t_range.clone()) {
+                Some(b) => b,
+                None => AABB::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.0)),
+            })
+            .collect();
+        let (axis, left_list, right_list) = match BVHNode::sah_split(&boxes) {
+            Some((axis, left_side)) => {
+                let mut left_list = Vec::default();
+                let mut right_list = Vec::default();
+                for (g, is_left) in list.iter().zip(left_side.iter()) {
+                    if *is_left {
+                        left_list.push(g.clone());
+                    } else {
+                        right_list.push(g.clone());
+                    }
+                }
+                (axis, left_list, right_list)
+            }
+            None => (
+                0,
+                list[0..list.len() / 2].to_vec(),
+                list[(list.len() / 2)..list.len()].to_vec(),
+            ),
+        };
+        let left = BVHNode::build_sah(Object::new_id(), &left_list, t_range.clone());
+        let right = BVHNode::build_sah(Object::new_id(), &right_list, t_range.clone());
+        BVHNode::inner_node(id, BVHBuilder::SAH, left, right, axis, t_range)
+    }
+
+    /// Finds the binned SAH split of a list of bounding boxes.
+    /// Returns the split axis and for each box whether it belongs to the left side,
+    /// or `None` if the boxes can't be separated.
+    pub(crate) fn sah_split(boxes: &[AABB]) -> Option<(usize, Vec<bool>)> {
+        if boxes.len() < 2 {
+            return None;
+        }
+        let centroids: Vec<Vector3> = boxes.iter().map(|b| b.centroid()).collect();
+        let centroid_bounds = centroids
+            .iter()
+            .skip(1)
+            .fold(AABB::new(centroids[0], centroids[0]), |b, c| {
+                b.or_vector(*c)
+            });
+
+        let bin_index = |axis: usize, c: &Vector3| {
+            let extent = centroid_bounds.max[axis] - centroid_bounds.min[axis];
+            let i = ((c[axis] - centroid_bounds.min[axis]) / extent * SAH_BINS as FSize) as usize;
+            usize::min(i, SAH_BINS - 1)
+        };
+
+        let mut best: Option<(FSize, usize, usize)> = None;
+        for axis in 0..3 {
+            if centroid_bounds.max[axis] - centroid_bounds.min[axis] <= 0.0 {
+                continue;
+            }
+            let mut bin_boxes: Vec<Option<AABB>> = vec![None; SAH_BINS];
+            let mut bin_counts = [0usize; SAH_BINS];
+            for (b, c) in boxes.iter().zip(centroids.iter()) {
+                let i = bin_index(axis, c);
+                bin_counts[i] += 1;
+                bin_boxes[i] = match bin_boxes[i].take() {
+                    Some(bin_box) => Some(bin_box | b.clone()),
+                    None => Some(b.clone()),
+                };
+            }
+            let mut right_areas = [0.0; SAH_BINS];
+            let mut right_box: Option<AABB> = None;
+            for i in (1..SAH_BINS).rev() {
+                right_box = BVHNode::or_box(right_box, &bin_boxes[i]);
+                right_areas[i] = BVHNode::area(&right_box);
+            }
+            let mut left_box: Option<AABB> = None;
+            let mut left_count = 0;
+            for i in 0..SAH_BINS - 1 {
+                left_box = BVHNode::or_box(left_box, &bin_boxes[i]);
+                left_count += bin_counts[i];
+                let right_count = boxes.len() - left_count;
+                if left_count == 0 || right_count == 0 {
+                    continue;
+                }
+                let cost = BVHNode::area(&left_box) * left_count as FSize
+                    + right_areas[i + 1] * right_count as FSize;
+                best = match best {
+                    Some((best_cost, _, _)) if best_cost <= cost => best,
+                    _ => Some((cost, axis, i)),
+                };
+            }
+        }
+
+        best.map(|(_, axis, split)| {
+            (
+                axis,
+                centroids
+                    .iter()
+                    .map(|c| bin_index(axis, c) <= split)
+                    .collect(),
+            )
         })
     }
 
+    fn or_box(a: Option<AABB>, b: &Option<AABB>) -> Option<AABB> {
+        match (a, b) {
+            (Some(a), Some(b)) => Some(a | b.clone()),
+            (Some(a), None) => Some(a),
+            (None, b) => b.clone(),
+        }
+    }
+
     fn new_bounding_box(
         left: &Arc<dyn Geometry>,
         right: &Arc<dyn Geometry>,
@@ -138,6 +358,13 @@ impl Geometry for BVHNode {
         }
     }
 
+    fn occluded(&self, ray: &Ray, t_range: Range<FSize>) -> bool {
+        match &self.bounding_box {
+            Some(bounding_box) if !bounding_box.hit(ray, t_range.clone()) => false,
+            _ => self.left.occluded(ray, t_range.clone()) || self.right.occluded(ray, t_range),
+        }
+    }
+
     fn pdf_value(&self, o: &Vector3, v: &Vector3) -> FSize {
         0.5 * self.left.pdf_value(o, v) + 0.5 * self.right.pdf_value(o, v)
     }
@@ -228,4 +455,59 @@ mod bhv_node_test {
             None => (),
         }
     }
+
+    #[test]
+    fn sah_hit_test() {
+        let list = test::spheres_scene();
+        let bvh = BVHNode::new_sah(&list, 0.0..0.0);
+        let ray1 = Ray::new_ray(Vector3::new(3.5, 5.0, -2.5), Vector3::new(0.0, -1.0, 0.0));
+        let ray2 = Ray::new_ray(Vector3::new(3.0, 5.0, -2.0), Vector3::new(0.0, -1.0, 0.0));
+        match bvh.hit(&ray1, 0.0..10.0) {
+            Some(hit_record) => test::assert_eq_float(hit_record.t, 4.6, 0.001),
+            None => panic!("no result"),
+        }
+        match bvh.hit(&ray2, 0.0..10.0) {
+            Some(hit_record) => test::assert_eq_float(hit_record.t, 5.0, 0.01),
+            None => panic!("no result"),
+        }
+    }
+
+    #[test]
+    fn sah_traversal_cost_test() {
+        let list = test::spheres_scene();
+        let sah_1 = BVHNode::build_sah(0, &list, 0.0..0.0).1;
+        let sah_2 = BVHNode::build_sah(0, &list, 0.0..0.0).1;
+        let median = BVHNode::build_random_median(0, &list, 0.0..0.0).1;
+        test::assert_eq_float(sah_1, sah_2, 0.000001);
+        assert!(sah_1 <= median);
+    }
+
+    #[test]
+    fn traversal_cost_test() {
+        let m = Arc::new(NoMaterial::new());
+        let list: Vec<Arc<dyn Geometry>> = vec![
+            Arc::new(Sphere::new(Point3::new(-1.0, 0.0, 0.0), 1.0, m.clone())),
+            Arc::new(Sphere::new(Point3::new(1.0, 0.0, 0.0), 1.0, m)),
+        ];
+        let (_, cost) = BVHNode::build_sah(0, &list, 0.0..0.0);
+        // root: 2*(4*2 + 2*2 + 2*4) = 40, leaves: 2 * 24
+        test::assert_eq_float(cost, 40.0 + 48.0, 0.001);
+    }
+
+    #[test]
+    fn occluded_test() {
+        let list = test::spheres_scene();
+        let bvh = BVHNode::new_sah(&list, 0.0..0.0);
+        for i in 0..100 {
+            let a = i as FSize * 0.2;
+            let ray = Ray::new_ray(
+                Vector3::new(0.0, 0.5, 0.0),
+                Vector3::new(FSize::cos(a), 0.1 * FSize::sin(a * 3.0), FSize::sin(a)),
+            );
+            assert_eq!(
+                bvh.occluded(&ray, 0.001..5.0),
+                bvh.hit(&ray, 0.001..5.0).is_some()
+            );
+        }
+    }
 }
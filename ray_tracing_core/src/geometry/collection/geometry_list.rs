@@ -56,6 +56,12 @@ impl Geometry for GeometryList {
         hit_record
     }
 
+    fn occluded(&self, ray: &Ray, t_range: Range<FSize>) -> bool {
+        self.list
+            .iter()
+            .any(|geometry| geometry.occluded(ray, t_range.clone()))
+    }
+
     fn pdf_value(&self, o: &Vector3, v: &Vector3) -> FSize {
         self.list.iter().map(|node| node.pdf_value(o, v)).sum()
     }
@@ -45,6 +45,13 @@ impl Geometry for LeafNode {
         self.node.hit(ray, t_range.clone())
     }
 
+    fn occluded(&self, ray: &Ray, t_range: Range<FSize>) -> bool {
+        match &self.bounding_box {
+            Some(bounding_box) if !bounding_box.hit(ray, t_range.clone()) => false,
+            _ => self.node.occluded(ray, t_range),
+        }
+    }
+
     fn pdf_value(&self, o: &Vector3, v: &Vector3) -> FSize {
         self.node.pdf_value(o, v)
     }
@@ -0,0 +1,323 @@
+use crate::core::object::Object;
+use crate::core::HitRecord;
+use crate::geometry::collection::BVHNode;
+use crate::geometry::{Geometry, Visitor};
+use crate::math::{Ray, AABB};
+use crate::random;
+use crate::types::{FSize, Vector3};
+use std::error::Error;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Maximum number of primitives in a leaf of the linear bounding volume hierarchy
+pub const LINEAR_BVH_MAX_LEAF_PRIMITIVES: usize = 4;
+
+/// Node of the flattened hierarchy
+///
+/// The first child of an inner node directly follows the node in the node array,
+/// `offset` is the index of the second child.
+/// For a leaf node `offset` is the index of the first primitive and `count` is the number of primitives.
+struct LinearBVHNode {
+    bounding_box: AABB,
+    offset: usize,
+    count: usize,
+    axis: usize,
+}
+
+/// Bounding volume hierarchy, which is flattened to a linear array of nodes
+///
+/// The hierarchy is built with the binned surface area heuristic and traversed iteratively with a stack,
+/// front to back along the split axis. Geometries without bounding box are tested separately.
+pub struct LinearBVH {
+    pub id: usize,
+    pub primitives: Vec<Arc<dyn Geometry>>,
+    pub unbounded: Vec<Arc<dyn Geometry>>,
+    nodes: Vec<LinearBVHNode>,
+}
+
+impl LinearBVH {
+    pub fn new(list: &Vec<Arc<dyn Geometry>>, t_range: Range<FSize>) -> LinearBVH {
+        LinearBVH::new_id(Object::new_id(), list, t_range)
+    }
+
+    pub fn new_id(id: usize, list: &Vec<Arc<dyn Geometry>>, t_range: Range<FSize>) -> LinearBVH {
+        let mut bounded = Vec::default();
+        let mut unbounded = Vec::default();
+        for g in list.iter() {
+            match g.bounding_box(t_range.clone()) {
+                Some(b) => bounded.push((g.clone(), b)),
+                None => unbounded.push(g.clone()),
+            }
+        }
+        let mut bvh = LinearBVH {
+            id,
+            primitives: Vec::with_capacity(bounded.len()),
+            unbounded,
+            nodes: Vec::default(),
+        };
+        if !bounded.is_empty() {
+            bvh.build(bounded);
+        }
+        bvh
+    }
+
+    /// Number of nodes of the flattened hierarchy
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn build(&mut self, list: Vec<(Arc<dyn Geometry>, AABB)>) {
+        let bounding_box = list
+            .iter()
+            .skip(1)
+            .fold(list[0].1.clone(), |b, (_, g)| b | g.clone());
+        let index = self.nodes.len();
+        self.nodes.push(LinearBVHNode {
+            bounding_box,
+            offset: self.primitives.len(),
+            count: list.len(),
+            axis: 0,
+        });
+        if list.len() <= LINEAR_BVH_MAX_LEAF_PRIMITIVES {
+            self.primitives.extend(list.into_iter().map(|(g, _)| g));
+            return;
+        }
+
+        let boxes: Vec<AABB> = list.iter().map(|(_, b)| b.clone()).collect();
+        let (axis, left_list, right_list) = match BVHNode::sah_split(&boxes) {
+            Some((axis, left_side)) => {
+                let mut left_list = Vec::default();
+                let mut right_list = Vec::default();
+                for (g, is_left) in list.into_iter().zip(left_side) {
+                    if is_left {
+                        left_list.push(g);
+                    } else {
+                        right_list.push(g);
+                    }
+                }
+                (axis, left_list, right_list)
+            }
+            None => {
+                let mut left_list = list;
+                let right_list = left_list.split_off(left_list.len() / 2);
+                (0, left_list, right_list)
+            }
+        };
+        self.build(left_list);
+        let offset = self.nodes.len();
+        self.build(right_list);
+        let node = &mut self.nodes[index];
+        node.offset = offset;
+        node.count = 0;
+        node.axis = axis;
+    }
+}
+
+impl Geometry for LinearBVH {
+    fn get_id(&self) -> usize {
+        self.id
+    }
+
+    fn bounding_box(&self, _: Range<FSize>) -> Option<AABB> {
+        self.nodes.first().map(|node| node.bounding_box.clone())
+    }
+
+    fn hit(&self, ray: &Ray, t_range: Range<FSize>) -> Option<HitRecord> {
+        let mut hit_record = None;
+        let mut closest_so_far = t_range.end;
+        for geometry in self.unbounded.iter() {
+            if let Some(hit) = geometry.hit(ray, t_range.start..closest_so_far) {
+                closest_so_far = hit.t;
+                hit_record = Some(hit);
+            }
+        }
+        if self.nodes.is_empty() {
+            return hit_record;
+        }
+
+        let mut stack: Vec<usize> = Vec::with_capacity(64);
+        let mut index = 0;
+        loop {
+            let node = &self.nodes[index];
+            let next = if !node.bounding_box.hit(ray, t_range.start..closest_so_far) {
+                stack.pop()
+            } else if node.count > 0 {
+                for geometry in self.primitives[node.offset..node.offset + node.count].iter() {
+                    if let Some(hit) = geometry.hit(ray, t_range.start..closest_so_far) {
+                        closest_so_far = hit.t;
+                        hit_record = Some(hit);
+                    }
+                }
+                stack.pop()
+            } else if ray.direction[node.axis] >= 0.0 {
+                stack.push(node.offset);
+                Some(index + 1)
+            } else {
+                stack.push(index + 1);
+                Some(node.offset)
+            };
+            match next {
+                Some(next) => index = next,
+                None => break,
+            }
+        }
+        hit_record
+    }
+
+    fn occluded(&self, ray: &Ray, t_range: Range<FSize>) -> bool {
+        if self
+            .unbounded
+            .iter()
+            .any(|geometry| geometry.occluded(ray, t_range.clone()))
+        {
+            return true;
+        }
+        if self.nodes.is_empty() {
+            return false;
+        }
+
+        let mut stack: Vec<usize> = Vec::with_capacity(64);
+        let mut index = 0;
+        loop {
+            let node = &self.nodes[index];
+            let next = if !node.bounding_box.hit(ray, t_range.clone()) {
+                stack.pop()
+            } else if node.count > 0 {
+                if self.primitives[node.offset..node.offset + node.count]
+                    .iter()
+                    .any(|geometry| geometry.occluded(ray, t_range.clone()))
+                {
+                    return true;
+                }
+                stack.pop()
+            } else {
+                stack.push(node.offset);
+                Some(index + 1)
+            };
+            match next {
+                Some(next) => index = next,
+                None => return false,
+            }
+        }
+    }
+
+    fn pdf_value(&self, o: &Vector3, v: &Vector3) -> FSize {
+        let count = self.primitives.len() + self.unbounded.len();
+        if count == 0 {
+            return 0.0;
+        }
+        let sum: FSize = self
+            .primitives
+            .iter()
+            .chain(self.unbounded.iter())
+            .map(|node| node.pdf_value(o, v))
+            .sum();
+        sum / count as FSize
+    }
+
+    fn random(&self, o: &Vector3) -> Vector3 {
+        let i = random::generate_from_range(0..self.primitives.len() + self.unbounded.len());
+        if i < self.primitives.len() {
+            self.primitives[i].random(o)
+        } else {
+            self.unbounded[i - self.primitives.len()].random(o)
+        }
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) -> Result<(), Box<dyn Error>> {
+        visitor.visit_collection_linear_bvh(self)
+    }
+}
+
+#[cfg(test)]
+mod linear_bvh_test {
+    use super::*;
+    use crate::geometry::collection::GeometryList;
+    use crate::geometry::shape::Sphere;
+    use crate::material::NoMaterial;
+    use crate::test;
+    use crate::types::Point3;
+
+    #[test]
+    fn bounding_box_test() {
+        let bvh = LinearBVH::new(
+            &vec![
+                Arc::new(Sphere::new(
+                    Point3::new(-1.0, 0.0, -1.0),
+                    1.0,
+                    Arc::new(NoMaterial::new()),
+                )),
+                Arc::new(Sphere::new(
+                    Point3::new(1.0, 1.0, 0.0),
+                    1.0,
+                    Arc::new(NoMaterial::new()),
+                )),
+            ],
+            0.0..0.0,
+        );
+        match bvh.bounding_box(0.0..0.0) {
+            Some(b) => {
+                test::assert_eq_vector3(&b.min, &Vector3::new(-2.0, -1.0, -2.0), 0.001);
+                test::assert_eq_vector3(&b.max, &Vector3::new(2.0, 2.0, 1.0), 0.001);
+            }
+            None => panic!("no bounding box"),
+        }
+        assert_eq!(bvh.node_count(), 1);
+    }
+
+    #[test]
+    fn hit_test() {
+        let list = test::spheres_scene();
+        let bvh = LinearBVH::new(&list, 0.0..0.0);
+        let reference = GeometryList::new(&list);
+        assert!(bvh.node_count() > 1);
+        for i in 0..200 {
+            let a = i as FSize * 0.1;
+            let ray = Ray::new_ray(
+                Vector3::new(6.0 * FSize::cos(a), 3.0, 6.0 * FSize::sin(a)),
+                Vector3::new(-FSize::cos(a * 1.3), -0.5, -FSize::sin(a * 0.7)),
+            );
+            match (
+                bvh.hit(&ray, 0.001..FSize::MAX),
+                reference.hit(&ray, 0.001..FSize::MAX),
+            ) {
+                (Some(h1), Some(h2)) => {
+                    test::assert_eq_float(h1.t, h2.t, 0.0001);
+                    test::assert_eq_vector3(&h1.position, &h2.position, 0.0001);
+                }
+                (None, None) => (),
+                _ => panic!("different hit result"),
+            }
+        }
+    }
+
+    #[test]
+    fn pdf_value_test() {
+        let list = test::spheres_scene();
+        let bvh = LinearBVH::new(&list, 0.0..0.0);
+        let o = Vector3::new(0.0, 10.0, 0.0);
+        let v = Vector3::new(0.0, -1.0, 0.0);
+        let expected: FSize =
+            list.iter().map(|g| g.pdf_value(&o, &v)).sum::<FSize>() / list.len() as FSize;
+        test::assert_eq_float(bvh.pdf_value(&o, &v), expected, 0.0001);
+    }
+
+    #[test]
+    fn occluded_test() {
+        let list = test::spheres_scene();
+        let bvh = LinearBVH::new(&list, 0.0..0.0);
+        for i in 0..200 {
+            let a = i as FSize * 0.1;
+            let ray = Ray::new_ray(
+                Vector3::new(6.0 * FSize::cos(a), 3.0, 6.0 * FSize::sin(a)),
+                Vector3::new(-FSize::cos(a * 1.3), -0.5, -FSize::sin(a * 0.7)),
+            );
+            for t_max in [2.0, 5.0, 10.0] {
+                assert_eq!(
+                    bvh.occluded(&ray, 0.001..t_max),
+                    bvh.hit(&ray, 0.001..t_max).is_some()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,2 @@
+mod csg_node;
+pub use self::csg_node::{CSGNode, CSGOperation};
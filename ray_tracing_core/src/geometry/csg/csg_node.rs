@@ -0,0 +1,352 @@
+use crate::core::object::Object;
+use crate::core::HitRecord;
+use crate::geometry::{Geometry, Visitor};
+use crate::math::{Ray, AABB};
+use crate::random;
+use crate::types::{FSize, Vector3};
+use std::error::Error;
+use std::iter::Peekable;
+use std::ops::Range;
+use std::sync::Arc;
+use std::vec::IntoIter;
+
+/// Boolean operation of a CSG node
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CSGOperation {
+    /// Inside of either of the geometries (OR)
+    Union,
+
+    /// Inside of both geometries (AND)
+    Intersection,
+
+    /// Inside of the left geometry and outside of the right geometry (MINUS)
+    Difference,
+}
+
+impl CSGOperation {
+    fn inside(&self, inside_left: bool, inside_right: bool) -> bool {
+        match self {
+            CSGOperation::Union => inside_left || inside_right,
+            CSGOperation::Intersection => inside_left && inside_right,
+            CSGOperation::Difference => inside_left && !inside_right,
+        }
+    }
+}
+
+/// Constructive solid geometry node
+///
+/// Combines 2 closed geometries with a boolean operation.
+/// The intersections of the ray with both geometries are listed with `Geometry::hit_all` along the entire ray.
+/// The intersections are classified as entry or exit by the direction of the normal vector
+/// and the boundary of the combined geometry is where the inside state of the operation changes.
+/// The normal vector of a boundary hit is oriented, so that it points out of the combined geometry.
+/// This flips the normal vectors on the surfaces which are subtracted.
+pub struct CSGNode {
+    pub id: usize,
+    pub operation: CSGOperation,
+    pub left: Arc<dyn Geometry>,
+    pub right: Arc<dyn Geometry>,
+}
+
+impl CSGNode {
+    pub fn new(
+        operation: CSGOperation,
+        left: Arc<dyn Geometry>,
+        right: Arc<dyn Geometry>,
+    ) -> CSGNode {
+        CSGNode::new_id(Object::new_id(), operation, left, right)
+    }
+
+    pub fn new_id(
+        id: usize,
+        operation: CSGOperation,
+        left: Arc<dyn Geometry>,
+        right: Arc<dyn Geometry>,
+    ) -> CSGNode {
+        CSGNode {
+            id,
+            operation,
+            left,
+            right,
+        }
+    }
+
+    fn is_entry(ray: &Ray, hit_record: &HitRecord) -> bool {
+        glm::dot(ray.direction, hit_record.normal) < 0.0
+    }
+
+    /// The ray starts inside a closed geometry, if the first intersection exits the geometry
+    fn starts_inside(ray: &Ray, hits: &mut Peekable<IntoIter<HitRecord>>) -> bool {
+        match hits.peek() {
+            Some(hit_record) => !CSGNode::is_entry(ray, hit_record),
+            None => false,
+        }
+    }
+
+    /// Intersections with the boundary of the combined geometry in `t_range`.
+    /// If `first_only` is set, the search is terminated after the first intersection.
+    fn boundary_hits(&self, ray: &Ray, t_range: Range<FSize>, first_only: bool) -> Vec<HitRecord> {
+        let mut result = Vec::default();
+        let mut left = self
+            .left
+            .hit_all(ray, -FSize::MAX..t_range.end)
+            .into_iter()
+            .peekable();
+        let mut right = self
+            .right
+            .hit_all(ray, -FSize::MAX..t_range.end)
+            .into_iter()
+            .peekable();
+        let mut inside_left = CSGNode::starts_inside(ray, &mut left);
+        let mut inside_right = CSGNode::starts_inside(ray, &mut right);
+        loop {
+            let take_left = match (left.peek(), right.peek()) {
+                (Some(l), Some(r)) => l.t <= r.t,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => break,
+            };
+            let was_inside = self.operation.inside(inside_left, inside_right);
+            let mut hit_record = if take_left {
+                let hit_record = left.next().unwrap();
+                inside_left = CSGNode::is_entry(ray, &hit_record);
+                hit_record
+            } else {
+                let hit_record = right.next().unwrap();
+                inside_right = CSGNode::is_entry(ray, &hit_record);
+                hit_record
+            };
+            let is_inside = self.operation.inside(inside_left, inside_right);
+            if was_inside == is_inside || !t_range.contains(&hit_record.t) {
+                continue;
+            }
+            if is_inside != CSGNode::is_entry(ray, &hit_record) {
+                hit_record.invert_normal();
+            }
+            result.push(hit_record);
+            if first_only {
+                break;
+            }
+        }
+        result
+    }
+}
+
+impl Geometry for CSGNode {
+    fn get_id(&self) -> usize {
+        self.id
+    }
+
+    fn bounding_box(&self, t_range: Range<FSize>) -> Option<AABB> {
+        let left = self.left.bounding_box(t_range.clone());
+        let right = self.right.bounding_box(t_range);
+        match self.operation {
+            CSGOperation::Union => match (left, right) {
+                (Some(l), Some(r)) => Some(l | r),
+                (_, _) => None,
+            },
+            CSGOperation::Intersection => match (left, right) {
+                (Some(l), Some(r)) => Some(AABB::new(
+                    Vector3::new(
+                        FSize::max(l.min.x, r.min.x),
+                        FSize::max(l.min.y, r.min.y),
+                        FSize::max(l.min.z, r.min.z),
+                    ),
+                    Vector3::new(
+                        FSize::max(FSize::min(l.max.x, r.max.x), FSize::max(l.min.x, r.min.x)),
+                        FSize::max(FSize::min(l.max.y, r.max.y), FSize::max(l.min.y, r.min.y)),
+                        FSize::max(FSize::min(l.max.z, r.max.z), FSize::max(l.min.z, r.min.z)),
+                    ),
+                )),
+                (Some(l), None) => Some(l),
+                (None, Some(r)) => Some(r),
+                (None, None) => None,
+            },
+            CSGOperation::Difference => left,
+        }
+    }
+
+    fn hit(&self, ray: &Ray, t_range: Range<FSize>) -> Option<HitRecord> {
+        self.boundary_hits(ray, t_range, true).into_iter().next()
+    }
+
+    fn hit_all(&self, ray: &Ray, t_range: Range<FSize>) -> Vec<HitRecord> {
+        self.boundary_hits(ray, t_range, false)
+    }
+
+    /// Both geometries are sampled with the same probability, for each operation.
+    /// The boundary of the combined geometry is a part of the surfaces of the geometries,
+    /// so each direction, which hits the boundary, can be generated.
+    /// The density is the density of [`CSGNode::random`] for all directions.
+    /// Directions, which miss the boundary, don't hit the light and don't contribute.
+    fn pdf_value(&self, o: &Vector3, v: &Vector3) -> FSize {
+        0.5 * self.left.pdf_value(o, v) + 0.5 * self.right.pdf_value(o, v)
+    }
+
+    fn random(&self, o: &Vector3) -> Vector3 {
+        if random::generate_size() < 0.5 {
+            self.left.random(o)
+        } else {
+            self.right.random(o)
+        }
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) -> Result<(), Box<dyn Error>> {
+        visitor.visit_csg_node(self)
+    }
+}
+
+#[cfg(test)]
+mod csg_node_test {
+    use super::*;
+    use crate::geometry::shape::{Cuboid, Sphere};
+    use crate::material::Lambertian;
+    use crate::test;
+    use crate::texture::ConstantTexture;
+    use crate::types::{ColorRGBA, Point3};
+    use std::f64::consts::PI;
+
+    fn spheres(operation: CSGOperation) -> CSGNode {
+        let material = Arc::new(Lambertian::new(Arc::new(ConstantTexture::new(
+            ColorRGBA::new(1.0, 1.0, 1.0, 1.0),
+        ))));
+        CSGNode::new(
+            operation,
+            Arc::new(Sphere::new(
+                Point3::new(-0.5, 0.0, 0.0),
+                1.0,
+                material.clone(),
+            )),
+            Arc::new(Sphere::new(Point3::new(0.5, 0.0, 0.0), 1.0, material)),
+        )
+    }
+
+    fn ray_x() -> Ray {
+        Ray::new_ray(Vector3::new(-5.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0))
+    }
+
+    fn hit_positions(n: &CSGNode, ray: &Ray) -> Vec<(FSize, FSize)> {
+        n.hit_all(ray, 0.0..FSize::MAX)
+            .iter()
+            .map(|h| (h.position.x, h.normal.x))
+            .collect()
+    }
+
+    fn assert_hits(actual: Vec<(FSize, FSize)>, expected: Vec<(FSize, FSize)>) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            test::assert_eq_float(a.0, e.0, 0.001);
+            test::assert_eq_float(a.1, e.1, 0.001);
+        }
+    }
+
+    #[test]
+    fn union_test() {
+        let n = spheres(CSGOperation::Union);
+        assert_hits(hit_positions(&n, &ray_x()), vec![(-1.5, -1.0), (1.5, 1.0)]);
+    }
+
+    #[test]
+    fn intersection_test() {
+        let n = spheres(CSGOperation::Intersection);
+        assert_hits(hit_positions(&n, &ray_x()), vec![(-0.5, -1.0), (0.5, 1.0)]);
+    }
+
+    #[test]
+    fn difference_test() {
+        let n = spheres(CSGOperation::Difference);
+        assert_hits(hit_positions(&n, &ray_x()), vec![(-1.5, -1.0), (-0.5, 1.0)]);
+        let ray = Ray::new_ray(Vector3::new(5.0, 0.0, 0.0), Vector3::new(-1.0, 0.0, 0.0));
+        match n.hit(&ray, 0.0..FSize::MAX) {
+            Some(hit_record) => {
+                test::assert_eq_vector3(&hit_record.position, &Point3::new(-0.5, 0.0, 0.0), 0.001);
+                test::assert_eq_vector3(&hit_record.normal, &Vector3::new(1.0, 0.0, 0.0), 0.001);
+            }
+            None => panic!("no result"),
+        }
+    }
+
+    #[test]
+    fn ray_inside_test() {
+        let n = spheres(CSGOperation::Difference);
+        let ray = Ray::new_ray(Vector3::new(-1.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        match n.hit(&ray, 0.001..FSize::MAX) {
+            Some(hit_record) => {
+                test::assert_eq_float(hit_record.t, 0.5, 0.001);
+                test::assert_eq_vector3(&hit_record.normal, &Vector3::new(1.0, 0.0, 0.0), 0.001);
+            }
+            None => panic!("no result"),
+        }
+    }
+
+    #[test]
+    fn cut_cuboid_test() {
+        let material = Arc::new(Lambertian::new(Arc::new(ConstantTexture::new(
+            ColorRGBA::new(1.0, 1.0, 1.0, 1.0),
+        ))));
+        let n = CSGNode::new(
+            CSGOperation::Difference,
+            Arc::new(Cuboid::new(
+                Point3::new(-1.0, -1.0, -1.0)..Point3::new(1.0, 1.0, 1.0),
+                material.clone(),
+            )),
+            Arc::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 0.5, material)),
+        );
+        assert_hits(
+            hit_positions(&n, &ray_x()),
+            vec![(-1.0, -1.0), (-0.5, 1.0), (0.5, -1.0), (1.0, 1.0)],
+        );
+        let ray = Ray::new_ray(Vector3::new(-5.0, 0.75, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        assert_hits(hit_positions(&n, &ray), vec![(-1.0, -1.0), (1.0, 1.0)]);
+    }
+
+    #[test]
+    fn pdf_value_test() {
+        let n = spheres(CSGOperation::Difference);
+        let o = Vector3::new(0.0, 0.0, 3.0);
+        let samples = 100000;
+        random::with_seed(Some(1), || {
+            // the density of the generated directions in small cones around directions to the left sphere,
+            // to both spheres and to the right sphere
+            let directions: Vec<Vector3> =
+                (0..samples).map(|_| glm::normalize(n.random(&o))).collect();
+            let cos_cone = 0.998;
+            let cone_solid_angle = 2.0 * PI * (1.0 - cos_cone);
+            for target in [-1.0, 0.0, 1.0] {
+                let v = glm::normalize(Vector3::new(target, 0.0, 0.0) - o);
+                let count = directions
+                    .iter()
+                    .filter(|d| glm::dot(**d, v) > cos_cone)
+                    .count();
+                let estimate = count as FSize / (samples as FSize * cone_solid_angle);
+                test::assert_eq_float(estimate / n.pdf_value(&o, &v), 1.0, 0.05);
+            }
+
+            // the solid angle of the boundary, estimated by the density and by uniform directions
+            let hits = |v: &Vector3| n.hit(&Ray::new_ray(o, *v), 0.001..FSize::MAX).is_some();
+            let by_pdf: FSize = directions
+                .iter()
+                .filter(|v| hits(v))
+                .map(|v| 1.0 / n.pdf_value(&o, v))
+                .sum::<FSize>()
+                / samples as FSize;
+            let uniform_hits = (0..samples)
+                .filter(|_| hits(&glm::normalize(random::generate_unit_sphere())))
+                .count();
+            let by_uniform = 4.0 * PI * uniform_hits as FSize / samples as FSize;
+            test::assert_eq_float(by_pdf / by_uniform, 1.0, 0.05);
+        });
+    }
+
+    #[test]
+    fn bounding_box_test() {
+        let n = spheres(CSGOperation::Intersection);
+        match n.bounding_box(0.0..0.0) {
+            Some(b) => {
+                test::assert_eq_vector3(&b.min, &Vector3::new(-0.5, -1.0, -1.0), 0.001);
+                test::assert_eq_vector3(&b.max, &Vector3::new(0.5, 1.0, 1.0), 0.001);
+            }
+            None => panic!("no bounding box"),
+        }
+    }
+}
@@ -12,3 +12,6 @@ pub use rotate_z::RotateZ;
 
 mod translate;
 pub use translate::Translate;
+
+mod transform;
+pub use transform::Transform;
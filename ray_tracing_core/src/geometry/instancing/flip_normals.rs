@@ -40,6 +40,10 @@ impl Geometry for FlipNormals {
         }
     }
 
+    fn occluded(&self, ray: &Ray, t_range: Range<FSize>) -> bool {
+        self.node.occluded(ray, t_range)
+    }
+
     fn pdf_value(&self, o: &Vector3, v: &Vector3) -> FSize {
         self.node.pdf_value(o, v)
     }
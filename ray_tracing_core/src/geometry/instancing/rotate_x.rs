@@ -83,6 +83,17 @@ impl Geometry for RotateX {
         }
     }
 
+    fn occluded(&self, ray: &Ray, t_range: Range<FSize>) -> bool {
+        self.node.occluded(
+            &Ray::new_ray_with_attributes(
+                self.rotate_inverse(ray.origin),
+                self.rotate_inverse(ray.direction),
+                ray,
+            ),
+            t_range,
+        )
+    }
+
     fn pdf_value(&self, o: &Vector3, v: &Vector3) -> FSize {
         self.node.pdf_value(o, &self.rotate_inverse(*v))
     }
@@ -0,0 +1,303 @@
+use crate::core::object::Object;
+use crate::core::HitRecord;
+use crate::geometry::{Geometry, Visitor};
+use crate::math::{Ray, AABB};
+use crate::types::{FSize, Matrix4, Point3, Vector3, Vector4};
+use glm::GenSquareMat;
+use std::error::Error;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// General affine transformation of a geometry
+///
+/// `matrix` transforms from object space to world space, `inverse` from world space to object space.
+/// Rays are transformed into object space and the hit record is transformed back to world space.
+/// Normal vectors are transformed by the inverse transpose matrix.
+pub struct Transform {
+    pub id: usize,
+    pub matrix: Matrix4,
+    pub inverse: Matrix4,
+    pub node: Arc<dyn Geometry>,
+}
+
+impl Transform {
+    /// Creates a transformation instance. Fails if `matrix` is not invertible.
+    pub fn new(matrix: Matrix4, node: Arc<dyn Geometry>) -> Result<Transform, Box<dyn Error>> {
+        Transform::new_id(Object::new_id(), matrix, node)
+    }
+
+    pub fn new_id(
+        id: usize,
+        matrix: Matrix4,
+        node: Arc<dyn Geometry>,
+    ) -> Result<Transform, Box<dyn Error>> {
+        match matrix.inverse() {
+            Some(inverse) => Ok(Transform {
+                id,
+                matrix,
+                inverse,
+                node,
+            }),
+            None => Err("transformation matrix is not invertible".into()),
+        }
+    }
+
+    /// Transformation matrix from translation, rotation around an axis and scale.
+    /// The geometry is scaled first, then rotated and finally translated.
+    pub fn trs_matrix(
+        translation: Vector3,
+        rotation_axis: Vector3,
+        rotation_angle_radians: FSize,
+        scale: Vector3,
+    ) -> Matrix4 {
+        let identity = Matrix4::new(
+            Vector4::new(1.0, 0.0, 0.0, 0.0),
+            Vector4::new(0.0, 1.0, 0.0, 0.0),
+            Vector4::new(0.0, 0.0, 1.0, 0.0),
+            Vector4::new(0.0, 0.0, 0.0, 1.0),
+        );
+        let m = glm::ext::translate(&identity, translation);
+        let m = if rotation_angle_radians != 0.0 {
+            glm::ext::rotate(&m, rotation_angle_radians, rotation_axis)
+        } else {
+            m
+        };
+        glm::ext::scale(&m, scale)
+    }
+
+    /// Check whether the matrix can be inverted
+    pub fn is_invertible(matrix: &Matrix4) -> bool {
+        matrix.inverse().is_some()
+    }
+
+    fn transform_point(m: &Matrix4, p: Point3) -> Point3 {
+        (*m * Vector4::new(p.x, p.y, p.z, 1.0)).truncate(3)
+    }
+
+    fn transform_vector(m: &Matrix4, v: Vector3) -> Vector3 {
+        (*m * Vector4::new(v.x, v.y, v.z, 0.0)).truncate(3)
+    }
+
+    fn transform_normal(&self, n: Vector3) -> Vector3 {
+        glm::normalize(Transform::transform_vector(
+            &glm::transpose(&self.inverse),
+            n,
+        ))
+    }
+}
+
+impl Geometry for Transform {
+    fn get_id(&self) -> usize {
+        self.id
+    }
+
+    fn bounding_box(&self, time: Range<FSize>) -> Option<AABB> {
+        match self.node.bounding_box(time) {
+            Some(b) => {
+                let corner = |x: FSize, y: FSize, z: FSize| {
+                    Transform::transform_point(&self.matrix, Point3::new(x, y, z))
+                };
+                Some(
+                    AABB::new(
+                        corner(b.min.x, b.min.y, b.min.z),
+                        corner(b.max.x, b.max.y, b.max.z),
+                    )
+                    .or_vector(corner(b.max.x, b.min.y, b.min.z))
+                    .or_vector(corner(b.min.x, b.max.y, b.min.z))
+                    .or_vector(corner(b.min.x, b.min.y, b.max.z))
+                    .or_vector(corner(b.max.x, b.max.y, b.min.z))
+                    .or_vector(corner(b.max.x, b.min.y, b.max.z))
+                    .or_vector(corner(b.min.x, b.max.y, b.max.z)),
+                )
+            }
+            None => None,
+        }
+    }
+
+    fn hit(&self, ray: &Ray, t_range: Range<FSize>) -> Option<HitRecord> {
+        match self.node.hit(
+            &Ray::new_ray_with_attributes(
+                Transform::transform_point(&self.inverse, ray.origin),
+                Transform::transform_vector(&self.inverse, ray.direction),
+                ray,
+            ),
+            t_range,
+        ) {
+            Some(mut hit_record) => {
+                hit_record.position = Transform::transform_point(&self.matrix, hit_record.position);
+                hit_record.normal = self.transform_normal(hit_record.normal);
+                Some(hit_record)
+            }
+            None => None,
+        }
+    }
+
+    fn occluded(&self, ray: &Ray, t_range: Range<FSize>) -> bool {
+        self.node.occluded(
+            &Ray::new_ray_with_attributes(
+                Transform::transform_point(&self.inverse, ray.origin),
+                Transform::transform_vector(&self.inverse, ray.direction),
+                ray,
+            ),
+            t_range,
+        )
+    }
+
+    /// The probability density of the object space direction is scaled by the ratio of the solid angles.
+    /// For the linear map `A` and the unit direction `u` the ratio is `|det(A)| / |A * u|^3`.
+    fn pdf_value(&self, o: &Vector3, v: &Vector3) -> FSize {
+        let object_v = Transform::transform_vector(&self.inverse, *v);
+        let pdf = self
+            .node
+            .pdf_value(&Transform::transform_point(&self.inverse, *o), &object_v);
+        if pdf <= 0.0 {
+            return pdf;
+        }
+        let a = glm::Matrix3::new(
+            self.inverse.c0.truncate(3),
+            self.inverse.c1.truncate(3),
+            self.inverse.c2.truncate(3),
+        );
+        let length_ratio = glm::length(object_v) / glm::length(*v);
+        pdf * FSize::abs(a.determinant()) / (length_ratio * length_ratio * length_ratio)
+    }
+
+    fn random(&self, o: &Vector3) -> Vector3 {
+        Transform::transform_vector(
+            &self.matrix,
+            self.node
+                .random(&Transform::transform_point(&self.inverse, *o)),
+        )
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) -> Result<(), Box<dyn Error>> {
+        visitor.visit_instancing_transform(self)
+    }
+}
+
+#[cfg(test)]
+mod transform_test {
+    use super::*;
+    use crate::geometry::shape::{Cuboid, Sphere};
+    use crate::material::{Lambertian, NoMaterial};
+    use crate::test;
+    use crate::texture::ConstantTexture;
+    use crate::types::ColorRGBA;
+
+    fn white() -> Arc<Lambertian> {
+        Arc::new(Lambertian::new(Arc::new(ConstantTexture::new(
+            ColorRGBA::new(1.0, 1.0, 1.0, 1.0),
+        ))))
+    }
+
+    #[test]
+    fn bounding_box_test() {
+        let c = Cuboid::new(
+            Point3::new(-1.0, -1.0, -1.0)..Point3::new(1.0, 1.0, 1.0),
+            Arc::new(NoMaterial::new()),
+        );
+        let m = Transform::trs_matrix(
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            FSize::to_radians(30.0),
+            Vector3::new(2.0, 1.0, 1.0),
+        );
+        let i = Transform::new(m, Arc::new(c)).unwrap();
+        match i.bounding_box(0.0..0.0) {
+            Some(b) => {
+                test::assert_eq_vector3(&b.min, &Vector3::new(-1.232, -1.0, -1.866), 0.01);
+                test::assert_eq_vector3(&b.max, &Vector3::new(3.232, 1.0, 1.866), 0.01);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn new_not_invertible_test() {
+        let m = Transform::trs_matrix(
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            0.0,
+            Vector3::new(1.0, 0.0, 1.0),
+        );
+        let s = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, white());
+        assert!(Transform::new(m, Arc::new(s)).is_err());
+    }
+
+    #[test]
+    fn hit_test() {
+        let s = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, white());
+        let m = Transform::trs_matrix(
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            FSize::to_radians(45.0),
+            Vector3::new(2.0, 1.0, 1.0),
+        );
+        let i = Transform::new(m, Arc::new(s)).unwrap();
+        let ray1 = Ray::new_ray(Vector3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let ray2 = Ray::new_ray(Vector3::new(1.2, -1.2, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let ray3 = Ray::new_ray(Vector3::new(1.2, 1.2, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        match i.hit(&ray1, 0.0..10.0) {
+            Some(hit_record) => {
+                test::assert_eq_float(hit_record.t, 4.0, 0.001);
+                test::assert_eq_vector3(&hit_record.position, &Point3::new(0.0, 0.0, -1.0), 0.001);
+                test::assert_eq_vector3(&hit_record.normal, &Vector3::new(0.0, 0.0, -1.0), 0.001);
+            }
+            None => panic!("no result"),
+        }
+        match i.hit(&ray2, 0.0..10.0) {
+            Some(_) => panic!("unexpected hit"),
+            None => (),
+        }
+        match i.hit(&ray3, 0.0..10.0) {
+            Some(_) => (),
+            None => panic!("no result"),
+        }
+    }
+
+    #[test]
+    fn normal_test() {
+        let s = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, white());
+        let m = Transform::trs_matrix(
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            0.0,
+            Vector3::new(2.0, 1.0, 1.0),
+        );
+        let i = Transform::new(m, Arc::new(s)).unwrap();
+        let p = Point3::new(1.2, 0.0, -0.8);
+        let ray = Ray::new_ray(Vector3::new(p.x, p.y, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        match i.hit(&ray, 0.0..10.0) {
+            Some(hit_record) => {
+                test::assert_eq_vector3(&hit_record.position, &p, 0.001);
+                test::assert_eq_vector3(
+                    &hit_record.normal,
+                    &glm::normalize(Vector3::new(p.x / 4.0, p.y, p.z)),
+                    0.001,
+                );
+            }
+            None => panic!("no result"),
+        }
+    }
+
+    #[test]
+    fn pdf_value_test() {
+        let m = Transform::trs_matrix(
+            Vector3::new(0.0, 0.0, 5.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            0.0,
+            Vector3::new(2.0, 2.0, 2.0),
+        );
+        let i = Transform::new(
+            m,
+            Arc::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, white())),
+        )
+        .unwrap();
+        let s = Sphere::new(Point3::new(0.0, 0.0, 5.0), 2.0, white());
+        let o = Vector3::new(0.0, 0.0, 0.0);
+        let v = Vector3::new(0.1, 0.0, 1.0);
+        test::assert_eq_float(i.pdf_value(&o, &v), s.pdf_value(&o, &v), 0.001);
+        let d = i.random(&o);
+        assert!(i.hit(&Ray::new_ray(o, d), 0.001..FSize::MAX).is_some());
+    }
+}
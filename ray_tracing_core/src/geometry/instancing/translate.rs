@@ -52,6 +52,13 @@ impl Geometry for Translate {
         }
     }
 
+    fn occluded(&self, ray: &Ray, t_range: Range<FSize>) -> bool {
+        self.node.occluded(
+            &Ray::new_ray_with_attributes(ray.origin - self.offset, ray.direction, ray),
+            t_range,
+        )
+    }
+
     fn pdf_value(&self, o: &Vector3, v: &Vector3) -> FSize {
         self.node.pdf_value(&(*o - self.offset), v)
     }
@@ -76,7 +76,14 @@ impl Geometry for Cuboid {
     }
 
     fn hit(&self, ray: &Ray, t_range: Range<FSize>) -> Option<HitRecord> {
-        self.sides.hit(ray, t_range)
+        self.sides.hit(ray, t_range).map(|mut hit_record| {
+            hit_record.object_id = self.id;
+            hit_record
+        })
+    }
+
+    fn occluded(&self, ray: &Ray, t_range: Range<FSize>) -> bool {
+        self.sides.occluded(ray, t_range)
     }
 
     fn pdf_value(&self, o: &Vector3, v: &Vector3) -> FSize {
@@ -1,6 +1,6 @@
 use crate::core::object::Object;
 use crate::core::HitRecord;
-use crate::geometry::{Geometry, Visitor};
+use crate::geometry::{Geometry, TraversalStatistics, Visitor};
 use crate::material::Material;
 use crate::math::OrthoNormalBase;
 use crate::math::{Ray, AABB};
@@ -73,6 +73,7 @@ impl Geometry for MovableSphere {
     }
 
     fn hit(&self, ray: &Ray, t_range: Range<FSize>) -> Option<HitRecord> {
+        TraversalStatistics::count_intersection_test();
         let center = self.center(ray.time);
         let oc = ray.origin - center;
         let a = glm::dot(ray.direction, ray.direction);
@@ -93,6 +94,7 @@ impl Geometry for MovableSphere {
                     p,
                     n,
                     self.material.clone(),
+                    self.id,
                 );
             }
             if result.is_none() {
@@ -107,6 +109,7 @@ impl Geometry for MovableSphere {
                         p,
                         n,
                         self.material.clone(),
+                        self.id,
                     );
                 }
             }
@@ -115,6 +118,33 @@ impl Geometry for MovableSphere {
         None
     }
 
+    fn occluded(&self, ray: &Ray, t_range: Range<FSize>) -> bool {
+        TraversalStatistics::count_intersection_test();
+        let center = self.center(ray.time);
+        let oc = ray.origin - center;
+        let a = glm::dot(ray.direction, ray.direction);
+        let b = 2.0 * glm::dot(oc, ray.direction);
+        let c = glm::dot(oc, oc) - self.radius * self.radius;
+
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant <= 0.0 {
+            return false;
+        }
+        for t in [
+            (-b - FSize::sqrt(discriminant)) / (2.0 * a),
+            (-b + FSize::sqrt(discriminant)) / (2.0 * a),
+        ] {
+            if t_range.contains(&t) {
+                let p = ray.point_at(t);
+                let n = (p - center) / self.radius;
+                if HitRecord::check_alpha(&TextureCoordinate::from_sphere(&n), &p, &self.material) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
     fn pdf_value(&self, o: &Vector3, v: &Vector3) -> FSize {
         match self.hit(&Ray::new_ray(*o, *v), 0.001..FSize::MAX) {
             Some(_) => {
@@ -1,6 +1,6 @@
 use crate::core::object::Object;
 use crate::core::HitRecord;
-use crate::geometry::{Geometry, Visitor};
+use crate::geometry::{Geometry, TraversalStatistics, Visitor};
 use crate::material::Material;
 use crate::math::OrthoNormalBase;
 use crate::math::{Ray, AABB};
@@ -52,6 +52,7 @@ impl Geometry for Sphere {
     }
 
     fn hit(&self, ray: &Ray, t_range: Range<FSize>) -> Option<HitRecord> {
+        TraversalStatistics::count_intersection_test();
         let oc = ray.origin - self.center;
         let a = glm::dot(ray.direction, ray.direction);
         let b = 2.0 * glm::dot(oc, ray.direction);
@@ -71,6 +72,7 @@ impl Geometry for Sphere {
                     p,
                     n,
                     self.material.clone(),
+                    self.id,
                 );
             }
             if result.is_none() {
@@ -85,6 +87,7 @@ impl Geometry for Sphere {
                         p,
                         n,
                         self.material.clone(),
+                        self.id,
                     );
                 }
             }
@@ -93,6 +96,32 @@ impl Geometry for Sphere {
         None
     }
 
+    fn occluded(&self, ray: &Ray, t_range: Range<FSize>) -> bool {
+        TraversalStatistics::count_intersection_test();
+        let oc = ray.origin - self.center;
+        let a = glm::dot(ray.direction, ray.direction);
+        let b = 2.0 * glm::dot(oc, ray.direction);
+        let c = glm::dot(oc, oc) - self.radius * self.radius;
+
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant <= 0.0 {
+            return false;
+        }
+        for t in [
+            (-b - FSize::sqrt(discriminant)) / (2.0 * a),
+            (-b + FSize::sqrt(discriminant)) / (2.0 * a),
+        ] {
+            if t_range.contains(&t) {
+                let p = ray.point_at(t);
+                let n = (p - self.center) / self.radius;
+                if HitRecord::check_alpha(&TextureCoordinate::from_sphere(&n), &p, &self.material) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
     fn pdf_value(&self, o: &Vector3, v: &Vector3) -> FSize {
         match self.hit(&Ray::new_ray(*o, *v), 0.001..FSize::MAX) {
             Some(_) => {
@@ -166,4 +195,32 @@ mod sphere_test {
             None => (),
         }
     }
+
+    #[test]
+    fn occluded_test() {
+        let s = Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            1.0,
+            Arc::new(Metal::new(
+                0.0,
+                Arc::new(ConstantTexture::new(ColorRGBA::new(1.0, 1.0, 1.0, 1.0))),
+            )),
+        );
+        let transparent = Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            1.0,
+            Arc::new(Metal::new(
+                0.0,
+                Arc::new(ConstantTexture::new(ColorRGBA::new(1.0, 1.0, 1.0, 0.0))),
+            )),
+        );
+        let ray1 = Ray::new_ray(Vector3::new(0.0, -5.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+        let ray2 = Ray::new_ray(Vector3::new(2.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+        assert!(s.occluded(&ray1, 0.0..10.0));
+        assert!(s.occluded(&ray1, 5.0..10.0));
+        assert!(!s.occluded(&ray1, 0.0..3.0));
+        assert!(!s.occluded(&ray1, 10.0..20.0));
+        assert!(!s.occluded(&ray2, 0.0..10.0));
+        assert!(!transparent.occluded(&ray1, 0.0..10.0));
+    }
 }
@@ -0,0 +1,267 @@
+use crate::core::object::Object;
+use crate::core::HitRecord;
+use crate::geometry::{Geometry, TraversalStatistics, Visitor};
+use crate::material::Material;
+use crate::math::{Ray, AABB};
+use crate::random;
+use crate::types::{FSize, Point3, TextureCoordinate, Vector3};
+use std::error::Error;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Triangle shape
+///
+/// Object that represents a single flat shaded triangle in the scene.
+/// The front face is defined by the counterclockwise winding of the vertices.
+///
+/// Ray - Triangle intersection ([Möller–Trumbore](https://en.wikipedia.org/wiki/M%C3%B6ller%E2%80%93Trumbore_intersection_algorithm))
+///
+/// Triangle:       p(u, v) = V0 + u * (V1-V0) + v * (V2-V0)      `u >= 0`, `v >= 0`, `u + v <= 1`
+/// Ray:            p(t) = A + B * t                              `A`: origin, `B`: direction
+/// Intersection:   A + B * t = V0 + u * (V1-V0) + v * (V2-V0)
+pub struct Triangle {
+    pub id: usize,
+    pub vertices: [Point3; 3],
+    pub material: Arc<dyn Material>,
+}
+
+impl Triangle {
+    pub fn new(vertices: [Point3; 3], material: Arc<dyn Material>) -> Triangle {
+        Triangle::new_id(Object::new_id(), vertices, material)
+    }
+
+    pub fn new_id(id: usize, vertices: [Point3; 3], material: Arc<dyn Material>) -> Triangle {
+        Triangle {
+            id,
+            vertices,
+            material,
+        }
+    }
+
+    /// Intersect a ray with the triangle `v0`, `v1`, `v2`.
+    /// Returns the ray parameter `t` and the barycentric coordinates `u` and `v` of the intersection point.
+    pub fn intersect(
+        ray: &Ray,
+        v0: &Point3,
+        v1: &Point3,
+        v2: &Point3,
+        t_range: &Range<FSize>,
+    ) -> Option<(FSize, FSize, FSize)> {
+        TraversalStatistics::count_intersection_test();
+        let e1 = *v1 - *v0;
+        let e2 = *v2 - *v0;
+        let p = glm::cross(ray.direction, e2);
+        let det = glm::dot(e1, p);
+        if det.abs() < 1.0e-12 {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+        let s = ray.origin - *v0;
+        let u = glm::dot(s, p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = glm::cross(s, e1);
+        let v = glm::dot(ray.direction, q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = glm::dot(e2, q) * inv_det;
+        if t_range.contains(&t) {
+            Some((t, u, v))
+        } else {
+            None
+        }
+    }
+
+    /// Bounding box of the triangle `v0`, `v1`, `v2`.
+    /// The box is slightly enlarged, so that it has a volume even if the triangle is axis aligned.
+    pub fn triangle_bounding_box(v0: &Point3, v1: &Point3, v2: &Point3) -> AABB {
+        let b = AABB::new(*v0, *v1).or_vector(*v2);
+        AABB::new(
+            b.min - Vector3::new(0.0001, 0.0001, 0.0001),
+            b.max + Vector3::new(0.0001, 0.0001, 0.0001),
+        )
+    }
+
+    /// Area of the triangle `v0`, `v1`, `v2`
+    pub fn triangle_area(v0: &Point3, v1: &Point3, v2: &Point3) -> FSize {
+        glm::length(glm::cross(*v1 - *v0, *v2 - *v0)) * 0.5
+    }
+
+    /// Uniformly distributed random point on the triangle `v0`, `v1`, `v2`
+    pub fn triangle_random_point(v0: &Point3, v1: &Point3, v2: &Point3) -> Point3 {
+        let su = FSize::sqrt(random::generate_size());
+        let r2 = random::generate_size();
+        *v0 * (1.0 - su) + *v1 * (su * (1.0 - r2)) + *v2 * (su * r2)
+    }
+
+    fn face_normal(&self) -> Vector3 {
+        glm::normalize(glm::cross(
+            self.vertices[1] - self.vertices[0],
+            self.vertices[2] - self.vertices[0],
+        ))
+    }
+}
+
+impl Geometry for Triangle {
+    fn get_id(&self) -> usize {
+        self.id
+    }
+
+    fn bounding_box(&self, _: Range<FSize>) -> Option<AABB> {
+        Some(Triangle::triangle_bounding_box(
+            &self.vertices[0],
+            &self.vertices[1],
+            &self.vertices[2],
+        ))
+    }
+
+    fn hit(&self, ray: &Ray, t_range: Range<FSize>) -> Option<HitRecord> {
+        let (t, u, v) = Triangle::intersect(
+            ray,
+            &self.vertices[0],
+            &self.vertices[1],
+            &self.vertices[2],
+            &t_range,
+        )?;
+        HitRecord::check_alpha_and_create(
+            ray,
+            t,
+            TextureCoordinate::from_uv(u, v),
+            ray.point_at(t),
+            self.face_normal(),
+            self.material.clone(),
+            self.id,
+        )
+    }
+
+    fn occluded(&self, ray: &Ray, t_range: Range<FSize>) -> bool {
+        match Triangle::intersect(
+            ray,
+            &self.vertices[0],
+            &self.vertices[1],
+            &self.vertices[2],
+            &t_range,
+        ) {
+            Some((t, u, v)) => HitRecord::check_alpha(
+                &TextureCoordinate::from_uv(u, v),
+                &ray.point_at(t),
+                &self.material,
+            ),
+            None => false,
+        }
+    }
+
+    fn pdf_value(&self, o: &Vector3, v: &Vector3) -> FSize {
+        match self.hit(&Ray::new_ray(*o, *v), 0.001..FSize::MAX) {
+            Some(hit_record) => {
+                let area = Triangle::triangle_area(
+                    &self.vertices[0],
+                    &self.vertices[1],
+                    &self.vertices[2],
+                );
+                let distance_squared = hit_record.t * hit_record.t * glm::dot(*v, *v);
+                let cosine = FSize::abs(glm::dot(*v, hit_record.normal) / glm::length(*v));
+                distance_squared / (cosine * area)
+            }
+            None => 0.0,
+        }
+    }
+
+    fn random(&self, o: &Vector3) -> Vector3 {
+        Triangle::triangle_random_point(&self.vertices[0], &self.vertices[1], &self.vertices[2])
+            - *o
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) -> Result<(), Box<dyn Error>> {
+        visitor.visit_shape_triangle(self)
+    }
+}
+
+#[cfg(test)]
+mod triangle_test {
+    use super::*;
+    use crate::material::{Lambertian, NoMaterial};
+    use crate::test;
+    use crate::texture::ConstantTexture;
+    use crate::types::ColorRGBA;
+
+    #[test]
+    fn bounding_box_test() {
+        let t = Triangle::new(
+            [
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+            ],
+            Arc::new(NoMaterial::new()),
+        );
+        let b = t.bounding_box(0.0..0.0);
+        match b {
+            Some(b) => {
+                test::assert_eq_vector3(&b.min, &Vector3::new(0.0, 0.0, 0.0), 0.01);
+                test::assert_eq_vector3(&b.max, &Vector3::new(1.0, 1.0, 0.0), 0.01);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn hit_test() {
+        let t = Triangle::new(
+            [
+                Point3::new(0.0, 0.0, 0.5),
+                Point3::new(1.0, 0.0, 0.5),
+                Point3::new(0.0, 1.0, 0.5),
+            ],
+            Arc::new(Lambertian::new(Arc::new(ConstantTexture::new(
+                ColorRGBA::new(1.0, 1.0, 1.0, 1.0),
+            )))),
+        );
+        let ray1 = Ray::new_ray(Vector3::new(0.25, 0.25, -1.0), Vector3::new(0.0, 0.0, 1.0));
+        let ray2 = Ray::new_ray(Vector3::new(0.75, 0.75, -1.0), Vector3::new(0.0, 0.0, 1.0));
+        let ray3 = Ray::new_ray(Vector3::new(-1.0, 0.25, 0.5), Vector3::new(1.0, 0.0, 0.0));
+        match t.hit(&ray1, 0.0..10.0) {
+            Some(hit_record) => {
+                test::assert_eq_float(hit_record.t, 1.5, 0.001);
+                test::assert_eq_float(hit_record.uv.u, 0.25, 0.001);
+                test::assert_eq_float(hit_record.uv.v, 0.25, 0.001);
+                test::assert_eq_vector3(&hit_record.normal, &Vector3::new(0.0, 0.0, 1.0), 0.001);
+            }
+            None => panic!("no result"),
+        }
+        match t.hit(&ray1, 0.0..1.0) {
+            Some(_) => panic!("unexpected hit"),
+            None => (),
+        }
+        match t.hit(&ray2, 0.0..10.0) {
+            Some(_) => panic!("unexpected hit"),
+            None => (),
+        }
+        match t.hit(&ray3, 0.0..10.0) {
+            Some(_) => panic!("unexpected hit"),
+            None => (),
+        }
+    }
+
+    #[test]
+    fn pdf_value_test() {
+        let t = Triangle::new(
+            [
+                Point3::new(-1.0, -1.0, 1.0),
+                Point3::new(1.0, -1.0, 1.0),
+                Point3::new(0.0, 1.0, 1.0),
+            ],
+            Arc::new(Lambertian::new(Arc::new(ConstantTexture::new(
+                ColorRGBA::new(1.0, 1.0, 1.0, 1.0),
+            )))),
+        );
+        let o = Vector3::new(0.0, 0.0, 0.0);
+        test::assert_eq_float(t.pdf_value(&o, &Vector3::new(0.0, 0.0, 1.0)), 0.5, 0.001);
+        test::assert_eq_float(t.pdf_value(&o, &Vector3::new(0.0, 0.0, -1.0)), 0.0, 0.001);
+        let d = t.random(&o);
+        test::assert_eq_float(d.z, 1.0, 0.001);
+        assert!(t.pdf_value(&o, &d) > 0.0);
+    }
+}
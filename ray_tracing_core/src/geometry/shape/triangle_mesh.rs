@@ -0,0 +1,446 @@
+use crate::core::object::Object;
+use crate::core::HitRecord;
+use crate::geometry::shape::Triangle;
+use crate::geometry::{Geometry, Visitor};
+use crate::material::Material;
+use crate::math::{Ray, AABB};
+use crate::random;
+use crate::types::{FSize, Point3, TextureCoordinate, Vector2, Vector3};
+use std::cmp::Ordering;
+use std::error::Error;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Maximum number of triangles in a leaf of the internal bounding volume hierarchy
+const MAXIMUM_LEAF_SIZE: usize = 4;
+
+/// Node of the internal bounding volume hierarchy of a triangle mesh
+///
+/// The left child of an inner node directly follows the node.
+/// A leaf references the range `start..start+count` of the triangle order.
+struct MeshNode {
+    aabb: AABB,
+    start: usize,
+    count: usize,
+    right: usize,
+}
+
+/// Indexed triangle mesh
+///
+/// Object that represents a mesh of triangles which share a vertex buffer.
+/// Each triangle is defined by 3 indices into the vertex buffer.
+/// If per vertex normal vectors are specified, the normal vector of a hit is interpolated (smooth shading),
+/// else the normal vector of the triangle is used.
+/// If per vertex texture coordinates are specified, the texture coordinate of a hit is interpolated,
+/// else the barycentric coordinates of the hit are used.
+/// The triangles are organized in an internal bounding volume hierarchy.
+pub struct TriangleMesh {
+    pub id: usize,
+    pub vertices: Vec<Point3>,
+    pub normals: Vec<Vector3>,
+    pub uvs: Vec<Vector2>,
+    pub indices: Vec<[usize; 3]>,
+    pub material: Arc<dyn Material>,
+    nodes: Vec<MeshNode>,
+    order: Vec<usize>,
+    area_cdf: Vec<FSize>,
+}
+
+impl TriangleMesh {
+    pub fn new(
+        vertices: Vec<Point3>,
+        normals: Vec<Vector3>,
+        uvs: Vec<Vector2>,
+        indices: Vec<[usize; 3]>,
+        material: Arc<dyn Material>,
+    ) -> TriangleMesh {
+        TriangleMesh::new_id(Object::new_id(), vertices, normals, uvs, indices, material)
+    }
+
+    pub fn new_id(
+        id: usize,
+        vertices: Vec<Point3>,
+        normals: Vec<Vector3>,
+        uvs: Vec<Vector2>,
+        indices: Vec<[usize; 3]>,
+        material: Arc<dyn Material>,
+    ) -> TriangleMesh {
+        let mut mesh = TriangleMesh {
+            id,
+            vertices,
+            normals,
+            uvs,
+            indices,
+            material,
+            nodes: Vec::default(),
+            order: Vec::default(),
+            area_cdf: Vec::default(),
+        };
+        let mut area = 0.0;
+        for i in 0..mesh.indices.len() {
+            let (v0, v1, v2) = mesh.triangle(i);
+            area += Triangle::triangle_area(v0, v1, v2);
+            mesh.area_cdf.push(area);
+        }
+        let mut order: Vec<usize> = (0..mesh.indices.len()).collect();
+        if !order.is_empty() {
+            let count = order.len();
+            mesh.build_node(&mut order, 0, count);
+        }
+        mesh.order = order;
+        mesh
+    }
+
+    /// Number of triangles
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Total surface area of all triangles
+    pub fn area(&self) -> FSize {
+        match self.area_cdf.last() {
+            Some(area) => *area,
+            None => 0.0,
+        }
+    }
+
+    fn triangle(&self, i: usize) -> (&Point3, &Point3, &Point3) {
+        let [i0, i1, i2] = self.indices[i];
+        (&self.vertices[i0], &self.vertices[i1], &self.vertices[i2])
+    }
+
+    fn triangle_bounding_box(&self, i: usize) -> AABB {
+        let (v0, v1, v2) = self.triangle(i);
+        Triangle::triangle_bounding_box(v0, v1, v2)
+    }
+
+    fn centroid(&self, i: usize) -> Point3 {
+        let (v0, v1, v2) = self.triangle(i);
+        (*v0 + *v1 + *v2) / 3.0
+    }
+
+    fn build_node(&mut self, order: &mut [usize], start: usize, count: usize) -> usize {
+        let node_index = self.nodes.len();
+        let mut aabb = self.triangle_bounding_box(order[start]);
+        let first_centroid = self.centroid(order[start]);
+        let mut centroid_box = AABB::new(first_centroid, first_centroid);
+        for &i in order[start + 1..start + count].iter() {
+            aabb = aabb | self.triangle_bounding_box(i);
+            centroid_box = centroid_box.or_vector(self.centroid(i));
+        }
+        self.nodes.push(MeshNode {
+            aabb,
+            start,
+            count,
+            right: 0,
+        });
+        if count <= MAXIMUM_LEAF_SIZE {
+            return node_index;
+        }
+
+        let extent = centroid_box.max - centroid_box.min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+        order[start..start + count].sort_by(|a, b| {
+            self.centroid(*a)[axis]
+                .partial_cmp(&self.centroid(*b)[axis])
+                .unwrap_or(Ordering::Equal)
+        });
+        let left_count = count / 2;
+        self.build_node(order, start, left_count);
+        let right = self.build_node(order, start + left_count, count - left_count);
+        self.nodes[node_index].count = 0;
+        self.nodes[node_index].right = right;
+        node_index
+    }
+
+    /// Find the closest intersection of a ray with the triangles.
+    /// Returns the index of the triangle, the ray parameter `t` and the barycentric coordinates of the hit.
+    fn closest_intersection(
+        &self,
+        ray: &Ray,
+        t_range: Range<FSize>,
+    ) -> Option<(usize, FSize, FSize, FSize)> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        let mut result = None;
+        let mut closest_so_far = t_range.end;
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if !node.aabb.hit(ray, t_range.start..closest_so_far) {
+                continue;
+            }
+            if node.count > 0 {
+                for &i in self.order[node.start..node.start + node.count].iter() {
+                    let (v0, v1, v2) = self.triangle(i);
+                    if let Some((t, u, v)) =
+                        Triangle::intersect(ray, v0, v1, v2, &(t_range.start..closest_so_far))
+                    {
+                        closest_so_far = t;
+                        result = Some((i, t, u, v));
+                    }
+                }
+            } else {
+                stack.push(node.right);
+                stack.push(node_index + 1);
+            }
+        }
+        result
+    }
+
+    fn face_normal(&self, i: usize) -> Vector3 {
+        let (v0, v1, v2) = self.triangle(i);
+        glm::normalize(glm::cross(*v1 - *v0, *v2 - *v0))
+    }
+
+    fn texture_coordinate(&self, i: usize, u: FSize, v: FSize) -> TextureCoordinate {
+        if self.uvs.is_empty() {
+            TextureCoordinate::from_uv(u, v)
+        } else {
+            let [i0, i1, i2] = self.indices[i];
+            let uv = self.uvs[i0] * (1.0 - u - v) + self.uvs[i1] * u + self.uvs[i2] * v;
+            TextureCoordinate::from_uv(uv.x, uv.y)
+        }
+    }
+
+    fn create_hit_record(
+        &self,
+        ray: &Ray,
+        i: usize,
+        t: FSize,
+        u: FSize,
+        v: FSize,
+    ) -> Option<HitRecord> {
+        let [i0, i1, i2] = self.indices[i];
+        let w = 1.0 - u - v;
+        let normal = if self.normals.is_empty() {
+            self.face_normal(i)
+        } else {
+            glm::normalize(self.normals[i0] * w + self.normals[i1] * u + self.normals[i2] * v)
+        };
+        HitRecord::check_alpha_and_create(
+            ray,
+            t,
+            self.texture_coordinate(i, u, v),
+            ray.point_at(t),
+            normal,
+            self.material.clone(),
+            self.id,
+        )
+    }
+}
+
+impl Geometry for TriangleMesh {
+    fn get_id(&self) -> usize {
+        self.id
+    }
+
+    fn bounding_box(&self, _: Range<FSize>) -> Option<AABB> {
+        self.nodes.first().map(|node| node.aabb.clone())
+    }
+
+    fn hit(&self, ray: &Ray, t_range: Range<FSize>) -> Option<HitRecord> {
+        let mut t_start = t_range.start;
+        loop {
+            let (i, t, u, v) = self.closest_intersection(ray, t_start..t_range.end)?;
+            match self.create_hit_record(ray, i, t, u, v) {
+                Some(hit_record) => return Some(hit_record),
+                // the hit was discarded by the alpha channel of the material, continue behind the hit
+                None => t_start = t + 0.0001,
+            }
+        }
+    }
+
+    fn occluded(&self, ray: &Ray, t_range: Range<FSize>) -> bool {
+        if self.nodes.is_empty() {
+            return false;
+        }
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if !node.aabb.hit(ray, t_range.clone()) {
+                continue;
+            }
+            if node.count > 0 {
+                for &i in self.order[node.start..node.start + node.count].iter() {
+                    let (v0, v1, v2) = self.triangle(i);
+                    if let Some((t, u, v)) = Triangle::intersect(ray, v0, v1, v2, &t_range) {
+                        if HitRecord::check_alpha(
+                            &self.texture_coordinate(i, u, v),
+                            &ray.point_at(t),
+                            &self.material,
+                        ) {
+                            return true;
+                        }
+                    }
+                }
+            } else {
+                stack.push(node.right);
+                stack.push(node_index + 1);
+            }
+        }
+        false
+    }
+
+    fn pdf_value(&self, o: &Vector3, v: &Vector3) -> FSize {
+        match self.closest_intersection(&Ray::new_ray(*o, *v), 0.001..FSize::MAX) {
+            Some((i, t, _, _)) => {
+                let distance_squared = t * t * glm::dot(*v, *v);
+                let cosine = FSize::abs(glm::dot(*v, self.face_normal(i)) / glm::length(*v));
+                distance_squared / (cosine * self.area())
+            }
+            None => 0.0,
+        }
+    }
+
+    fn random(&self, o: &Vector3) -> Vector3 {
+        if self.is_empty() {
+            return Vector3::new(1.0, 0.0, 0.0);
+        }
+        let a = random::generate_size() * self.area();
+        let i = usize::min(
+            self.area_cdf.partition_point(|area| *area < a),
+            self.len() - 1,
+        );
+        let (v0, v1, v2) = self.triangle(i);
+        Triangle::triangle_random_point(v0, v1, v2) - *o
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor) -> Result<(), Box<dyn Error>> {
+        visitor.visit_shape_triangle_mesh(self)
+    }
+}
+
+#[cfg(test)]
+mod triangle_mesh_test {
+    use super::*;
+    use crate::material::{Lambertian, NoMaterial};
+    use crate::test;
+    use crate::texture::ConstantTexture;
+    use crate::types::ColorRGBA;
+
+    fn create_grid(n: usize, material: Arc<dyn Material>) -> TriangleMesh {
+        let mut vertices = Vec::default();
+        let mut normals = Vec::default();
+        let mut uvs = Vec::default();
+        for j in 0..=n {
+            for i in 0..=n {
+                let x = i as FSize / n as FSize;
+                let y = j as FSize / n as FSize;
+                vertices.push(Point3::new(x, y, 0.0));
+                normals.push(Vector3::new(x - 0.5, 0.0, 1.0));
+                uvs.push(Vector2::new(x, y));
+            }
+        }
+        let mut indices = Vec::default();
+        for j in 0..n {
+            for i in 0..n {
+                let i0 = j * (n + 1) + i;
+                indices.push([i0, i0 + 1, i0 + n + 2]);
+                indices.push([i0, i0 + n + 2, i0 + n + 1]);
+            }
+        }
+        TriangleMesh::new(vertices, normals, uvs, indices, material)
+    }
+
+    #[test]
+    fn bounding_box_test() {
+        let m = create_grid(4, Arc::new(NoMaterial::new()));
+        assert_eq!(m.len(), 32);
+        let b = m.bounding_box(0.0..0.0);
+        match b {
+            Some(b) => {
+                test::assert_eq_vector3(&b.min, &Vector3::new(0.0, 0.0, 0.0), 0.01);
+                test::assert_eq_vector3(&b.max, &Vector3::new(1.0, 1.0, 0.0), 0.01);
+            }
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn hit_test() {
+        let m = create_grid(
+            4,
+            Arc::new(Lambertian::new(Arc::new(ConstantTexture::new(
+                ColorRGBA::new(1.0, 1.0, 1.0, 1.0),
+            )))),
+        );
+        let ray1 = Ray::new_ray(Vector3::new(0.3, 0.6, 1.0), Vector3::new(0.0, 0.0, -1.0));
+        let ray2 = Ray::new_ray(Vector3::new(1.5, 0.5, 1.0), Vector3::new(0.0, 0.0, -1.0));
+        let ray3 = Ray::new_ray(Vector3::new(0.5, 0.5, 1.0), Vector3::new(0.0, 0.0, 1.0));
+        match m.hit(&ray1, 0.0..10.0) {
+            Some(hit_record) => {
+                test::assert_eq_float(hit_record.t, 1.0, 0.001);
+                test::assert_eq_vector3(&hit_record.position, &Point3::new(0.3, 0.6, 0.0), 0.001);
+                test::assert_eq_float(hit_record.uv.u, 0.3, 0.001);
+                test::assert_eq_float(hit_record.uv.v, 0.6, 0.001);
+                test::assert_eq_vector3(
+                    &hit_record.normal,
+                    &glm::normalize(Vector3::new(-0.2, 0.0, 1.0)),
+                    0.001,
+                );
+            }
+            None => panic!("no result"),
+        }
+        match m.hit(&ray1, 2.0..10.0) {
+            Some(_) => panic!("unexpected hit"),
+            None => (),
+        }
+        match m.hit(&ray2, 0.0..10.0) {
+            Some(_) => panic!("unexpected hit"),
+            None => (),
+        }
+        match m.hit(&ray3, 0.0..10.0) {
+            Some(_) => panic!("unexpected hit"),
+            None => (),
+        }
+    }
+
+    #[test]
+    fn pdf_value_test() {
+        let m = create_grid(2, Arc::new(NoMaterial::new()));
+        test::assert_eq_float(m.area(), 1.0, 0.001);
+        let o = Vector3::new(0.5, 0.5, 1.0);
+        test::assert_eq_float(m.pdf_value(&o, &Vector3::new(0.0, 0.0, -1.0)), 1.0, 0.001);
+        test::assert_eq_float(m.pdf_value(&o, &Vector3::new(0.0, 0.0, 1.0)), 0.0, 0.001);
+        for _ in 0..10 {
+            let d = m.random(&o);
+            test::assert_eq_float(d.z, -1.0, 0.001);
+            test::assert_in_range(d.x, -0.5..0.5);
+            test::assert_in_range(d.y, -0.5..0.5);
+        }
+    }
+
+    #[test]
+    fn occluded_test() {
+        let m = create_grid(
+            4,
+            Arc::new(Lambertian::new(Arc::new(ConstantTexture::new(
+                ColorRGBA::new(1.0, 1.0, 1.0, 1.0),
+            )))),
+        );
+        let transparent = create_grid(
+            4,
+            Arc::new(Lambertian::new(Arc::new(ConstantTexture::new(
+                ColorRGBA::new(1.0, 1.0, 1.0, 0.0),
+            )))),
+        );
+        let ray1 = Ray::new_ray(Vector3::new(0.3, 0.6, 1.0), Vector3::new(0.0, 0.0, -1.0));
+        let ray2 = Ray::new_ray(Vector3::new(1.5, 0.5, 1.0), Vector3::new(0.0, 0.0, -1.0));
+        assert!(m.occluded(&ray1, 0.0..10.0));
+        assert!(!m.occluded(&ray1, 2.0..10.0));
+        assert!(!m.occluded(&ray2, 0.0..10.0));
+        assert!(!transparent.occluded(&ray1, 0.0..10.0));
+    }
+}
@@ -1,6 +1,6 @@
 use crate::core::object::Object;
 use crate::core::HitRecord;
-use crate::geometry::{Geometry, Visitor};
+use crate::geometry::{Geometry, TraversalStatistics, Visitor};
 use crate::material::Material;
 use crate::math::{Ray, AABB};
 use crate::random;
@@ -51,6 +51,7 @@ impl Geometry for XZRect {
     }
 
     fn hit(&self, ray: &Ray, t_range: Range<FSize>) -> Option<HitRecord> {
+        TraversalStatistics::count_intersection_test();
         let t = (self.k - ray.origin.y) / ray.direction.y;
         if t < t_range.start || t > t_range.end {
             return None;
@@ -74,6 +75,32 @@ impl Geometry for XZRect {
             ray.point_at(t),
             Vector3::new(0.0, 1.0, 0.0),
             self.material.clone(),
+            self.id,
+        )
+    }
+
+    fn occluded(&self, ray: &Ray, t_range: Range<FSize>) -> bool {
+        TraversalStatistics::count_intersection_test();
+        let t = (self.k - ray.origin.y) / ray.direction.y;
+        if t < t_range.start || t > t_range.end {
+            return false;
+        }
+
+        let x = ray.origin.x + t * ray.direction.x;
+        let z = ray.origin.z + t * ray.direction.z;
+        if x < self.rect.start.0
+            || x > self.rect.end.0
+            || z < self.rect.start.1
+            || z > self.rect.end.1
+        {
+            return false;
+        }
+
+        let uv = self.calculate_uv(x, z);
+        HitRecord::check_alpha(
+            &TextureCoordinate::from_uv(uv.0, uv.1),
+            &ray.point_at(t),
+            &self.material,
         )
     }
 
@@ -1,6 +1,6 @@
 use crate::core::object::Object;
 use crate::core::HitRecord;
-use crate::geometry::{Geometry, Visitor};
+use crate::geometry::{Geometry, TraversalStatistics, Visitor};
 use crate::material::Material;
 use crate::math::{Ray, AABB};
 use crate::random;
@@ -51,6 +51,7 @@ impl Geometry for YZRect {
     }
 
     fn hit(&self, ray: &Ray, t_range: Range<FSize>) -> Option<HitRecord> {
+        TraversalStatistics::count_intersection_test();
         let t = (self.k - ray.origin.x) / ray.direction.x;
         if t < t_range.start || t > t_range.end {
             return None;
@@ -74,6 +75,32 @@ impl Geometry for YZRect {
             ray.point_at(t),
             Vector3::new(1.0, 0.0, 0.0),
             self.material.clone(),
+            self.id,
+        )
+    }
+
+    fn occluded(&self, ray: &Ray, t_range: Range<FSize>) -> bool {
+        TraversalStatistics::count_intersection_test();
+        let t = (self.k - ray.origin.x) / ray.direction.x;
+        if t < t_range.start || t > t_range.end {
+            return false;
+        }
+
+        let y = ray.origin.y + t * ray.direction.y;
+        let z = ray.origin.z + t * ray.direction.z;
+        if y < self.rect.start.0
+            || y > self.rect.end.0
+            || z < self.rect.start.1
+            || z > self.rect.end.1
+        {
+            return false;
+        }
+
+        let uv = self.calculate_uv(y, z);
+        HitRecord::check_alpha(
+            &TextureCoordinate::from_uv(uv.0, uv.1),
+            &ray.point_at(t),
+            &self.material,
         )
     }
 
@@ -0,0 +1,159 @@
+#[cfg(feature = "traversal_statistics")]
+use std::cell::Cell;
+#[cfg(feature = "traversal_statistics")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Number of the intersection tests of the current thread
+///
+/// The counters are used by the debug views of the traversal cost of the bounding volume hierarchies.
+/// They are only compiled with the feature `traversal_statistics`, otherwise the tests aren't counted
+/// and the ray queries aren't instrumented. With the feature, the counters are only incremented
+/// while a thread runs [`TraversalStatistics::count`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TraversalStatistics {
+    /// Number of intersection tests with bounding boxes
+    pub bounding_box_tests: usize,
+
+    /// Number of intersection tests with primitives like spheres, rectangles and triangles
+    pub intersection_tests: usize,
+}
+
+/// Number of the threads, which run [`TraversalStatistics::count`]
+#[cfg(feature = "traversal_statistics")]
+static COUNTING_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(feature = "traversal_statistics")]
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+
+    static TRAVERSAL_STATISTICS: Cell<TraversalStatistics> = const {
+        Cell::new(TraversalStatistics {
+            bounding_box_tests: 0,
+            intersection_tests: 0,
+        })
+    };
+}
+
+impl TraversalStatistics {
+    /// Counters of the current thread
+    #[cfg(feature = "traversal_statistics")]
+    pub fn get() -> TraversalStatistics {
+        TRAVERSAL_STATISTICS.with(|statistics| statistics.get())
+    }
+
+    /// Counters of the current thread, which are always 0 without the feature `traversal_statistics`
+    #[cfg(not(feature = "traversal_statistics"))]
+    pub fn get() -> TraversalStatistics {
+        TraversalStatistics::default()
+    }
+
+    /// Sets the counters of the current thread to 0
+    pub fn reset() {
+        #[cfg(feature = "traversal_statistics")]
+        TRAVERSAL_STATISTICS.with(|statistics| statistics.set(TraversalStatistics::default()));
+    }
+
+    /// Runs `f` with counting enabled on the current thread.
+    /// Returns the result of `f` and the number of the tests, which are done by `f`.
+    #[cfg(feature = "traversal_statistics")]
+    pub fn count<T>(f: impl FnOnce() -> T) -> (T, TraversalStatistics) {
+        TraversalStatistics::reset();
+        COUNTING_THREADS.fetch_add(1, Ordering::Relaxed);
+        let previous = COUNTING.with(|counting| counting.replace(true));
+        let result = f();
+        COUNTING.with(|counting| counting.set(previous));
+        COUNTING_THREADS.fetch_sub(1, Ordering::Relaxed);
+        (result, TraversalStatistics::get())
+    }
+
+    /// Runs `f`. Without the feature `traversal_statistics` the number of the tests is always 0.
+    #[cfg(not(feature = "traversal_statistics"))]
+    pub fn count<T>(f: impl FnOnce() -> T) -> (T, TraversalStatistics) {
+        (f(), TraversalStatistics::default())
+    }
+
+    #[cfg(feature = "traversal_statistics")]
+    #[inline]
+    fn increment(f: impl FnOnce(&mut TraversalStatistics)) {
+        if COUNTING_THREADS.load(Ordering::Relaxed) == 0
+            || !COUNTING.with(|counting| counting.get())
+        {
+            return;
+        }
+        TRAVERSAL_STATISTICS.with(|statistics| {
+            let mut s = statistics.get();
+            f(&mut s);
+            statistics.set(s);
+        });
+    }
+
+    #[inline]
+    pub fn count_bounding_box_test() {
+        #[cfg(feature = "traversal_statistics")]
+        TraversalStatistics::increment(|s| s.bounding_box_tests += 1);
+    }
+
+    #[inline]
+    pub fn count_intersection_test() {
+        #[cfg(feature = "traversal_statistics")]
+        TraversalStatistics::increment(|s| s.intersection_tests += 1);
+    }
+}
+
+#[cfg(all(test, feature = "traversal_statistics"))]
+mod traversal_statistics_test {
+    use super::*;
+    use crate::geometry::collection::LinearBVH;
+    use crate::geometry::shape::Sphere;
+    use crate::geometry::Geometry;
+    use crate::material::NoMaterial;
+    use crate::math::Ray;
+    use crate::types::{Point3, Vector3};
+    use std::sync::Arc;
+
+    #[test]
+    fn count_test() {
+        TraversalStatistics::reset();
+        // nothing is counted outside of `count`
+        TraversalStatistics::count_bounding_box_test();
+        assert_eq!(TraversalStatistics::get(), TraversalStatistics::default());
+
+        let (_, statistics) = TraversalStatistics::count(|| {
+            TraversalStatistics::count_bounding_box_test();
+            TraversalStatistics::count_intersection_test();
+            TraversalStatistics::count_intersection_test();
+        });
+        assert_eq!(
+            statistics,
+            TraversalStatistics {
+                bounding_box_tests: 1,
+                intersection_tests: 2
+            }
+        );
+        TraversalStatistics::reset();
+        assert_eq!(TraversalStatistics::get(), TraversalStatistics::default());
+    }
+
+    #[test]
+    fn traversal_test() {
+        let list: Vec<Arc<dyn Geometry>> = (0..16)
+            .map(|i| {
+                Arc::new(Sphere::new(
+                    Point3::new(i as f64 * 3.0, 0.0, 0.0),
+                    1.0,
+                    Arc::new(NoMaterial::new()),
+                )) as Arc<dyn Geometry>
+            })
+            .collect();
+        let bvh = LinearBVH::new(&list, 0.0..0.0);
+        let (_, statistics) = TraversalStatistics::count(|| {
+            bvh.hit(
+                &Ray::new_ray(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0)),
+                0.001..f64::MAX,
+            )
+        });
+        // the hierarchy culls most of the spheres
+        assert!(statistics.bounding_box_tests > 0);
+        assert!(statistics.intersection_tests > 0 && statistics.intersection_tests <= 4);
+    }
+}
@@ -96,7 +96,7 @@ impl Geometry for ConstantMedium {
                             let p = ray.point_at(t);
                             //if (enableDebug)
                             //    Console.WriteLine($"hit_distance {hit_distance}; rec.T {rec.T}; rectP {rec.P}");
-                            return Some(HitRecord::new(
+                            let mut hit_record = HitRecord::new(
                                 t,
                                 TextureCoordinate::from_uv(0.0, 0.0),
                                 p,
@@ -104,7 +104,9 @@ impl Geometry for ConstantMedium {
                                 self.phase_function.clone(),
                                 self.phase_function
                                     .color_channels(&TextureCoordinate::from_uv(0.0, 0.0), &p),
-                            ));
+                            );
+                            hit_record.object_id = self.id;
+                            return Some(hit_record);
                         }
                         None
                     }
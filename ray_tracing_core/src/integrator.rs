@@ -0,0 +1,60 @@
+use crate::core::{AovRecord, Scene};
+use crate::math::Ray;
+use crate::types::{ColorRGB, FSize};
+use std::sync::Arc;
+
+mod path_tracer;
+pub use self::path_tracer::PathTracer;
+
+mod russian_roulette;
+pub use self::russian_roulette::RussianRoulette;
+
+mod recursive_path_tracer;
+pub use self::recursive_path_tracer::RecursivePathTracer;
+
+mod ambient_occlusion;
+pub use self::ambient_occlusion::AmbientOcclusion;
+
+mod debug_view;
+pub use self::debug_view::{DebugMode, DebugView};
+
+/// Rendering algorithm, which computes the light, that reaches the camera along a ray
+pub trait Integrator: Sync + Send {
+    /// Light, which reaches the camera along the camera ray `ray`.
+    /// The arbitrary output variables of the ray are recorded, if `aov_record` is set.
+    fn trace(&self, scene: &Scene, ray: &Ray, aov_record: Option<&mut AovRecord>) -> ColorRGB;
+}
+
+/// Integrator of a scene, see [`crate::core::Configuration::integrator`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IntegratorType {
+    /// Iterative path tracer
+    PathTracer,
+
+    /// Recursive path tracer without importance sampling of the lights
+    RecursivePathTracer,
+
+    /// Path tracer, which terminates the paths after the first diffuse scattering event
+    DirectLighting,
+
+    /// Fraction of the hemisphere around the first hit, which is not occluded within `distance`
+    AmbientOcclusion { distance: FSize },
+
+    /// Visualization of a property of the first hit, like the normal vector or the traversal cost
+    DebugView { mode: DebugMode },
+}
+
+impl IntegratorType {
+    /// Creates the rendering algorithm
+    pub fn to_integrator(&self) -> Arc<dyn Integrator> {
+        match *self {
+            IntegratorType::PathTracer => Arc::new(PathTracer::new()),
+            IntegratorType::RecursivePathTracer => Arc::new(RecursivePathTracer::new()),
+            IntegratorType::DirectLighting => Arc::new(PathTracer::new_direct_lighting()),
+            IntegratorType::AmbientOcclusion { distance } => {
+                Arc::new(AmbientOcclusion::new(distance))
+            }
+            IntegratorType::DebugView { mode } => Arc::new(DebugView::new(mode)),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use crate::core::{AovRecord, Scene};
+use crate::integrator::Integrator;
+use crate::math::Ray;
+use crate::probability_density_function::{CosinePdf, ProbabilityDensityFunction};
+use crate::random;
+use crate::sampler;
+use crate::types::{ColorRGB, FSize};
+
+/// Ambient occlusion.
+///
+/// A cosine weighted direction is sampled in the hemisphere around the normal vector of the first hit.
+/// The color is white, if the direction is not occluded within `distance`, else black.
+/// The mean of the samples is the cosine weighted fraction of the unoccluded hemisphere.
+pub struct AmbientOcclusion {
+    pub distance: FSize,
+}
+
+impl AmbientOcclusion {
+    pub fn new(distance: FSize) -> AmbientOcclusion {
+        AmbientOcclusion { distance }
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn trace(&self, scene: &Scene, ray: &Ray, aov_record: Option<&mut AovRecord>) -> ColorRGB {
+        let white = ColorRGB::new(1.0, 1.0, 1.0);
+        random::start_dimension(sampler::bounce_dimension(0, sampler::BOUNCE_OFFSET_HIT));
+        let hit_record = match scene.world.hit(ray, 0.001..FSize::MAX) {
+            Some(hit_record) => hit_record,
+            None => {
+                if let Some(aov_record) = aov_record {
+                    aov_record.albedo = white;
+                    aov_record.add_light(0, white);
+                }
+                return white;
+            }
+        };
+        // the hemisphere on the side of the surface, which is seen by the camera
+        let normal = if glm::dot(ray.direction, hit_record.normal) > 0.0 {
+            -hit_record.normal
+        } else {
+            hit_record.normal
+        };
+        random::start_dimension(sampler::bounce_dimension(0, sampler::BOUNCE_OFFSET_PDF));
+        let direction = CosinePdf::from_w(&normal).generate();
+        let occlusion_ray = Ray::new_ray_with_attributes(hit_record.position, direction, ray);
+        let t_max = self.distance / glm::length(direction);
+        let color = if scene.world.occluded(&occlusion_ray, 0.001..t_max) {
+            ColorRGB::new(0.0, 0.0, 0.0)
+        } else {
+            white
+        };
+        if let Some(aov_record) = aov_record {
+            aov_record.set_first_hit(&hit_record);
+            aov_record.add_light(1, color);
+        }
+        color
+    }
+}
+
+#[cfg(test)]
+mod ambient_occlusion_test {
+    use super::*;
+    use crate::core::{Camera, Configuration};
+    use crate::environment::Sky;
+    use crate::geometry::shape::Sphere;
+    use crate::integrator::IntegratorType;
+    use crate::material::Lambertian;
+    use crate::texture::ConstantTexture;
+    use crate::types::ColorRGBA;
+    use crate::types::{Point3, Vector3};
+    use std::sync::Arc;
+
+    fn scene(center: Point3, radius: FSize, distance: FSize) -> Scene {
+        let mut configuration = Configuration::default();
+        configuration.integrator = IntegratorType::AmbientOcclusion { distance };
+        let black = ColorRGB::new(0.0, 0.0, 0.0);
+        Scene::new(
+            configuration,
+            Arc::new(Camera::new(
+                Vector3::new(-1.0, -1.0, -1.0),
+                Vector3::new(2.0, 0.0, 0.0),
+                Vector3::new(0.0, 2.0, 0.0),
+                Vector3::new(0.0, 0.0, 0.0),
+                0.0,
+                0.0..0.0,
+            )),
+            Arc::new(Sky::new(black, black)),
+            Arc::new(Sphere::new(
+                center,
+                radius,
+                Arc::new(Lambertian::new(Arc::new(ConstantTexture::new(
+                    ColorRGBA::new(0.5, 0.5, 0.5, 1.0),
+                )))),
+            )),
+            Vec::default(),
+        )
+    }
+
+    #[test]
+    fn ambient_occlusion_test() {
+        // the outside of a convex object is not occluded
+        let outside = scene(Point3::new(0.0, 0.0, -3.0), 1.0, 100.0);
+        // the inside of a sphere is occluded in all directions
+        let inside = scene(Point3::new(0.0, 0.0, 0.0), 3.0, 100.0);
+        // the inside of a sphere isn't occluded within a small distance in the direction of the normal
+        let near = scene(Point3::new(0.0, 0.0, 0.0), 3.0, 0.01);
+        random::with_seed(Some(1), || {
+            let mut near_sum = 0.0;
+            for _ in 0..100 {
+                let (u, v) = (random::generate_size(), random::generate_size());
+                assert_eq!(outside.ray_trace_color(u, v), ColorRGB::new(1.0, 1.0, 1.0));
+                assert_eq!(inside.ray_trace_color(u, v), ColorRGB::new(0.0, 0.0, 0.0));
+                let (color, aov_record) = near.ray_trace_color_with_aovs(0.5, 0.5);
+                assert_eq!(aov_record.depth, 3.0);
+                near_sum += color.x;
+            }
+            assert!(near_sum > 90.0);
+        });
+    }
+}
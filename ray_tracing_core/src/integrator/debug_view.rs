@@ -0,0 +1,239 @@
+use crate::core::{AovRecord, Scene};
+use crate::display;
+use crate::geometry::TraversalStatistics;
+use crate::integrator::Integrator;
+use crate::math::Ray;
+use crate::random;
+use crate::sampler;
+use crate::types::{ColorRGB, FSize};
+
+/// Value, which is visualized by the [`DebugView`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DebugMode {
+    /// Shading normal vector, mapped from [-1, 1] to [0, 1]
+    Normal,
+
+    /// Texture coordinates in the red and the green channel
+    UV,
+
+    /// Ray parameter `t` of the first hit, white at the camera and black at `maximum_depth`
+    Depth { maximum_depth: FSize },
+
+    /// False color of the id of the material
+    MaterialId,
+
+    /// False color of the id of the geometry
+    GeometryId,
+
+    /// Heat map of the number of bounding box tests of the camera ray, red at `maximum` tests.
+    /// The tests are only counted with the feature `traversal_statistics`.
+    TraversalCost { maximum: usize },
+
+    /// Heat map of the number of primitive intersection tests of the camera ray, red at `maximum` tests.
+    /// The tests are only counted with the feature `traversal_statistics`.
+    IntersectionCount { maximum: usize },
+
+    /// Gray for the front faces and red for the back faces, where the normal vector points away from the camera
+    BackFace,
+}
+
+/// Integrator, which visualizes properties of the first hit of the camera ray.
+///
+/// The colors are converted from sRGB to linear, so they are displayed unchanged by the default display pipeline.
+/// Rays, which miss the world, are black.
+pub struct DebugView {
+    pub mode: DebugMode,
+}
+
+impl DebugView {
+    pub fn new(mode: DebugMode) -> DebugView {
+        DebugView { mode }
+    }
+
+    /// Distinguishable color of an id
+    pub fn false_color(id: usize) -> ColorRGB {
+        let hue = sampler::hash_to_size(sampler::hash(0, &[id as u64]));
+        DebugView::hue_to_rgb(hue) * 0.8 + ColorRGB::new(0.1, 0.1, 0.1)
+    }
+
+    /// Color ramp from blue over green and yellow to red for `x` in the range [0, 1]
+    pub fn heat_color(x: FSize) -> ColorRGB {
+        DebugView::hue_to_rgb((1.0 - FSize::clamp(x, 0.0, 1.0)) * 2.0 / 3.0)
+    }
+
+    fn hue_to_rgb(hue: FSize) -> ColorRGB {
+        let channel = |offset: FSize| {
+            let h = (hue + offset).fract() * 6.0;
+            FSize::clamp(FSize::abs(h - 3.0) - 1.0, 0.0, 1.0)
+        };
+        ColorRGB::new(channel(0.0), channel(2.0 / 3.0), channel(1.0 / 3.0))
+    }
+
+    fn to_linear(color: ColorRGB) -> ColorRGB {
+        ColorRGB::new(
+            display::srgb_to_linear(color.x),
+            display::srgb_to_linear(color.y),
+            display::srgb_to_linear(color.z),
+        )
+    }
+}
+
+impl Integrator for DebugView {
+    fn trace(&self, scene: &Scene, ray: &Ray, aov_record: Option<&mut AovRecord>) -> ColorRGB {
+        random::start_dimension(sampler::bounce_dimension(0, sampler::BOUNCE_OFFSET_HIT));
+        let (hit_record, statistics) =
+            TraversalStatistics::count(|| scene.world.hit(ray, 0.001..FSize::MAX));
+        let color = match (&hit_record, self.mode) {
+            (_, DebugMode::TraversalCost { maximum }) => DebugView::heat_color(
+                statistics.bounding_box_tests as FSize / FSize::max(1.0, maximum as FSize),
+            ),
+            (_, DebugMode::IntersectionCount { maximum }) => DebugView::heat_color(
+                statistics.intersection_tests as FSize / FSize::max(1.0, maximum as FSize),
+            ),
+            (None, _) => ColorRGB::new(0.0, 0.0, 0.0),
+            (Some(hit_record), DebugMode::Normal) => {
+                hit_record.normal * 0.5 + ColorRGB::new(0.5, 0.5, 0.5)
+            }
+            (Some(hit_record), DebugMode::UV) => {
+                ColorRGB::new(hit_record.uv.u, hit_record.uv.v, 0.0)
+            }
+            (Some(hit_record), DebugMode::Depth { maximum_depth }) => {
+                let gray = 1.0 - FSize::clamp(hit_record.t / maximum_depth, 0.0, 1.0);
+                ColorRGB::new(gray, gray, gray)
+            }
+            (Some(hit_record), DebugMode::MaterialId) => {
+                DebugView::false_color(hit_record.material.get_id())
+            }
+            (Some(hit_record), DebugMode::GeometryId) => {
+                DebugView::false_color(hit_record.object_id)
+            }
+            (Some(hit_record), DebugMode::BackFace) => {
+                let cosine = glm::dot(glm::normalize(ray.direction), hit_record.normal);
+                if cosine > 0.0 {
+                    ColorRGB::new(0.2 + 0.8 * cosine, 0.0, 0.0)
+                } else {
+                    let gray = 0.2 - 0.6 * cosine;
+                    ColorRGB::new(gray, gray, gray)
+                }
+            }
+        };
+        let color = DebugView::to_linear(color);
+        if let Some(aov_record) = aov_record {
+            if let Some(hit_record) = &hit_record {
+                aov_record.set_first_hit(hit_record);
+            }
+            aov_record.add_light(0, color);
+        }
+        color
+    }
+}
+
+#[cfg(test)]
+mod debug_view_test {
+    use super::*;
+    use crate::core::{Camera, Configuration};
+    use crate::environment::Sky;
+    use crate::geometry::shape::Sphere;
+    use crate::geometry::Geometry;
+    use crate::integrator::IntegratorType;
+    use crate::material::Lambertian;
+    use crate::test;
+    use crate::texture::ConstantTexture;
+    use crate::types::{ColorRGBA, Point3, Vector3};
+    use std::sync::Arc;
+
+    /// Camera at the origin, which looks along the negative z axis at the sphere
+    fn scene(mode: DebugMode, center: Point3, radius: FSize) -> Scene {
+        let mut configuration = Configuration::default();
+        configuration.integrator = IntegratorType::DebugView { mode };
+        let material = Arc::new(Lambertian::new(Arc::new(ConstantTexture::new(
+            ColorRGBA::new(0.5, 0.5, 0.5, 1.0),
+        ))));
+        let sphere: Arc<dyn Geometry> = Arc::new(Sphere::new(center, radius, material));
+        Scene::new(
+            configuration,
+            Arc::new(Camera::new(
+                Vector3::new(-1.0, -1.0, -1.0),
+                Vector3::new(2.0, 0.0, 0.0),
+                Vector3::new(0.0, 2.0, 0.0),
+                Vector3::new(0.0, 0.0, 0.0),
+                0.0,
+                0.0..0.0,
+            )),
+            Arc::new(Sky::new(
+                ColorRGB::new(1.0, 1.0, 1.0),
+                ColorRGB::new(1.0, 1.0, 1.0),
+            )),
+            sphere,
+            Vec::default(),
+        )
+    }
+
+    fn center_color(mode: DebugMode, center: Point3, radius: FSize) -> ColorRGB {
+        let color = scene(mode, center, radius).ray_trace_color(0.5, 0.5);
+        ColorRGB::new(
+            display::linear_to_srgb(color.x),
+            display::linear_to_srgb(color.y),
+            display::linear_to_srgb(color.z),
+        )
+    }
+
+    #[test]
+    fn normal_test() {
+        let color = center_color(DebugMode::Normal, Point3::new(0.0, 0.0, -3.0), 1.0);
+        test::assert_eq_vector3(&color, &ColorRGB::new(0.5, 0.5, 1.0), 1.0e-9);
+        // the camera doesn't hit the sphere
+        let color = center_color(DebugMode::Normal, Point3::new(0.0, 5.0, -3.0), 1.0);
+        assert_eq!(color, ColorRGB::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn depth_test() {
+        let mode = DebugMode::Depth { maximum_depth: 4.0 };
+        let color = center_color(mode, Point3::new(0.0, 0.0, -3.0), 1.0);
+        test::assert_eq_vector3(&color, &ColorRGB::new(0.5, 0.5, 0.5), 1.0e-9);
+    }
+
+    #[test]
+    fn id_test() {
+        let scene = scene(DebugMode::GeometryId, Point3::new(0.0, 0.0, -3.0), 1.0);
+        let (color, aov_record) = scene.ray_trace_color_with_aovs(0.5, 0.5);
+        assert_eq!(
+            color,
+            DebugView::to_linear(DebugView::false_color(scene.world.get_id()))
+        );
+        assert_eq!(aov_record.object_id, scene.world.get_id());
+        assert_ne!(DebugView::false_color(1), DebugView::false_color(2));
+    }
+
+    #[test]
+    fn heat_color_test() {
+        let blue = ColorRGB::new(0.0, 0.0, 1.0);
+        let red = ColorRGB::new(1.0, 0.0, 0.0);
+        test::assert_eq_vector3(&DebugView::heat_color(0.0), &blue, 1.0e-9);
+        test::assert_eq_vector3(&DebugView::heat_color(1.0), &red, 1.0e-9);
+        test::assert_eq_vector3(&DebugView::heat_color(2.0), &red, 1.0e-9);
+    }
+
+    #[cfg(feature = "traversal_statistics")]
+    #[test]
+    fn intersection_count_test() {
+        let red = ColorRGB::new(1.0, 0.0, 0.0);
+        let color = center_color(
+            DebugMode::IntersectionCount { maximum: 1 },
+            Point3::new(0.0, 0.0, -3.0),
+            1.0,
+        );
+        test::assert_eq_vector3(&color, &red, 1.0e-9);
+    }
+
+    #[test]
+    fn back_face_test() {
+        // outside of the sphere the front face is hit
+        let color = center_color(DebugMode::BackFace, Point3::new(0.0, 0.0, -3.0), 1.0);
+        assert!(color.x == color.y && color.y == color.z);
+        // inside of the sphere the normal vector points away from the camera
+        let color = center_color(DebugMode::BackFace, Point3::new(0.0, 0.0, 0.0), 3.0);
+        assert!(color.x > 0.5 && color.y == 0.0 && color.z == 0.0);
+    }
+}
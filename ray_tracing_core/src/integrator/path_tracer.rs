@@ -0,0 +1,253 @@
+use crate::core::{AovRecord, HitRecord, ScatterRecord, Scene};
+use crate::integrator::Integrator;
+use crate::math::Ray;
+use crate::probability_density_function::{
+    power_heuristic, MixturePdf, ProbabilityDensityFunction,
+};
+use crate::random;
+use crate::sampler;
+use crate::types::{ColorRGB, FSize};
+
+/// Iterative path tracer.
+///
+/// At diffuse scattering events the lights of the scene are sampled by multiple importance sampling.
+/// Either the direction is sampled by a mixture of the material and the lights (one-sample MIS),
+/// or a light is sampled by next event estimation and connected by a shadow ray.
+/// Paths can be terminated by Russian roulette, see [`crate::core::Configuration::russian_roulette`].
+pub struct PathTracer {
+    /// Number of diffuse scattering events, after which a path is terminated.
+    /// Specular scattering events are not counted.
+    pub maximum_diffuse_depth: usize,
+
+    /// Uses next event estimation, even if [`crate::core::Configuration::next_event_estimation`] is not set
+    pub next_event_estimation: bool,
+}
+
+impl PathTracer {
+    pub fn new() -> PathTracer {
+        PathTracer {
+            maximum_diffuse_depth: usize::MAX,
+            next_event_estimation: false,
+        }
+    }
+
+    /// Path tracer, which computes the direct lighting only.
+    /// The paths are terminated after the first diffuse scattering event.
+    pub fn new_direct_lighting() -> PathTracer {
+        PathTracer {
+            maximum_diffuse_depth: 1,
+            next_event_estimation: true,
+        }
+    }
+
+    /// Next event estimation: samples a light and traces a shadow ray to it.
+    /// Returns the scattered light of the sampled light, weighted by the power heuristic.
+    fn sample_light(
+        scene: &Scene,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        scatter_record: &ScatterRecord,
+        material_pdf: &dyn ProbabilityDensityFunction,
+    ) -> ColorRGB {
+        let black = ColorRGB::new(0.0, 0.0, 0.0);
+        let sample = match scene.lights.sample(&hit_record.position) {
+            Some(sample) if sample.pdf > 0.0 => sample,
+            _ => return black,
+        };
+        let shadow_ray = Ray::new_ray_with_attributes(hit_record.position, sample.direction, ray);
+        let light_hit_record = match sample.light.hit(&shadow_ray, 0.001..FSize::MAX) {
+            Some(light_hit_record) => light_hit_record,
+            None => return black,
+        };
+        if scene
+            .world
+            .occluded(&shadow_ray, 0.001..light_hit_record.t - 0.001)
+        {
+            return black;
+        }
+        let emitted = light_hit_record
+            .material
+            .emitted(&shadow_ray, &light_hit_record);
+        let s_pdf = scatter_record
+            .material
+            .scattering_pdf(ray, hit_record, &shadow_ray);
+        let weight = power_heuristic(sample.pdf, material_pdf.value(&sample.direction));
+        scatter_record.attenuation * emitted * s_pdf * weight / sample.pdf
+    }
+}
+
+impl Default for PathTracer {
+    fn default() -> PathTracer {
+        PathTracer::new()
+    }
+}
+
+impl Integrator for PathTracer {
+    fn trace(&self, scene: &Scene, ray: &Ray, mut aov_record: Option<&mut AovRecord>) -> ColorRGB {
+        let mut ray = *ray;
+        let mut color = ColorRGB::new(0.0, 0.0, 0.0);
+        let mut attenuation = ColorRGB::new(1.0, 1.0, 1.0);
+        let next_event_estimation = (self.next_event_estimation
+            || scene.configuration.next_event_estimation)
+            && !scene.lights.is_empty();
+        let mut diffuse_depth = 0;
+        // density of the direction of the ray, if the direction was sampled by the material
+        // and the lights were sampled by the next event estimation at the same scattering event
+        let mut material_pdf: Option<FSize> = None;
+        for depth in 0..scene.configuration.maximum_depth {
+            random::start_dimension(sampler::bounce_dimension(depth, sampler::BOUNCE_OFFSET_HIT));
+            if let Some(hit_record) = scene.world.hit(&ray, 0.001..FSize::MAX) {
+                let material = hit_record.material.clone();
+                let mut emitted = material.emitted(&ray, &hit_record);
+                if let Some(material_pdf) = material_pdf {
+                    if emitted.x + emitted.y + emitted.z > 0.0 {
+                        // the emitter may have been sampled by the next event estimation, too
+                        emitted = emitted
+                            * power_heuristic(
+                                material_pdf,
+                                scene.lights.emitter_pdf_value(&ray, hit_record.t),
+                            );
+                    }
+                }
+                color = color + attenuation * emitted;
+                if let Some(aov_record) = aov_record.as_deref_mut() {
+                    if depth == 0 {
+                        aov_record.set_first_hit(&hit_record);
+                    }
+                    aov_record.add_light(depth, attenuation * emitted);
+                    aov_record.check_finite(&hit_record, &color);
+                }
+                if diffuse_depth >= self.maximum_diffuse_depth {
+                    break;
+                }
+                random::start_dimension(sampler::bounce_dimension(
+                    depth,
+                    sampler::BOUNCE_OFFSET_SCATTER,
+                ));
+                if let Some(scatter_record) = hit_record.scatter(&ray) {
+                    material_pdf = None;
+                    if scatter_record.is_specular {
+                        attenuation = attenuation * scatter_record.attenuation;
+                        ray = scatter_record.ray;
+                    } else {
+                        diffuse_depth += 1;
+                        let light_pdf = if next_event_estimation {
+                            if let Some(pdf) = &scatter_record.pdf {
+                                random::start_dimension(sampler::bounce_dimension(
+                                    depth,
+                                    sampler::BOUNCE_OFFSET_LIGHT,
+                                ));
+                                let direct = attenuation
+                                    * PathTracer::sample_light(
+                                        scene,
+                                        &ray,
+                                        &hit_record,
+                                        &scatter_record,
+                                        pdf.as_ref(),
+                                    );
+                                color = color + direct;
+                                if let Some(aov_record) = aov_record.as_deref_mut() {
+                                    aov_record.add_light(depth + 1, direct);
+                                }
+                            }
+                            None
+                        } else {
+                            scene.lights.pdf(&hit_record.position)
+                        };
+                        random::start_dimension(sampler::bounce_dimension(
+                            depth,
+                            sampler::BOUNCE_OFFSET_PDF,
+                        ));
+                        match (scatter_record.pdf, light_pdf) {
+                            (Some(pdf), Some(light_pdf)) => {
+                                // one-sample multiple importance sampling of the material and the lights
+                                let pdf = MixturePdf::new(pdf, light_pdf);
+                                let (direction, index) = pdf.generate_with_index();
+                                let scattered = Ray::new_ray_with_attributes(
+                                    hit_record.position,
+                                    direction,
+                                    &ray,
+                                );
+                                let s_pdf = scatter_record.material.scattering_pdf(
+                                    &ray,
+                                    &hit_record,
+                                    &scattered,
+                                );
+                                attenuation = attenuation
+                                    * scatter_record.attenuation
+                                    * s_pdf
+                                    * pdf.power_heuristic(index, &scattered.direction);
+                                ray = scattered;
+                            }
+                            (Some(pdf), None) => {
+                                let scattered = Ray::new_ray_with_attributes(
+                                    hit_record.position,
+                                    pdf.generate(),
+                                    &ray,
+                                );
+                                let s_pdf = scatter_record.material.scattering_pdf(
+                                    &ray,
+                                    &hit_record,
+                                    &scattered,
+                                );
+                                let pdf_value = pdf.value(&scattered.direction);
+                                attenuation =
+                                    attenuation * scatter_record.attenuation * s_pdf / pdf_value;
+                                ray = scattered;
+                                if next_event_estimation {
+                                    material_pdf = Some(pdf_value);
+                                }
+                            }
+                            (None, _) => {
+                                let s_pdf = scatter_record.material.scattering_pdf(
+                                    &ray,
+                                    &hit_record,
+                                    &scatter_record.ray,
+                                );
+                                attenuation = attenuation * scatter_record.attenuation * s_pdf;
+                                ray = scatter_record.ray;
+                            }
+                        }
+                    }
+                    if let Some(aov_record) = aov_record.as_deref_mut() {
+                        aov_record.check_finite(&hit_record, &color);
+                        aov_record.check_finite(&hit_record, &attenuation);
+                    }
+                    if attenuation.x <= 0.0 && attenuation.y <= 0.0 && attenuation.z <= 0.0 {
+                        // the path doesn't contribute any light
+                        break;
+                    }
+                    let survival_probability =
+                        scene.configuration.russian_roulette.survival_probability(
+                            depth,
+                            scene.configuration.russian_roulette_minimum_depth,
+                            &attenuation,
+                        );
+                    if survival_probability < 1.0 {
+                        random::start_dimension(sampler::bounce_dimension(
+                            depth,
+                            sampler::BOUNCE_OFFSET_ROULETTE,
+                        ));
+                        if random::generate_size() >= survival_probability {
+                            break;
+                        }
+                        attenuation = attenuation / survival_probability;
+                    }
+                } else {
+                    break;
+                }
+            } else {
+                let sky_color = scene.sky.get(&ray);
+                color = color + attenuation * sky_color;
+                if let Some(aov_record) = aov_record.as_deref_mut() {
+                    if depth == 0 {
+                        aov_record.albedo = sky_color;
+                    }
+                    aov_record.add_light(depth, attenuation * sky_color);
+                }
+                break;
+            }
+        }
+        color
+    }
+}
@@ -0,0 +1,100 @@
+use crate::core::{AovRecord, Scene};
+use crate::integrator::Integrator;
+use crate::math::Ray;
+use crate::random;
+use crate::sampler;
+use crate::types::{ColorRGB, FSize};
+
+/// Recursive path tracer, which follows the directions, that are scattered by the materials.
+/// The lights are not sampled explicitly.
+pub struct RecursivePathTracer {}
+
+impl RecursivePathTracer {
+    pub fn new() -> RecursivePathTracer {
+        RecursivePathTracer {}
+    }
+
+    /// Light along `ray` at the bounce `depth`.
+    /// `throughput` is the attenuation of the path from the camera to the origin of `ray`,
+    /// it weights the light, which is recorded in `aov_record`.
+    fn trace_recursive(
+        scene: &Scene,
+        ray: &Ray,
+        depth: usize,
+        throughput: ColorRGB,
+        mut aov_record: Option<&mut AovRecord>,
+    ) -> ColorRGB {
+        random::start_dimension(sampler::bounce_dimension(depth, sampler::BOUNCE_OFFSET_HIT));
+        match scene.world.hit(ray, 0.001..FSize::MAX) {
+            Some(hit_record) => {
+                let material = hit_record.material.clone();
+                let mut color = material.emitted(ray, &hit_record);
+                if let Some(aov_record) = aov_record.as_deref_mut() {
+                    if depth == 0 {
+                        aov_record.set_first_hit(&hit_record);
+                    }
+                    aov_record.add_light(depth, throughput * color);
+                }
+                if depth < scene.configuration.maximum_depth {
+                    random::start_dimension(sampler::bounce_dimension(
+                        depth,
+                        sampler::BOUNCE_OFFSET_SCATTER,
+                    ));
+                    if let Some(scatter_record) = hit_record.scatter(ray) {
+                        let attenuation = if scatter_record.is_specular {
+                            scatter_record.attenuation
+                        } else {
+                            let s_pdf = scatter_record.material.scattering_pdf(
+                                ray,
+                                &hit_record,
+                                &scatter_record.ray,
+                            );
+                            scatter_record.attenuation * s_pdf
+                        };
+                        let scattered = RecursivePathTracer::trace_recursive(
+                            scene,
+                            &scatter_record.ray,
+                            depth + 1,
+                            throughput * attenuation,
+                            aov_record.as_deref_mut(),
+                        );
+                        color = color + attenuation * scattered;
+                    }
+                }
+                if let Some(aov_record) = aov_record {
+                    // the deeper hits are checked first
+                    aov_record.check_finite(&hit_record, &color);
+                }
+                color
+            }
+            None => {
+                let sky_color = scene.sky.get(ray);
+                if let Some(aov_record) = aov_record {
+                    if depth == 0 {
+                        aov_record.albedo = sky_color;
+                    }
+                    aov_record.add_light(depth, throughput * sky_color);
+                }
+                sky_color
+            }
+        }
+    }
+}
+
+impl Default for RecursivePathTracer {
+    fn default() -> RecursivePathTracer {
+        RecursivePathTracer::new()
+    }
+}
+
+impl Integrator for RecursivePathTracer {
+    fn trace(&self, scene: &Scene, ray: &Ray, aov_record: Option<&mut AovRecord>) -> ColorRGB {
+        RecursivePathTracer::trace_recursive(
+            scene,
+            ray,
+            0,
+            ColorRGB::new(1.0, 1.0, 1.0),
+            aov_record,
+        )
+    }
+}
@@ -0,0 +1,70 @@
+use crate::types::{ColorRGB, FSize};
+
+/// Strategy, which terminates paths randomly, see [`crate::core::Configuration::russian_roulette`].
+///
+/// A path, which survives, is weighted by the reciprocal of the survival probability,
+/// so that the expected value of the light is not changed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RussianRoulette {
+    /// Paths are terminated at the maximum depth only
+    Disabled,
+
+    /// The survival probability is the maximum component of the path throughput, limited to 1
+    Throughput,
+}
+
+impl RussianRoulette {
+    /// Probability, that a path with the throughput `throughput` is continued after the bounce `depth`.
+    /// Russian roulette is not applied to the bounces before `minimum_depth`.
+    pub fn survival_probability(
+        &self,
+        depth: usize,
+        minimum_depth: usize,
+        throughput: &ColorRGB,
+    ) -> FSize {
+        match self {
+            RussianRoulette::Disabled => 1.0,
+            RussianRoulette::Throughput => {
+                if depth < minimum_depth {
+                    1.0
+                } else {
+                    throughput
+                        .x
+                        .max(throughput.y)
+                        .max(throughput.z)
+                        .clamp(0.0, 1.0)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod russian_roulette_test {
+    use super::*;
+
+    #[test]
+    fn survival_probability_test() {
+        let throughput = ColorRGB::new(0.1, 0.4, 0.2);
+        assert_eq!(
+            RussianRoulette::Disabled.survival_probability(10, 3, &throughput),
+            1.0,
+        );
+        assert_eq!(
+            RussianRoulette::Throughput.survival_probability(2, 3, &throughput),
+            1.0,
+        );
+        assert_eq!(
+            RussianRoulette::Throughput.survival_probability(3, 3, &throughput),
+            0.4,
+        );
+        assert_eq!(
+            RussianRoulette::Throughput.survival_probability(3, 3, &ColorRGB::new(2.0, 0.0, 0.0)),
+            1.0,
+        );
+        assert_eq!(
+            RussianRoulette::Throughput.survival_probability(3, 3, &ColorRGB::new(0.0, 0.0, 0.0)),
+            0.0,
+        );
+    }
+}
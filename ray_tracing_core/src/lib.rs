@@ -17,6 +17,7 @@
 //! # Example
 //!
 //! ```rust
+//! use ray_tracing_core::display::DisplayPipeline;
 //! use ray_tracing_core::random;
 //! use ray_tracing_core::test::TestSceneSimple;
 //! use ray_tracing_core::types::ColorRGB;
@@ -27,6 +28,7 @@
 //!     let cy = 20;
 //!     let samples = 10;
 //!     let scene = TestSceneSimple::new().scene;
+//!     let display = DisplayPipeline::default();
 //!     
 //!     let mut pixel_data: Vec<u8> = Vec::with_capacity(cx * cy * 4);
 //!     pixel_data.resize(cx * cy * 4, 0);
@@ -42,10 +44,7 @@
 //!             c = c / samples as FSize;
 //!     
 //!             let i = (y * cx) + x;
-//!             pixel_data[i * 4] = (c[0].sqrt() * 255.0).round() as u8;
-//!             pixel_data[i * 4 + 1] = (c[1].sqrt() * 255.0).round() as u8;
-//!             pixel_data[i * 4 + 2] = (c[2].sqrt() * 255.0).round() as u8;
-//!             pixel_data[i * 4 + 3] = 255;
+//!             pixel_data[i * 4..i * 4 + 4].copy_from_slice(&display.to_rgba8(&c, x, y));
 //!         }
 //!     }
 //!     
@@ -63,6 +62,16 @@ pub mod types;
 /// Generators for random data like vectors and colors using [Crate `rand`](https://docs.rs/rand/0.8.3/rand/)
 pub mod random;
 
+/// Sampler
+///
+/// Low discrepancy sample generators for the dimensions of a path
+pub mod sampler;
+
+/// Display pipeline
+///
+/// Conversion of the linear radiance to displayable sRGB pixel data with exposure and tone mapping
+pub mod display;
+
 /// Ray Trace Math
 ///
 /// Ray trace math objects and equations  
@@ -96,5 +105,10 @@ pub mod core;
 /// Probability Density Function
 pub mod probability_density_function;
 
+/// Integrator
+///
+/// Rendering algorithms like path tracing and ambient occlusion
+pub mod integrator;
+
 /// Internal module for test for integration tests
 pub mod test;
@@ -1,3 +1,4 @@
+use crate::geometry::TraversalStatistics;
 use crate::math::Ray;
 use crate::types::{FSize, Vector3};
 use std::mem;
@@ -30,6 +31,7 @@ impl AABB {
     }
 
     pub fn hit(&self, ray: &Ray, t_range: Range<FSize>) -> bool {
+        TraversalStatistics::count_bounding_box_test();
         for a in 0..3 {
             //let t0 = FSize::min((self.min[a] - ray.origin[a]) / ray.direction[a], (self.max[a] - ray.origin[a]) / ray.direction[a]);
             //let t1 = FSize::max((self.min[a] - ray.origin[a]) / ray.direction[a], (self.max[a] - ray.origin[a]) / ray.direction[a]);
@@ -50,6 +52,15 @@ impl AABB {
         true
     }
 
+    pub fn surface_area(&self) -> FSize {
+        let d = self.max - self.min;
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    pub fn centroid(&self) -> Vector3 {
+        (self.min + self.max) * 0.5
+    }
+
     pub fn or_vector(&self, v: Vector3) -> AABB {
         AABB {
             min: Vector3::new(
@@ -106,6 +117,13 @@ mod aabb_test {
         test::assert_eq_vector3(&b_or.max, &Vector3::new(6.0, 6.0, 6.0), 0.001);
     }
 
+    #[test]
+    fn surface_area_test() {
+        let b = AABB::new(Vector3::new(1.0, 1.0, 1.0), Vector3::new(2.0, 3.0, 4.0));
+        test::assert_eq_float(b.surface_area(), 22.0, 0.001);
+        test::assert_eq_vector3(&b.centroid(), &Vector3::new(1.5, 2.0, 2.5), 0.001);
+    }
+
     #[test]
     fn hit_test() {
         let b = AABB::new(Vector3::new(-1.0, -1.0, -1.0), Vector3::new(1.0, 1.0, 1.0));
@@ -9,7 +9,32 @@ pub use self::geometry_pdf::GeometryPdf;
 mod mixture_pdf;
 pub use self::mixture_pdf::MixturePdf;
 
-pub trait ProbabilityDensityFunction {
+pub trait ProbabilityDensityFunction: Sync + Send {
     fn value(&self, direction: &Vector3) -> FSize;
     fn generate(&self) -> Vector3;
 }
+
+/// Multiple importance sampling weight of a sample with the density `pdf`,
+/// which could also have been generated by another strategy with the density `other_pdf`
+pub fn power_heuristic(pdf: FSize, other_pdf: FSize) -> FSize {
+    let sum_squared = pdf * pdf + other_pdf * other_pdf;
+    if sum_squared > 0.0 {
+        pdf * pdf / sum_squared
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod probability_density_function_test {
+    use super::*;
+
+    #[test]
+    fn power_heuristic_test() {
+        assert_eq!(power_heuristic(1.0, 0.0), 1.0);
+        assert_eq!(power_heuristic(0.0, 1.0), 0.0);
+        assert_eq!(power_heuristic(0.0, 0.0), 0.0);
+        assert_eq!(power_heuristic(1.0, 3.0) + power_heuristic(3.0, 1.0), 1.0);
+        assert_eq!(power_heuristic(3.0, 1.0), 0.9);
+    }
+}
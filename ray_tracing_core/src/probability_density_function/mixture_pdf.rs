@@ -3,34 +3,174 @@ use crate::random;
 use crate::types::{FSize, Vector3};
 use std::sync::Arc;
 
+/// Weighted mixture of probability density functions
 pub struct MixturePdf {
-    // TODO Vec<Arc<dyn ProbabilityDensityFunction>>
-    pub pdf_0: Arc<dyn ProbabilityDensityFunction>,
-    pub pdf_1: Arc<dyn ProbabilityDensityFunction>,
+    pub pdfs: Vec<Arc<dyn ProbabilityDensityFunction>>,
+
+    /// Probabilities, with which the density functions are sampled. The sum of the weights is 1.
+    pub weights: Vec<FSize>,
 }
 
 impl MixturePdf {
+    /// Mixture of 2 density functions with equal weights
     pub fn new(
         pdf_0: Arc<dyn ProbabilityDensityFunction>,
         pdf_1: Arc<dyn ProbabilityDensityFunction>,
     ) -> MixturePdf {
-        MixturePdf {
-            pdf_0: pdf_0.clone(),
-            pdf_1: pdf_1.clone(),
+        MixturePdf::new_weighted(vec![pdf_0, pdf_1], vec![1.0, 1.0])
+    }
+
+    /// Mixture of density functions, the weights are normalized
+    pub fn new_weighted(
+        pdfs: Vec<Arc<dyn ProbabilityDensityFunction>>,
+        weights: Vec<FSize>,
+    ) -> MixturePdf {
+        let sum: FSize = weights.iter().sum();
+        let weights = if sum > 0.0 {
+            weights.iter().map(|w| w / sum).collect()
+        } else {
+            vec![1.0 / pdfs.len() as FSize; pdfs.len()]
+        };
+        MixturePdf { pdfs, weights }
+    }
+
+    /// Generates a direction and returns the index of the density function, which generated it
+    pub fn generate_with_index(&self) -> (Vector3, usize) {
+        let index = self.select(random::generate_size());
+        (self.pdfs[index].generate(), index)
+    }
+
+    /// Factor of the one-sample multiple importance sampling estimator `f * w_i / (c_i * p_i)`
+    /// with the power heuristic `w_i = (c_i * p_i)² / Σ (c_j * p_j)²`,
+    /// where `i` is the index of the density function, which generated the direction,
+    /// and `c_j` are the weights of the mixture. The factor is `c_i * p_i / Σ (c_j * p_j)²`.
+    pub fn power_heuristic(&self, index: usize, direction: &Vector3) -> FSize {
+        let mut sum_squared = 0.0;
+        let mut selected = 0.0;
+        for (i, (pdf, weight)) in self.pdfs.iter().zip(self.weights.iter()).enumerate() {
+            let p = weight * pdf.value(direction);
+            sum_squared += p * p;
+            if i == index {
+                selected = p;
+            }
         }
+        if sum_squared > 0.0 {
+            selected / sum_squared
+        } else {
+            0.0
+        }
+    }
+
+    fn select(&self, x: FSize) -> usize {
+        let mut sum = 0.0;
+        for (i, weight) in self.weights.iter().enumerate() {
+            sum += weight;
+            if x < sum {
+                return i;
+            }
+        }
+        self.weights.len() - 1
     }
 }
 
 impl ProbabilityDensityFunction for MixturePdf {
     fn value(&self, direction: &Vector3) -> FSize {
-        0.5 * self.pdf_0.value(direction) + 0.5 * self.pdf_1.value(direction)
+        self.pdfs
+            .iter()
+            .zip(self.weights.iter())
+            .map(|(pdf, weight)| weight * pdf.value(direction))
+            .sum()
     }
 
     fn generate(&self) -> Vector3 {
-        if random::generate_size() < 0.5 {
-            self.pdf_0.generate()
-        } else {
-            self.pdf_1.generate()
+        self.generate_with_index().0
+    }
+}
+
+#[cfg(test)]
+mod mixture_pdf_test {
+    use super::*;
+    use crate::test;
+
+    /// Density function, which generates a constant direction
+    struct ConstantPdf {
+        direction: Vector3,
+        value: FSize,
+    }
+
+    impl ProbabilityDensityFunction for ConstantPdf {
+        fn value(&self, direction: &Vector3) -> FSize {
+            if *direction == self.direction {
+                self.value
+            } else {
+                0.0
+            }
+        }
+
+        fn generate(&self) -> Vector3 {
+            self.direction
+        }
+    }
+
+    fn constant_pdf(x: FSize, value: FSize) -> Arc<dyn ProbabilityDensityFunction> {
+        Arc::new(ConstantPdf {
+            direction: Vector3::new(x, 0.0, 0.0),
+            value,
+        })
+    }
+
+    #[test]
+    fn value_test() {
+        let pdf = MixturePdf::new_weighted(
+            vec![
+                constant_pdf(1.0, 2.0),
+                constant_pdf(2.0, 4.0),
+                constant_pdf(3.0, 8.0),
+            ],
+            vec![2.0, 1.0, 1.0],
+        );
+        assert_eq!(pdf.weights, vec![0.5, 0.25, 0.25]);
+        assert_eq!(pdf.value(&Vector3::new(1.0, 0.0, 0.0)), 1.0);
+        assert_eq!(pdf.value(&Vector3::new(3.0, 0.0, 0.0)), 2.0);
+        assert_eq!(pdf.value(&Vector3::new(4.0, 0.0, 0.0)), 0.0);
+
+        let pdf = MixturePdf::new(constant_pdf(1.0, 2.0), constant_pdf(1.0, 4.0));
+        assert_eq!(pdf.value(&Vector3::new(1.0, 0.0, 0.0)), 3.0);
+    }
+
+    #[test]
+    fn generate_test() {
+        let pdf = MixturePdf::new_weighted(
+            vec![
+                constant_pdf(1.0, 1.0),
+                constant_pdf(2.0, 1.0),
+                constant_pdf(3.0, 1.0),
+            ],
+            vec![0.0, 3.0, 1.0],
+        );
+        let mut count = [0; 3];
+        for _ in 0..1000 {
+            let (direction, index) = pdf.generate_with_index();
+            assert_eq!(direction.x, index as FSize + 1.0);
+            count[index] += 1;
         }
+        assert_eq!(count[0], 0);
+        assert!(count[1] > 650 && count[2] > 150);
+    }
+
+    #[test]
+    fn power_heuristic_test() {
+        let direction = Vector3::new(1.0, 0.0, 0.0);
+        let pdf = MixturePdf::new(constant_pdf(1.0, 2.0), constant_pdf(1.0, 6.0));
+        // c_0 * p_0 = 1, c_1 * p_1 = 3
+        test::assert_eq_float(pdf.power_heuristic(0, &direction), 0.1, 1.0e-12);
+        test::assert_eq_float(pdf.power_heuristic(1, &direction), 0.3, 1.0e-12);
+        // the weights w_i = c_i * p_i * factor_i sum up to 1
+        test::assert_eq_float(
+            1.0 * pdf.power_heuristic(0, &direction) + 3.0 * pdf.power_heuristic(1, &direction),
+            1.0,
+            1.0e-12,
+        );
+        assert_eq!(pdf.power_heuristic(0, &Vector3::new(0.0, 1.0, 0.0)), 0.0);
     }
 }
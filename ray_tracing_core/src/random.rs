@@ -1,28 +1,160 @@
+use crate::sampler;
+use crate::sampler::Sampler;
 use crate::types::{ColorRGB, FSize, Point3, TextureCoordinate, Vector3};
 use core::f64::consts::PI;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cell::RefCell;
 use std::ops::Range;
+use std::sync::Arc;
+
+/// Random number state of a thread
+///
+/// If a sampler is set, the random numbers are the consecutive dimensions of the current sample.
+/// If a group of dimensions is used up, the following random numbers are independent hash values,
+/// so that the dimensions of the next group are not used twice.
+/// Else if the context is seeded, the random numbers are generated deterministically,
+/// otherwise `rand::thread_rng` is used.
+struct SamplerContext {
+    rng: Option<StdRng>,
+    sampler: Option<(Arc<dyn Sampler>, u64)>,
+    index: usize,
+    dimension: usize,
+    dimension_end: usize,
+}
+
+thread_local! {
+    static SAMPLER_CONTEXT: RefCell<SamplerContext> = const {
+        RefCell::new(SamplerContext {
+            rng: None,
+            sampler: None,
+            index: 0,
+            dimension: 0,
+            dimension_end: 0,
+        })
+    };
+}
+
+/// Seeds the sampler context of the current thread, or resets it to non deterministic random numbers
+pub fn set_seed(seed: Option<u64>) {
+    SAMPLER_CONTEXT.with(|context| context.borrow_mut().rng = seed.map(StdRng::seed_from_u64));
+}
+
+/// Check whether the sampler context of the current thread is seeded
+pub fn is_seeded() -> bool {
+    SAMPLER_CONTEXT.with(|context| context.borrow().rng.is_some())
+}
+
+/// Runs `f` with a seeded sampler context and restores the previous context of the thread afterwards.
+/// If `seed` is `None` the current context is kept.
+pub fn with_seed<T>(seed: Option<u64>, f: impl FnOnce() -> T) -> T {
+    match seed {
+        Some(seed) => {
+            let previous = SAMPLER_CONTEXT.with(|context| {
+                context
+                    .borrow_mut()
+                    .rng
+                    .replace(StdRng::seed_from_u64(seed))
+            });
+            let result = f();
+            SAMPLER_CONTEXT.with(|context| context.borrow_mut().rng = previous);
+            result
+        }
+        None => f(),
+    }
+}
+
+/// Derives the seed of a single pixel and sample (or pass) index from a global seed.
+/// The values are mixed with the SplitMix64 finalizer, so that neighbouring pixels get uncorrelated sequences.
+pub fn derive_seed(seed: u64, x: usize, y: usize, sample: usize) -> u64 {
+    sampler::hash(seed, &[x as u64, y as u64, sample as u64])
+}
+
+/// Generate a random seed
+pub fn generate_seed() -> u64 {
+    SAMPLER_CONTEXT.with(|context| match context.borrow_mut().rng.as_mut() {
+        Some(rng) => rng.gen(),
+        None => rand::thread_rng().gen(),
+    })
+}
+
+/// Sets the sampler of the current thread and the seed of the pixel, which is sampled.
+/// `None` switches back to random numbers.
+pub fn set_sampler(sampler: Option<Arc<dyn Sampler>>, seed: u64) {
+    SAMPLER_CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        context.sampler = sampler.map(|sampler| (sampler, seed));
+        context.index = 0;
+        context.dimension = 0;
+        context.dimension_end = sampler::dimension_end(0);
+    });
+}
+
+/// Starts the sample `index` of the current pixel at the first dimension
+pub fn start_sample(index: usize) {
+    SAMPLER_CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        context.index = index;
+        context.dimension = 0;
+        context.dimension_end = sampler::dimension_end(0);
+    });
+}
+
+/// Continues the current sample at the dimension `dimension`, see [`sampler::DIMENSION_PIXEL`] etc.
+/// The sampler supplies the dimensions up to the end of the group, see [`sampler::dimension_end`].
+pub fn start_dimension(dimension: usize) {
+    SAMPLER_CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        context.dimension = dimension;
+        context.dimension_end = sampler::dimension_end(dimension);
+    });
+}
+
+/// Next value in the range [0.0, 1.0) from the sampler or the random number generator
+fn generate_uniform() -> FSize {
+    SAMPLER_CONTEXT.with(|context| {
+        let context = &mut *context.borrow_mut();
+        match &context.sampler {
+            Some((sampler, seed)) => {
+                let value = if context.dimension < context.dimension_end {
+                    sampler.sample(*seed, context.index, context.dimension)
+                } else {
+                    // the group of dimensions is used up
+                    sampler::hash_to_size(sampler::hash(
+                        *seed,
+                        &[u64::MAX, context.index as u64, context.dimension as u64],
+                    ))
+                };
+                context.dimension += 1;
+                value
+            }
+            None => match context.rng.as_mut() {
+                Some(rng) => rng.gen(),
+                None => rand::thread_rng().gen(),
+            },
+        }
+    })
+}
 
 /// Generate random axis
 pub fn generate_axis() -> usize {
-    rand::thread_rng().gen_range(0..3)
+    generate_from_range(0..3)
 }
 
 /// Generate random axis
 pub fn generate_from_range(range: Range<usize>) -> usize {
-    rand::thread_rng().gen_range(range)
+    let i = (generate_uniform() * (range.end - range.start) as FSize) as usize;
+    range.start + usize::min(i, range.end - range.start - 1)
 }
 
 /// Generate a single floating point value in the range [0.0, 1.0]
 pub fn generate_size() -> FSize {
-    let value: FSize = rand::thread_rng().gen();
-    value
+    generate_uniform()
 }
 
 /// Generate a single floating point value in a specific range
 pub fn generate_range(range: Range<FSize>) -> FSize {
-    let value: FSize = rand::thread_rng().gen_range(range);
-    value
+    range.start + generate_uniform() * (range.end - range.start)
 }
 
 /// Generate a floating point tuple in a specific range
@@ -35,24 +167,31 @@ pub fn generate_range2d(range: &Range<(FSize, FSize)>) -> (FSize, FSize) {
 
 /// Generate a single floating point value in range [-1.0, 1.0]
 pub fn generate_unit() -> FSize {
-    let value: FSize = rand::thread_rng().gen();
-    value * 2.0 - 1.0
+    generate_uniform() * 2.0 - 1.0
 }
 
 /// Generate a single floating point value in range [0.0, 1.0]
 pub fn generate_unit_abs() -> FSize {
-    let value: FSize = rand::thread_rng().gen();
-    value
+    generate_uniform()
 }
 
-/// Generate a random vector, whose length is less than or equal 1.0
+/// Generate a random vector, whose length is less than or equal 1.0.
+/// The vectors are uniformly distributed in the unit sphere, exactly 3 values are consumed.
 pub fn generate_unit_sphere() -> Vector3 {
-    loop {
-        let v = generate_vector3();
-        if glm::dot(v, v) < 1.0 {
-            break v;
-        }
-    }
+    let z = generate_unit();
+    let phi = 2.0 * PI * generate_size();
+    let r = FSize::cbrt(generate_size());
+    let s = FSize::sqrt(FSize::max(0.0, 1.0 - z * z));
+    Vector3::new(r * s * FSize::cos(phi), r * s * FSize::sin(phi), r * z)
+}
+
+/// Generate a point in the unit disk in the xy plane, which is distributed like the projection
+/// of a uniformly distributed point in the unit sphere to the xy plane, exactly 2 values are consumed.
+/// The radius is generated by the inverse of its distribution function `1 - (1 - r²)^(3/2)`.
+pub fn generate_unit_sphere_xy() -> Vector3 {
+    let r = FSize::sqrt(1.0 - FSize::powf(1.0 - generate_size(), 2.0 / 3.0));
+    let phi = 2.0 * PI * generate_size();
+    Vector3::new(r * FSize::cos(phi), r * FSize::sin(phi), 0.0)
 }
 
 /// Generate a random vector
@@ -61,8 +200,8 @@ pub fn generate_cosine_direction() -> Vector3 {
     let r2 = generate_size();
     let z = FSize::sqrt(1.0 - r2);
     let phi = 2.0 * PI * r1;
-    let x = FSize::cos(phi) * 2.0 * FSize::sqrt(r2);
-    let y = FSize::sin(phi) * 2.0 * FSize::sqrt(r2);
+    let x = FSize::cos(phi) * FSize::sqrt(r2);
+    let y = FSize::sin(phi) * FSize::sqrt(r2);
     Vector3::new(x, y, z)
 }
 
@@ -132,11 +271,30 @@ mod random_test {
 
     #[test]
     fn generate_unit_sphere_test() {
-        let v = generate_unit_sphere();
-        for i in 0..3 {
-            test::assert_in_range(v[i], -1.0..1.0);
-        }
-        assert!(glm::length(v) <= 1.0);
+        with_seed(Some(1), || {
+            // the cubed length of uniformly distributed vectors in the unit sphere is uniformly distributed
+            let n = 10000;
+            let mut sum = 0.0;
+            for _ in 0..n {
+                let v = generate_unit_sphere();
+                for i in 0..3 {
+                    test::assert_in_range(v[i], -1.0..1.0);
+                }
+                let length = glm::length(v);
+                assert!(length <= 1.0);
+                sum += length * length * length;
+            }
+            test::assert_eq_float(sum / n as FSize, 0.5, 0.01);
+        });
+
+        // exactly 3 dimensions are consumed
+        let sampler: Arc<dyn Sampler> = Arc::new(sampler::StratifiedSampler::new(4));
+        let dimension = sampler::bounce_dimension(0, sampler::BOUNCE_OFFSET_SCATTER);
+        set_sampler(Some(sampler.clone()), 3);
+        start_dimension(dimension);
+        generate_unit_sphere();
+        assert_eq!(generate_size(), sampler.sample(3, 0, dimension + 3));
+        set_sampler(None, 0);
     }
 
     #[test]
@@ -169,4 +327,103 @@ mod random_test {
         test::assert_in_range(uv.u, 0.0..1.0);
         test::assert_in_range(uv.v, 0.0..1.0);
     }
+
+    #[test]
+    fn seed_test() {
+        set_seed(Some(42));
+        assert!(is_seeded());
+        let a: Vec<FSize> = (0..10).map(|_| generate_size()).collect();
+        set_seed(Some(42));
+        let b: Vec<FSize> = (0..10).map(|_| generate_size()).collect();
+        set_seed(None);
+        assert!(!is_seeded());
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn with_seed_test() {
+        let a = with_seed(Some(7), || generate_vector3());
+        let b = with_seed(Some(7), || generate_vector3());
+        assert_eq!(a, b);
+        assert!(!is_seeded());
+    }
+
+    #[test]
+    fn generate_unit_sphere_xy_test() {
+        with_seed(Some(1), || {
+            // the mean of x² + y² of uniformly distributed vectors in the unit sphere is 2/5
+            let n = 10000;
+            let mut sum = 0.0;
+            for _ in 0..n {
+                let v = generate_unit_sphere_xy();
+                let length = glm::length(v);
+                assert!(length <= 1.0 + 0.000001);
+                test::assert_eq_float(v.z, 0.0, 0.000001);
+                sum += length * length;
+            }
+            test::assert_eq_float(sum / n as FSize, 0.4, 0.01);
+        });
+
+        // exactly 2 dimensions are consumed
+        let sampler: Arc<dyn Sampler> = Arc::new(sampler::StratifiedSampler::new(4));
+        set_sampler(Some(sampler.clone()), 3);
+        let dimension = sampler::bounce_dimension(0, sampler::BOUNCE_OFFSET_SCATTER);
+        start_dimension(dimension);
+        generate_unit_sphere_xy();
+        assert_eq!(generate_size(), sampler.sample(3, 0, dimension + 2));
+        set_sampler(None, 0);
+    }
+
+    #[test]
+    fn generate_cosine_direction_test() {
+        with_seed(Some(1), || {
+            // the mean of cos(theta) of a cosine weighted distribution is 2/3
+            let n = 10000;
+            let mut sum = 0.0;
+            for _ in 0..n {
+                let v = generate_cosine_direction();
+                test::assert_eq_float(glm::length(v), 1.0, 0.000001);
+                assert!(v.z >= 0.0);
+                sum += v.z;
+            }
+            test::assert_eq_float(sum / n as FSize, 2.0 / 3.0, 0.01);
+        });
+    }
+
+    #[test]
+    fn sampler_test() {
+        let sampler: Arc<dyn Sampler> = Arc::new(sampler::StratifiedSampler::new(4));
+        set_sampler(Some(sampler.clone()), 3);
+        let mut values = Vec::default();
+        for index in 0..4 {
+            start_sample(index);
+            start_dimension(sampler::DIMENSION_LENS);
+            values.push(generate_size());
+            assert_eq!(
+                generate_size(),
+                sampler.sample(3, index, sampler::DIMENSION_LENS + 1)
+            );
+        }
+        // the time is a single dimension, the next value doesn't use the first dimension of the bounce
+        start_dimension(sampler::DIMENSION_TIME);
+        assert_eq!(
+            generate_size(),
+            sampler.sample(3, 3, sampler::DIMENSION_TIME)
+        );
+        assert_ne!(
+            generate_size(),
+            sampler.sample(3, 3, sampler::DIMENSION_BOUNCE)
+        );
+        set_sampler(None, 0);
+        let mut strata: Vec<usize> = values.iter().map(|v| (v * 4.0) as usize).collect();
+        strata.sort();
+        assert_eq!(strata, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn derive_seed_test() {
+        assert_eq!(derive_seed(1, 2, 3, 4), derive_seed(1, 2, 3, 4));
+        assert_ne!(derive_seed(1, 2, 3, 4), derive_seed(1, 3, 2, 4));
+        assert_ne!(derive_seed(1, 2, 3, 4), derive_seed(2, 2, 3, 4));
+    }
 }
@@ -0,0 +1,142 @@
+use crate::types::FSize;
+
+mod stratified_sampler;
+pub use self::stratified_sampler::StratifiedSampler;
+
+mod halton_sampler;
+pub use self::halton_sampler::HaltonSampler;
+
+mod sobol_sampler;
+pub use self::sobol_sampler::SobolSampler;
+
+/// First dimension of the sub pixel position (2 dimensions)
+pub const DIMENSION_PIXEL: usize = 0;
+
+/// First dimension of the position on the lens (2 dimensions)
+pub const DIMENSION_LENS: usize = 2;
+
+/// Dimension of the time of the camera ray
+pub const DIMENSION_TIME: usize = 4;
+
+/// First dimension of the first bounce of a path
+pub const DIMENSION_BOUNCE: usize = 5;
+
+/// Number of dimensions, which are reserved for each bounce of a path
+pub const DIMENSIONS_PER_BOUNCE: usize = 13;
+
+/// Offset of the dimensions used by the intersection test (alpha test, volumes) in a bounce (2 dimensions)
+pub const BOUNCE_OFFSET_HIT: usize = 0;
+
+/// Offset of the dimensions used by the material scattering in a bounce (4 dimensions)
+pub const BOUNCE_OFFSET_SCATTER: usize = 2;
+
+/// Offset of the dimensions used by the probability density function of the scattered direction in a bounce
+/// (3 dimensions)
+pub const BOUNCE_OFFSET_PDF: usize = 6;
+
+/// Offset of the dimensions used by the sampling of a light (next event estimation) in a bounce (3 dimensions)
+pub const BOUNCE_OFFSET_LIGHT: usize = 9;
+
+/// Offset of the dimension used by the Russian roulette in a bounce (1 dimension)
+pub const BOUNCE_OFFSET_ROULETTE: usize = 12;
+
+const BOUNCE_OFFSETS: [usize; 5] = [
+    BOUNCE_OFFSET_HIT,
+    BOUNCE_OFFSET_SCATTER,
+    BOUNCE_OFFSET_PDF,
+    BOUNCE_OFFSET_LIGHT,
+    BOUNCE_OFFSET_ROULETTE,
+];
+
+/// Sample generator
+///
+/// Supplies a sample value for each dimension of a sample of a pixel.
+/// The dimensions of a path are laid out in a fixed order:
+/// the sub pixel position, the lens position, the time and [`DIMENSIONS_PER_BOUNCE`] dimensions for each bounce.
+/// Random numbers, which are generated by [`crate::random`], consume the dimensions one after another.
+pub trait Sampler: Sync + Send {
+    /// Sample value in the range [0.0, 1.0) of the dimension `dimension` of the sample `index`.
+    /// `seed` decorrelates the sequences of different pixels.
+    fn sample(&self, seed: u64, index: usize, dimension: usize) -> FSize;
+}
+
+/// First dimension of a bounce of a path
+pub fn bounce_dimension(depth: usize, offset: usize) -> usize {
+    DIMENSION_BOUNCE + depth * DIMENSIONS_PER_BOUNCE + offset
+}
+
+/// End of the group of dimensions, which contains the dimension `dimension`.
+/// The groups are the sub pixel position, the lens position, the time and the offsets of the bounces.
+pub fn dimension_end(dimension: usize) -> usize {
+    if dimension < DIMENSION_LENS {
+        DIMENSION_LENS
+    } else if dimension < DIMENSION_TIME {
+        DIMENSION_TIME
+    } else if dimension < DIMENSION_BOUNCE {
+        DIMENSION_BOUNCE
+    } else {
+        let offset = (dimension - DIMENSION_BOUNCE) % DIMENSIONS_PER_BOUNCE;
+        let next_offset = BOUNCE_OFFSETS
+            .iter()
+            .find(|o| **o > offset)
+            .copied()
+            .unwrap_or(DIMENSIONS_PER_BOUNCE);
+        dimension - offset + next_offset
+    }
+}
+
+/// Hash of a seed and a list of values
+pub(crate) fn hash(seed: u64, values: &[u64]) -> u64 {
+    values.iter().fold(mix(seed), |h, v| mix(h ^ mix(*v)))
+}
+
+/// Converts a hash value to a floating point value in the range [0.0, 1.0)
+pub(crate) fn hash_to_size(h: u64) -> FSize {
+    (h >> 11) as FSize / (1u64 << 53) as FSize
+}
+
+/// SplitMix64 finalizer
+pub(crate) fn mix(v: u64) -> u64 {
+    let mut z = v.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod sampler_test {
+    use super::*;
+    use crate::test;
+
+    #[test]
+    fn bounce_dimension_test() {
+        assert_eq!(bounce_dimension(0, 0), DIMENSION_BOUNCE);
+        assert_eq!(
+            bounce_dimension(2, BOUNCE_OFFSET_PDF),
+            DIMENSION_BOUNCE + 2 * DIMENSIONS_PER_BOUNCE + BOUNCE_OFFSET_PDF
+        );
+    }
+
+    #[test]
+    fn dimension_end_test() {
+        assert_eq!(dimension_end(DIMENSION_PIXEL), DIMENSION_LENS);
+        assert_eq!(dimension_end(DIMENSION_LENS + 1), DIMENSION_TIME);
+        assert_eq!(dimension_end(DIMENSION_TIME), DIMENSION_BOUNCE);
+        assert_eq!(
+            dimension_end(bounce_dimension(3, BOUNCE_OFFSET_SCATTER + 1)),
+            bounce_dimension(3, BOUNCE_OFFSET_PDF)
+        );
+        assert_eq!(
+            dimension_end(bounce_dimension(3, BOUNCE_OFFSET_ROULETTE)),
+            bounce_dimension(4, BOUNCE_OFFSET_HIT)
+        );
+    }
+
+    #[test]
+    fn hash_to_size_test() {
+        for i in 0..100 {
+            test::assert_in_range(hash_to_size(hash(1, &[i])), 0.0..1.0);
+        }
+        test::assert_in_range(hash_to_size(u64::MAX), 0.0..1.0);
+    }
+}
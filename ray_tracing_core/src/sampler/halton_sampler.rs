@@ -0,0 +1,85 @@
+use crate::sampler::{hash, hash_to_size, Sampler};
+use crate::types::FSize;
+
+/// Bases of the Halton sequence, the dimension `i` uses the `i`-th prime number
+const HALTON_PRIMES: [u64; 64] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307,
+    311,
+];
+
+/// Halton sampler
+///
+/// The dimension `i` of the sample `index` is the radical inverse of `index` in the base of the `i`-th prime.
+/// The sequence of each pixel is shifted by a random offset per dimension (Cranley-Patterson rotation).
+/// Dimensions beyond the table of prime numbers are uniform random.
+pub struct HaltonSampler {}
+
+impl HaltonSampler {
+    pub fn new() -> HaltonSampler {
+        HaltonSampler {}
+    }
+
+    /// Mirrors the digits of `index` in the base `base` at the decimal point
+    pub fn radical_inverse(base: u64, index: u64) -> FSize {
+        let inverse_base = 1.0 / base as FSize;
+        let mut f = 1.0;
+        let mut result = 0.0;
+        let mut i = index;
+        while i > 0 {
+            f *= inverse_base;
+            result += f * (i % base) as FSize;
+            i /= base;
+        }
+        result
+    }
+}
+
+impl Default for HaltonSampler {
+    fn default() -> Self {
+        HaltonSampler::new()
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn sample(&self, seed: u64, index: usize, dimension: usize) -> FSize {
+        if dimension >= HALTON_PRIMES.len() {
+            return hash_to_size(hash(seed, &[dimension as u64, index as u64]));
+        }
+        let value = HaltonSampler::radical_inverse(HALTON_PRIMES[dimension], index as u64)
+            + hash_to_size(hash(seed, &[dimension as u64]));
+        if value >= 1.0 {
+            value - 1.0
+        } else {
+            value
+        }
+    }
+}
+
+#[cfg(test)]
+mod halton_sampler_test {
+    use super::*;
+    use crate::test;
+
+    #[test]
+    fn radical_inverse_test() {
+        test::assert_eq_float(HaltonSampler::radical_inverse(2, 1), 0.5, 0.000001);
+        test::assert_eq_float(HaltonSampler::radical_inverse(2, 6), 0.375, 0.000001);
+        test::assert_eq_float(HaltonSampler::radical_inverse(3, 5), 7.0 / 9.0, 0.000001);
+    }
+
+    #[test]
+    fn sample_test() {
+        let sampler = HaltonSampler::new();
+        for dimension in [0, 1, 10, 63, 64, 100] {
+            for index in 0..20 {
+                test::assert_in_range(sampler.sample(7, index, dimension), 0.0..1.0);
+            }
+        }
+        // the first 2 samples of the base 2 dimension are in different halves
+        let a = sampler.sample(7, 0, 0);
+        let b = sampler.sample(7, 1, 0);
+        test::assert_eq_float(FSize::abs(a - b), 0.5, 0.000001);
+    }
+}
@@ -0,0 +1,135 @@
+use crate::sampler::{hash, Sampler};
+use crate::types::FSize;
+
+/// Parameters `(s, a, m)` of the primitive polynomials of the dimensions 2 to 4 of the Sobol sequence
+/// (S. Joe and F. Y. Kuo, "Constructing Sobol sequences with better two-dimensional projections")
+const SOBOL_POLYNOMIALS: [(usize, u32, [u32; 3]); 3] =
+    [(1, 0, [1, 0, 0]), (2, 1, [1, 3, 0]), (3, 1, [1, 3, 1])];
+
+/// Number of dimensions of the Sobol sequence, higher dimensions are padded with independently scrambled patterns
+const SOBOL_DIMENSIONS: usize = 4;
+
+/// Owen scrambled Sobol sampler
+///
+/// 4 dimensional Sobol points with hash based nested uniform scrambling
+/// (Brent Burley, "Practical Hash-based Owen Scrambling").
+/// Higher dimensions are padded with independently shuffled and scrambled 4 dimensional patterns.
+pub struct SobolSampler {
+    directions: [[u32; 32]; SOBOL_DIMENSIONS],
+}
+
+impl SobolSampler {
+    pub fn new() -> SobolSampler {
+        let mut directions = [[0u32; 32]; SOBOL_DIMENSIONS];
+        for (i, v) in directions[0].iter_mut().enumerate() {
+            *v = 1 << (31 - i);
+        }
+        for (dimension, (s, a, m)) in SOBOL_POLYNOMIALS.iter().enumerate() {
+            let v = &mut directions[dimension + 1];
+            for i in 0..*s {
+                v[i] = m[i] << (31 - i);
+            }
+            for i in *s..32 {
+                v[i] = v[i - s] ^ (v[i - s] >> s);
+                for k in 1..*s {
+                    v[i] ^= ((a >> (s - 1 - k)) & 1) * v[i - k];
+                }
+            }
+        }
+        SobolSampler { directions }
+    }
+
+    /// Unscrambled Sobol point of the dimension `dimension` (< 4) as 32 bit fixed point value
+    pub fn sobol(&self, index: u32, dimension: usize) -> u32 {
+        let mut result = 0;
+        let mut i = index;
+        let mut bit = 0;
+        while i != 0 {
+            if i & 1 != 0 {
+                result ^= self.directions[dimension][bit];
+            }
+            i >>= 1;
+            bit += 1;
+        }
+        result
+    }
+
+    fn laine_karras_permutation(x: u32, seed: u32) -> u32 {
+        let mut x = x.wrapping_add(seed);
+        x ^= x.wrapping_mul(0x6c50b47c);
+        x ^= x.wrapping_mul(0xb82f1e52);
+        x ^= x.wrapping_mul(0xc7afe638);
+        x ^= x.wrapping_mul(0x8d22f6e6);
+        x
+    }
+
+    fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+        SobolSampler::laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+    }
+}
+
+impl Default for SobolSampler {
+    fn default() -> Self {
+        SobolSampler::new()
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn sample(&self, seed: u64, index: usize, dimension: usize) -> FSize {
+        let pattern = (dimension / SOBOL_DIMENSIONS) as u64;
+        let sobol_dimension = dimension % SOBOL_DIMENSIONS;
+        let shuffled_index =
+            SobolSampler::nested_uniform_scramble(index as u32, hash(seed, &[pattern]) as u32);
+        let value = SobolSampler::nested_uniform_scramble(
+            self.sobol(shuffled_index, sobol_dimension),
+            hash(seed, &[pattern, sobol_dimension as u64 + 1]) as u32,
+        );
+        value as FSize / (1u64 << 32) as FSize
+    }
+}
+
+#[cfg(test)]
+mod sobol_sampler_test {
+    use super::*;
+    use crate::test;
+
+    #[test]
+    fn sobol_test() {
+        let sampler = SobolSampler::new();
+        let to_size = |v: u32| v as FSize / (1u64 << 32) as FSize;
+        let expected = [
+            [0.0, 0.5, 0.75, 0.25, 0.625],
+            [0.0, 0.5, 0.75, 0.25, 0.375],
+            [0.0, 0.5, 0.75, 0.25, 0.125],
+        ];
+        for (dimension, values) in expected.iter().enumerate() {
+            for (index, value) in values.iter().enumerate() {
+                test::assert_eq_float(
+                    to_size(sampler.sobol(index as u32, dimension + 1)),
+                    *value,
+                    0.000001,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn sample_test() {
+        let sampler = SobolSampler::new();
+        for dimension in 0..20 {
+            for index in 0..20 {
+                test::assert_in_range(sampler.sample(3, index, dimension), 0.0..1.0);
+            }
+        }
+        // scrambling preserves the stratification of the first 4 points in the first 2 dimensions of each pattern
+        for (d0, d1) in [(0, 1), (4, 5), (8, 9)] {
+            let mut quadrants = [0; 4];
+            for index in 0..4 {
+                let x = sampler.sample(11, index, d0);
+                let y = sampler.sample(11, index, d1);
+                quadrants[(x * 2.0) as usize + 2 * (y * 2.0) as usize] += 1;
+            }
+            assert_eq!(quadrants, [1, 1, 1, 1]);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use crate::sampler::{hash, hash_to_size, Sampler};
+use crate::types::FSize;
+
+/// Stratified sampler
+///
+/// Each dimension is divided into `samples_per_pixel` strata and each sample of a pixel is placed
+/// in a different stratum with a random offset. The strata are randomly permuted for each dimension
+/// (Latin hypercube sampling), so that the dimensions are uncorrelated.
+/// Samples beyond `samples_per_pixel` are uniform random.
+pub struct StratifiedSampler {
+    pub samples_per_pixel: usize,
+}
+
+impl StratifiedSampler {
+    pub fn new(samples_per_pixel: usize) -> StratifiedSampler {
+        StratifiedSampler { samples_per_pixel }
+    }
+
+    /// Random permutation of the index `i` in the range [0, `l`) with the permutation seed `p`.
+    /// See Andrew Kensler, "Correlated Multi-Jittered Sampling".
+    fn permute(i: u32, l: u32, p: u32) -> u32 {
+        let mut w = l - 1;
+        w |= w >> 1;
+        w |= w >> 2;
+        w |= w >> 4;
+        w |= w >> 8;
+        w |= w >> 16;
+        let mut i = i;
+        loop {
+            i ^= p;
+            i = i.wrapping_mul(0xe170893d);
+            i ^= p >> 16;
+            i ^= (i & w) >> 4;
+            i ^= p >> 8;
+            i = i.wrapping_mul(0x0929eb3f);
+            i ^= p >> 23;
+            i ^= (i & w) >> 1;
+            i = i.wrapping_mul(1 | p >> 27);
+            i = i.wrapping_mul(0x6935fa69);
+            i ^= (i & w) >> 11;
+            i = i.wrapping_mul(0x74dcb303);
+            i ^= (i & w) >> 2;
+            i = i.wrapping_mul(0x9e501cc3);
+            i ^= (i & w) >> 2;
+            i = i.wrapping_mul(0xc860a3df);
+            i &= w;
+            i ^= i >> 5;
+            if i < l {
+                break;
+            }
+        }
+        (i.wrapping_add(p)) % l
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn sample(&self, seed: u64, index: usize, dimension: usize) -> FSize {
+        let jitter = hash_to_size(hash(seed, &[dimension as u64, index as u64]));
+        if index >= self.samples_per_pixel || self.samples_per_pixel > u32::MAX as usize {
+            return jitter;
+        }
+        let stratum = StratifiedSampler::permute(
+            index as u32,
+            self.samples_per_pixel as u32,
+            hash(seed, &[dimension as u64]) as u32,
+        );
+        (stratum as FSize + jitter) / self.samples_per_pixel as FSize
+    }
+}
+
+#[cfg(test)]
+mod stratified_sampler_test {
+    use super::*;
+    use crate::test;
+
+    #[test]
+    fn permute_test() {
+        for l in [1, 7, 16, 100] {
+            let mut values: Vec<u32> = (0..l)
+                .map(|i| StratifiedSampler::permute(i, l, 1234))
+                .collect();
+            values.sort();
+            assert_eq!(values, (0..l).collect::<Vec<u32>>());
+        }
+    }
+
+    #[test]
+    fn sample_test() {
+        let n = 16;
+        let sampler = StratifiedSampler::new(n);
+        for dimension in 0..10 {
+            let mut strata = vec![0; n];
+            for index in 0..n {
+                let value = sampler.sample(42, index, dimension);
+                test::assert_in_range(value, 0.0..1.0);
+                strata[(value * n as FSize) as usize] += 1;
+            }
+            assert!(strata.iter().all(|count| *count == 1));
+        }
+        test::assert_in_range(sampler.sample(42, n + 5, 0), 0.0..1.0);
+    }
+}
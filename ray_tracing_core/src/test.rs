@@ -4,5 +4,11 @@ pub use self::test_help::{
     assert_in_range_vector3, assert_in_range_vector4,
 };
 
+mod sample_statistics;
+pub use self::sample_statistics::{assert_in_confidence_interval, SampleStatistics};
+
 mod test_scene_simple;
 pub use test_scene_simple::TestSceneSimple;
+
+mod spheres_scene;
+pub use self::spheres_scene::spheres_scene;
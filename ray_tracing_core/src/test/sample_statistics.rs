@@ -0,0 +1,107 @@
+use crate::types::{ColorRGB, FSize};
+
+/// Mean and variance of the samples of a Monte Carlo estimator, accumulated with Welford's algorithm
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SampleStatistics {
+    pub count: usize,
+    pub mean: ColorRGB,
+    sum_squared_deviation: ColorRGB,
+}
+
+impl SampleStatistics {
+    pub fn new() -> SampleStatistics {
+        SampleStatistics {
+            count: 0,
+            mean: ColorRGB::new(0.0, 0.0, 0.0),
+            sum_squared_deviation: ColorRGB::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn add(&mut self, sample: ColorRGB) {
+        self.count += 1;
+        let delta = sample - self.mean;
+        self.mean = self.mean + delta / self.count as FSize;
+        self.sum_squared_deviation = self.sum_squared_deviation + delta * (sample - self.mean);
+    }
+
+    /// Unbiased variance of the samples
+    pub fn variance(&self) -> ColorRGB {
+        if self.count < 2 {
+            ColorRGB::new(0.0, 0.0, 0.0)
+        } else {
+            self.sum_squared_deviation / (self.count - 1) as FSize
+        }
+    }
+
+    /// Standard deviation of the mean
+    pub fn standard_error(&self) -> ColorRGB {
+        let variance = self.variance() / FSize::max(1.0, self.count as FSize);
+        ColorRGB::new(variance.x.sqrt(), variance.y.sqrt(), variance.z.sqrt())
+    }
+}
+
+impl Default for SampleStatistics {
+    fn default() -> SampleStatistics {
+        SampleStatistics::new()
+    }
+}
+
+/// Expected value in the confidence interval `mean ± z * standard error` of the samples.
+/// `eps` is an absolute tolerance for estimators without variance.
+#[allow(dead_code)]
+pub fn assert_in_confidence_interval(
+    statistics: &SampleStatistics,
+    expected: ColorRGB,
+    z: FSize,
+    eps: FSize,
+) {
+    let standard_error = statistics.standard_error();
+    for i in 0..3 {
+        assert!(
+            FSize::abs(statistics.mean[i] - expected[i]) <= z * standard_error[i] + eps,
+            "expected value is not in the confidence interval: {} != {} ± {}",
+            expected[i],
+            statistics.mean[i],
+            z * standard_error[i]
+        );
+    }
+}
+
+#[cfg(test)]
+mod sample_statistics_test {
+    use super::*;
+
+    #[test]
+    fn sample_statistics_test() {
+        let mut statistics = SampleStatistics::new();
+        for v in [1.0, 2.0, 3.0, 4.0] {
+            statistics.add(ColorRGB::new(v, 2.0 * v, 5.0));
+        }
+        assert_eq!(statistics.count, 4);
+        assert_eq!(statistics.mean, ColorRGB::new(2.5, 5.0, 5.0));
+        let variance = statistics.variance();
+        assert!((variance.x - 5.0 / 3.0).abs() < 1.0e-12);
+        assert!((variance.y - 20.0 / 3.0).abs() < 1.0e-12);
+        assert_eq!(variance.z, 0.0);
+        assert!((statistics.standard_error().x - FSize::sqrt(5.0 / 12.0)).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn assert_in_confidence_interval_test() {
+        let mut statistics = SampleStatistics::new();
+        for v in [0.9, 1.1, 0.8, 1.2] {
+            statistics.add(ColorRGB::new(v, v, v));
+        }
+        assert_in_confidence_interval(&statistics, ColorRGB::new(1.1, 1.1, 1.1), 3.0, 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected value is not in the confidence interval")]
+    fn assert_in_confidence_interval_test_fail() {
+        let mut statistics = SampleStatistics::new();
+        for v in [0.9, 1.1, 0.8, 1.2] {
+            statistics.add(ColorRGB::new(v, v, v));
+        }
+        assert_in_confidence_interval(&statistics, ColorRGB::new(2.0, 2.0, 2.0), 3.0, 0.0);
+    }
+}
@@ -0,0 +1,28 @@
+use crate::geometry::shape::Sphere;
+use crate::geometry::Geometry;
+use crate::material::Lambertian;
+use crate::texture::ConstantTexture;
+use crate::types::{ColorRGBA, FSize, Point3};
+use std::sync::Arc;
+
+/// Grid of 20 x 20 small spheres on a large ground sphere, for testing collections
+pub fn spheres_scene() -> Vec<Arc<dyn Geometry>> {
+    let material = Arc::new(Lambertian::new(Arc::new(ConstantTexture::new(
+        ColorRGBA::new(1.0, 1.0, 1.0, 1.0),
+    ))));
+    let mut list: Vec<Arc<dyn Geometry>> = vec![Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        material.clone(),
+    ))];
+    for a in -10..10 {
+        for b in -10..10 {
+            list.push(Arc::new(Sphere::new(
+                Point3::new(a as FSize + 0.5, 0.2, b as FSize + 0.5),
+                0.2,
+                material.clone(),
+            )));
+        }
+    }
+    list
+}
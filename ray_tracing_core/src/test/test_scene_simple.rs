@@ -47,7 +47,7 @@ impl TestSceneSimple {
             Arc::new(camera),
             Arc::new(sky),
             world,
-            None,
+            Vec::default(),
         );
 
         TestSceneSimple { scene }
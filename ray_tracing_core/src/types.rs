@@ -25,6 +25,9 @@ pub type Vector2 = glm::Vector2<FSize>;
 /// Data type that represents a three-dimensional Cartesian vector
 pub type Vector4 = glm::Vector4<FSize>;
 
+/// Data type that represents a 4x4 transformation matrix
+pub type Matrix4 = glm::Matrix4<FSize>;
+
 /// Object that represents texture coordinates
 ///
 /// We do not use `glm :: Vector2` here, as the texture coordinates may be extended by a `w` component or a layer in the future
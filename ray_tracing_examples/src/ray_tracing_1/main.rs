@@ -12,7 +12,7 @@ use ray_tracing_show_image;
 use ray_tracing_utility::image;
 use ray_tracing_utility::serialization::json;
 use ray_tracing_utility::view;
-use ray_tracing_utility::view::{ViewModel, Viewer};
+use ray_tracing_utility::view::{DisplaySettings, SamplerType, ViewModel, Viewer};
 use std::env;
 use std::error::Error;
 use std::fs;
@@ -34,6 +34,12 @@ fn main() -> Result<(), Box<dyn Error>> {
         repetitions_threads: 2,
         repetitions: 1000,
         samples: 10,
+        seed: None,
+        sampler: SamplerType::Independent,
+        adaptive_sampling: None,
+        display: DisplaySettings::default(),
+        aovs: Vec::default(),
+        denoise: None,
     };
 
     let view_model = match args.next() {
@@ -171,7 +177,7 @@ fn main() -> Result<(), Box<dyn Error>> {
             ColorRGB::new(0.5, 0.7, 1.0),
         )),
         BVHNode::new(&object_vec, 0.0..1.0),
-        None,
+        Vec::default(),
     );
 
     let window = ray_tracing_show_image::ShowImageWindow::new(view_model.cx, view_model.cy);
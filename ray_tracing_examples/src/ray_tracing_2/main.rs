@@ -1,6 +1,6 @@
 use ray_tracing_core::core::{Camera, Configuration, Scene};
 use ray_tracing_core::environment::Sky;
-use ray_tracing_core::geometry::collection::{BVHNode, GeometryList};
+use ray_tracing_core::geometry::collection::BVHNode;
 use ray_tracing_core::geometry::instancing::{FlipNormals, RotateY, Translate};
 use ray_tracing_core::geometry::shape::{Cuboid, MovableSphere, Sphere, XZRect};
 use ray_tracing_core::geometry::volume::ConstantMedium;
@@ -14,7 +14,7 @@ use ray_tracing_utility::image;
 use ray_tracing_utility::serialization::json;
 use ray_tracing_utility::serialization::texture::BitmapFile;
 use ray_tracing_utility::view;
-use ray_tracing_utility::view::{ViewModel, Viewer};
+use ray_tracing_utility::view::{DisplaySettings, SamplerType, ViewModel, Viewer};
 use std::env;
 use std::error::Error;
 use std::fs;
@@ -32,6 +32,12 @@ fn main() -> Result<(), Box<dyn Error>> {
         repetitions_threads: 2,
         repetitions: 10000,
         samples: 10,
+        seed: None,
+        sampler: SamplerType::Independent,
+        adaptive_sampling: None,
+        display: DisplaySettings::default(),
+        aovs: Vec::default(),
+        denoise: None,
     };
 
     let view_model = match args.next() {
@@ -259,7 +265,7 @@ fn main() -> Result<(), Box<dyn Error>> {
             ColorRGB::new(0.0, 0.0, 0.0),
         )),
         BVHNode::new(&object_vec, 0.0..1.0),
-        Some(Arc::new(GeometryList::new(&lights))),
+        lights,
     );
 
     let window = ray_tracing_show_image::ShowImageWindow::new(view_model.cx, view_model.cy);
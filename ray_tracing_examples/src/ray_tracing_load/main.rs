@@ -1,18 +1,26 @@
+use ray_tracing_core::random;
 use ray_tracing_show_image;
 use ray_tracing_utility::image;
+use ray_tracing_utility::render;
 use ray_tracing_utility::serialization::core::DeserializeOptions;
 use ray_tracing_utility::serialization::json;
 use ray_tracing_utility::view;
-use ray_tracing_utility::view::{ViewModel, Viewer};
+use ray_tracing_utility::view::{
+    CheckpointSettings, DisplaySettings, SamplerType, ViewModel, Viewer,
+};
 use std::env;
 use std::error::Error;
 use std::fs;
 use std::path::Path;
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
+/// With `--checkpoint` the state of the render is saved to `./temp/<image name>.checkpoint` every minute
+/// and when the window is closed. A render with the same scene and view model is resumed from the checkpoint.
+///
 /// ```lang-none
 /// cargo run --bin rt_load ./scene/TestSceneSimple.json ./scene/TestConfiguration.json
+/// cargo run --release --bin rt_load ./scene/RoomGlassSphere.json ./scene/TestConfigurationSquare.json --checkpoint
 /// ```
 #[show_image::main]
 fn main() -> Result<(), Box<dyn Error>> {
@@ -23,6 +31,12 @@ fn main() -> Result<(), Box<dyn Error>> {
         repetitions_threads: 2,
         repetitions: 100,
         samples: 10,
+        seed: None,
+        sampler: SamplerType::Independent,
+        adaptive_sampling: None,
+        display: DisplaySettings::default(),
+        aovs: Vec::default(),
+        denoise: None,
     };
     let mut args = env::args();
     args.next();
@@ -34,6 +48,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         Some(arg) => json::deserialize_view_model(&fs::read_to_string(arg)?)?,
         None => default_view_model,
     };
+    let checkpoint = args.next().as_deref() == Some("--checkpoint");
     let mut json_dir = env::current_dir()?;
     match Path::new(&file_path).parent() {
         Some(path) => {
@@ -47,21 +62,19 @@ fn main() -> Result<(), Box<dyn Error>> {
     }
     let file_name = Path::new(&file_path).file_stem().unwrap().to_str().unwrap();
     let target_root = "./temp";
-    let target_file_name = format!(
-        "{}_{}x{}_{}_samples",
-        file_name,
-        view_model.cx,
-        view_model.cy,
-        view_model.repetitions * view_model.samples
-    );
+    let target_file_name = render::image_file_name(file_name, &view_model);
 
     let json_scene = fs::read_to_string(file_path)?;
     let window = ray_tracing_show_image::ShowImageWindow::new(view_model.cx, view_model.cy);
     let test_file_name = format!("{}/{}_test_", target_root, target_file_name);
     let options = DeserializeOptions::form_path(json_dir.as_path());
+    let scene = random::with_seed(view_model.seed, || {
+        json::deserialize_scene_with_options(&json_scene, &options)
+    })?;
+    let adaptive_sampling = view_model.adaptive_sampling.is_some();
     let mut viewer = Viewer::new(
         view_model,
-        Arc::new(json::deserialize_scene_with_options(&json_scene, &options)?),
+        Arc::new(scene),
         window.clone(),
         Box::new(move |image_number, cx, cy, data| {
             let file_name = format!("{}{}.png", test_file_name, image_number);
@@ -69,6 +82,13 @@ fn main() -> Result<(), Box<dyn Error>> {
             println!("saved {}", file_name);
         }),
     )?;
+    if checkpoint {
+        viewer.set_checkpoint(Some(CheckpointSettings {
+            path: Path::new(target_root).join(format!("{}.checkpoint", target_file_name)),
+            interval: Duration::from_secs(60),
+            resume: true,
+        }))?;
+    }
 
     println!("start");
     let start_time = SystemTime::now();
@@ -90,6 +110,11 @@ fn main() -> Result<(), Box<dyn Error>> {
             let file_name = format!("{}/{}.png", target_root, target_file_name);
             image::save_image(&file_name, cx, cy, &pixel_data);
             println!("saved {}", file_name);
+            if adaptive_sampling {
+                let file_name = format!("{}/{}_heat_map.png", target_root, target_file_name);
+                image::save_image(&file_name, cx, cy, &viewer.sample_count_heat_map());
+                println!("saved {}", file_name);
+            }
 
             loop {
                 match window.handle_events() {
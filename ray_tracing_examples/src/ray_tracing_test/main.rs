@@ -1,3 +1,4 @@
+use ray_tracing_core::display::DisplayPipeline;
 use ray_tracing_core::random;
 use ray_tracing_core::test::TestSceneSimple;
 use ray_tracing_core::types::ColorRGB;
@@ -11,6 +12,7 @@ fn main() {
     let samples = 100;
     let scene = TestSceneSimple::new().scene;
     let ray_trace_iter = IteratorExp2::new(cx, cy);
+    let display = DisplayPipeline::default();
 
     let mut pixel_data: Vec<u8> = Vec::with_capacity(cx * cy * 4);
     pixel_data.resize(cx * cy * 4, 0);
@@ -25,10 +27,7 @@ fn main() {
         c = c / samples as FSize;
 
         let i = (y * cx) + x;
-        pixel_data[i * 4] = (c[0].sqrt() * 255.0).round() as u8;
-        pixel_data[i * 4 + 1] = (c[1].sqrt() * 255.0).round() as u8;
-        pixel_data[i * 4 + 2] = (c[2].sqrt() * 255.0).round() as u8;
-        pixel_data[i * 4 + 3] = 255;
+        pixel_data[i * 4..i * 4 + 4].copy_from_slice(&display.to_rgba8(&c, x, y));
     }
 
     let file_name = "./temp/rt_test.png";
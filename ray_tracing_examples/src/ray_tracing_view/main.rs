@@ -2,7 +2,7 @@ use ray_tracing_core::test::TestSceneSimple;
 use ray_tracing_show_image;
 use ray_tracing_utility::image;
 use ray_tracing_utility::view;
-use ray_tracing_utility::view::{ViewModel, Viewer};
+use ray_tracing_utility::view::{DisplaySettings, SamplerType, ViewModel, Viewer};
 use std::error::Error;
 use std::sync::Arc;
 use std::time::SystemTime;
@@ -15,6 +15,12 @@ fn main() -> Result<(), Box<dyn Error>> {
         repetitions_threads: 2,
         repetitions: 100,
         samples: 10,
+        seed: None,
+        sampler: SamplerType::Independent,
+        adaptive_sampling: None,
+        display: DisplaySettings::default(),
+        aovs: Vec::default(),
+        denoise: None,
     };
     let window = ray_tracing_show_image::ShowImageWindow::new(view_model.cx, view_model.cy);
     let mut viewer = Viewer::new(
@@ -15,7 +15,7 @@
 //! ```rust
 //! use ray_tracing_core::test::TestSceneSimple;
 //! use ray_tracing_utility::view;
-//! use ray_tracing_utility::view::{Viewer, ViewModel};
+//! use ray_tracing_utility::view::{DisplaySettings, SamplerType, Viewer, ViewModel};
 //! use std::error::Error;
 //! use std::sync::Arc;
 //!
@@ -27,6 +27,12 @@
 //!         repetitions_threads: 2,
 //!         repetitions: 5,
 //!         samples: 2,
+//!         seed: None,
+//!         sampler: SamplerType::Independent,
+//!         adaptive_sampling: None,
+//!         display: DisplaySettings::default(),
+//!         aovs: Vec::default(),
+//!         denoise: None,
 //!     };
 //!     let window = ray_tracing_show_image::ShowImageWindow::new(view_model.cx, view_model.cy);
 //!     let mut viewer = Viewer::new(
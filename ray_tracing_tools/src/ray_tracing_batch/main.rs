@@ -0,0 +1,189 @@
+use ray_tracing_utility::render;
+use ray_tracing_utility::render::BatchJob;
+use ray_tracing_utility::serialization::json;
+use ray_tracing_utility::view::{DisplaySettings, SamplerType, ViewModel};
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+const USAGE: &str = "usage: rt_batch [<scene directory>] [--view <default view_model.json>] \
+[--output <image directory>] [--format <png|hdr|pfm|exr>] [--parallel <number of scenes rendered at once>]";
+
+/// Result of the render of one scene
+struct BatchResult {
+    name: String,
+    image: String,
+    load_seconds: f64,
+    render_seconds: f64,
+    error: Option<String>,
+}
+
+/// Renders all scenes of a directory without a window.
+///
+/// A scene `<name>.json` is rendered with the view model `<name>.view.json`, if it exists,
+/// else with the default view model. The images are named like the images of `rt_load`
+/// and a summary with the render times is written to `batch_report.md` in the image directory.
+///
+/// ```lang-none
+/// cargo run --release --bin rt_batch ./scene --view ./scene/TestConfiguration.json --output ./temp/batch
+/// cargo run --release --bin rt_batch ./scene --parallel 2 --format exr
+/// ```
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut scene_dir = String::from("scene");
+    let mut output_dir = String::from("./temp");
+    let mut parallel = 1;
+    let mut format = String::from("png");
+    let mut default_view_model = ViewModel {
+        cx: 400,
+        cy: 200,
+        repetitions_threads: 2,
+        repetitions: 100,
+        samples: 10,
+        seed: None,
+        sampler: SamplerType::Independent,
+        adaptive_sampling: None,
+        display: DisplaySettings::default(),
+        aovs: Vec::default(),
+        denoise: None,
+    };
+
+    let mut args = env::args();
+    args.next();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or(format!("missing value of {}\n{}", arg, USAGE))
+        };
+        match arg.as_str() {
+            "--view" => {
+                default_view_model = json::deserialize_view_model(&fs::read_to_string(value()?)?)?
+            }
+            "--output" => output_dir = value()?,
+            "--format" => format = value()?,
+            "--parallel" => parallel = usize::max(1, value()?.parse::<usize>()?),
+            _ if !arg.starts_with("--") => scene_dir = arg,
+            _ => return Err(format!("unknown argument {}\n{}", arg, USAGE).into()),
+        }
+    }
+    fs::create_dir_all(&output_dir)?;
+
+    let jobs = render::find_batch_jobs(Path::new(&scene_dir))?;
+    println!("{} scenes found in {}", jobs.len(), scene_dir);
+    let start_time = Instant::now();
+    let queue = Arc::new(Mutex::new(jobs.into_iter().enumerate()));
+    let results = Arc::new(Mutex::new(Vec::default()));
+    let mut handles = Vec::default();
+    for _ in 0..parallel {
+        let queue = queue.clone();
+        let results = results.clone();
+        let default_view_model = default_view_model.clone();
+        let output_dir = PathBuf::from(&output_dir);
+        let format = format.clone();
+        handles.push(thread::spawn(move || loop {
+            let next = queue.lock().unwrap().next();
+            match next {
+                Some((index, job)) => {
+                    println!("render {}", job.name);
+                    let result = render_job(&job, &default_view_model, &output_dir, &format);
+                    match &result.error {
+                        Some(error) => println!("failed {}: {}", job.name, error),
+                        None => println!("saved {}", result.image),
+                    }
+                    results.lock().unwrap().push((index, result));
+                }
+                None => break,
+            }
+        }));
+    }
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let mut results = results.lock().unwrap();
+    results.sort_by_key(|(index, _)| *index);
+    let report = report(
+        &scene_dir,
+        results.iter().map(|(_, result)| result),
+        start_time.elapsed().as_secs_f64(),
+    );
+    print!("{}", report);
+    let report_file_name = Path::new(&output_dir).join("batch_report.md");
+    fs::write(&report_file_name, report)?;
+    println!("saved {}", report_file_name.display());
+    Ok(())
+}
+
+fn render_job(
+    job: &BatchJob,
+    default_view_model: &ViewModel,
+    output_dir: &Path,
+    format: &str,
+) -> BatchResult {
+    let mut result = BatchResult {
+        name: job.name.clone(),
+        image: String::default(),
+        load_seconds: 0.0,
+        render_seconds: 0.0,
+        error: None,
+    };
+    let render = |result: &mut BatchResult| -> Result<(), Box<dyn Error>> {
+        let view_model = match &job.view_model_path {
+            Some(path) => json::deserialize_view_model(&fs::read_to_string(path)?)?,
+            None => default_view_model.clone(),
+        };
+        let file_name = output_dir.join(format!(
+            "{}.{}",
+            render::image_file_name(&job.name, &view_model),
+            format
+        ));
+        result.image = file_name.display().to_string();
+
+        let start_time = Instant::now();
+        let scene = render::load_scene(&job.scene_path, view_model.seed)?;
+        result.load_seconds = start_time.elapsed().as_secs_f64();
+
+        let start_time = Instant::now();
+        let image = render::render(view_model, Arc::new(scene), None)?;
+        result.render_seconds = start_time.elapsed().as_secs_f64();
+
+        image.save(&result.image)?;
+        Ok(())
+    };
+    if let Err(error) = render(&mut result) {
+        result.error = Some(error.to_string());
+    }
+    result
+}
+
+fn report<'a>(
+    scene_dir: &str,
+    results: impl Iterator<Item = &'a BatchResult>,
+    total_seconds: f64,
+) -> String {
+    let mut report = format!("# Batch render of {}\n\n", scene_dir);
+    report += "| scene | image | load [s] | render [s] | result |\n";
+    report += "|-------|-------|---------:|-----------:|--------|\n";
+    let mut failed = 0;
+    for result in results {
+        report += &format!(
+            "| {} | {} | {:.3} | {:.3} | {} |\n",
+            result.name,
+            result.image,
+            result.load_seconds,
+            result.render_seconds,
+            match &result.error {
+                Some(error) => {
+                    failed += 1;
+                    format!("failed: {}", error)
+                }
+                None => String::from("ok"),
+            }
+        );
+    }
+    report += &format!("\ntotal {:.3} seconds, {} failed\n", total_seconds, failed);
+    report
+}
@@ -0,0 +1,116 @@
+use ray_tracing_core::math::Ray;
+use ray_tracing_core::random;
+use ray_tracing_core::types::FSize;
+use ray_tracing_utility::serialization::core::{DeserializeOptions, Scene};
+use ray_tracing_utility::serialization::geometry::collection::BVHBuilder;
+use ray_tracing_utility::serialization::RayTracingObject;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Compares the ray throughput of the pointer based `BVHNode` hierarchy
+/// and the flattened `LinearBVH` on the scenes in the `scene` directory.
+/// The sample throughput of the path tracer is measured with 1 sample per pixel.
+///
+/// ```lang-none
+/// cargo run --release --bin rt_bench
+/// cargo run --release --bin rt_bench ./scene/Spheres.json ./scene/Room.json
+/// ```
+fn main() -> Result<(), Box<dyn Error>> {
+    let default_scene_dir = "scene";
+    let (cx, cy) = (400, 200);
+
+    let mut args = env::args();
+    args.next();
+    let mut scene_files: Vec<PathBuf> = args.map(PathBuf::from).collect();
+    if scene_files.is_empty() {
+        for entry in fs::read_dir(default_scene_dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                scene_files.push(path);
+            }
+        }
+        scene_files.sort();
+    }
+
+    let variants = [
+        ("BVHNode (random median)", BVHBuilder::RandomMedian, false),
+        ("BVHNode (SAH)", BVHBuilder::SAH, false),
+        ("LinearBVH", BVHBuilder::SAH, true),
+    ];
+    for file_path in scene_files.iter() {
+        let scene: Scene = match serde_json::from_str(&fs::read_to_string(file_path)?) {
+            Ok(scene) => scene,
+            Err(_) => continue,
+        };
+        let json_dir = match file_path.parent() {
+            Some(path) => env::current_dir()?.join(path),
+            None => env::current_dir()?,
+        };
+        let options = DeserializeOptions::form_path(Path::new(&json_dir));
+
+        let mut rays: Option<Vec<Ray>> = None;
+        println!("{}", file_path.display());
+        for (name, builder, linear_bvh) in variants.iter() {
+            let mut scene = scene.clone();
+            for object in scene.objects.iter_mut() {
+                if let RayTracingObject::Collection(c) = object {
+                    c.bvh_tree = false;
+                    c.bvh_builder = *builder;
+                    c.linear_bvh = *linear_bvh;
+                }
+            }
+            let scene = scene.to_scene_with_options(&options)?;
+            let rays = rays.get_or_insert_with(|| {
+                let mut rays = Vec::with_capacity(cx * cy);
+                for y in 0..cy {
+                    for x in 0..cx {
+                        rays.push(scene.camera.get(
+                            (x as FSize + 0.5) / cx as FSize,
+                            (y as FSize + 0.5) / cy as FSize,
+                        ));
+                    }
+                }
+                rays
+            });
+
+            let start_time = Instant::now();
+            let mut hits = 0;
+            for ray in rays.iter() {
+                if scene.world.hit(ray, 0.001..FSize::MAX).is_some() {
+                    hits += 1;
+                }
+            }
+            let seconds = start_time.elapsed().as_secs_f64();
+            println!(
+                "  {:<24} {:>12.0} rays/s ({} hits)",
+                name,
+                rays.len() as f64 / seconds,
+                hits
+            );
+        }
+
+        // path tracer with the configuration and the collections of the scene file
+        let scene = scene.to_scene_with_options(&options)?;
+        let start_time = Instant::now();
+        random::with_seed(Some(1), || {
+            for y in 0..cy {
+                for x in 0..cx {
+                    scene.ray_trace_color(
+                        (x as FSize + 0.5) / cx as FSize,
+                        (y as FSize + 0.5) / cy as FSize,
+                    );
+                }
+            }
+        });
+        let seconds = start_time.elapsed().as_secs_f64();
+        println!(
+            "  {:<24} {:>12.0} samples/s",
+            "path tracer",
+            (cx * cy) as f64 / seconds
+        );
+    }
+    Ok(())
+}
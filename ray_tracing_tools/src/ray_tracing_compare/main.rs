@@ -0,0 +1,85 @@
+use ray_tracing_utility::compare::{ImageComparison, Metric};
+use ray_tracing_utility::image;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+const USAGE: &str = "usage: rt_compare <image> <reference image> [--diff <difference.png>] \
+[--metric <rmse|psnr|ssim|flip>] [--threshold <value>]";
+
+/// Compares a rendered image with a reference image, e.g. from `./rendering`.
+///
+/// Prints the RMSE, PSNR, SSIM and the FLIP-style perceptual error and writes a false color image
+/// of the perceptual error of the pixels, if `--diff` is specified.
+/// The process exits with the code 1, if the `--metric` (default `flip`) is worse than the `--threshold`
+/// (default: rmse 0.05, psnr 26, ssim 0.9, flip 0.1).
+///
+/// ```lang-none
+/// cargo run --release --bin rt_compare ./temp/Room.png ./rendering/Room_800x800_10000_samples.png --diff ./temp/Room_diff.png
+/// cargo run --release --bin rt_compare ./temp/Room.png ./rendering/Room_800x800_10000_samples.png --metric ssim --threshold 0.95
+/// ```
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut image_paths = Vec::default();
+    let mut diff_path = None;
+    let mut metric = Metric::Flip;
+    let mut threshold = None;
+
+    let mut args = env::args();
+    args.next();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or(format!("missing value of {}\n{}", arg, USAGE))
+        };
+        match arg.as_str() {
+            "--diff" => diff_path = Some(value()?),
+            "--metric" => {
+                let name = value()?;
+                metric = Metric::from_name(&name).ok_or(format!("unknown metric {}", name))?
+            }
+            "--threshold" => threshold = Some(value()?.parse()?),
+            _ if !arg.starts_with("--") && image_paths.len() < 2 => image_paths.push(arg),
+            _ => return Err(format!("unknown argument {}\n{}", arg, USAGE).into()),
+        }
+    }
+    if image_paths.len() != 2 {
+        return Err(USAGE.into());
+    }
+    let threshold = threshold.unwrap_or_else(|| metric.default_threshold());
+
+    let (cx, cy, pixel_data) = image::load_image(&image_paths[0])?;
+    let (reference_cx, reference_cy, reference_data) = image::load_image(&image_paths[1])?;
+    if (cx, cy) != (reference_cx, reference_cy) {
+        return Err(format!(
+            "the size of the image {}x{} does not match the size of the reference image {}x{}",
+            cx, cy, reference_cx, reference_cy
+        )
+        .into());
+    }
+    let comparison = ImageComparison::new(cx, cy, &pixel_data, &reference_data)?;
+    for m in Metric::ALL {
+        println!("{}: {:.6}", m.name(), comparison.value(m));
+    }
+
+    if let Some(diff_path) = diff_path {
+        if let Some(parent) = Path::new(&diff_path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        image::save_image(&diff_path, cx, cy, &comparison.difference_image());
+        println!("saved {}", diff_path);
+    }
+
+    let value = comparison.value(metric);
+    if metric.exceeds(value, threshold) {
+        println!(
+            "regression: {} {:.6} exceeds the threshold {}",
+            metric.name(),
+            value,
+            threshold
+        );
+        process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use ray_tracing_utility::regression::{
+    ConfidenceSettings, RegressionReference, REFERENCE_SAMPLES, REFERENCE_SEED, REGRESSION_SCENES,
+    REGRESSION_SIZE, TEST_SAMPLES, TEST_SEED,
+};
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+const USAGE: &str = "usage: rt_regression [--update] [--scenes <scene directory>] \
+[--references <reference directory>] [--samples <samples per pixel>]";
+
+/// Renders small versions of the regression scenes with a fixed seed and compares the per pixel means
+/// with the stored references. The process exits with the code 1, if a scene differs from its reference.
+///
+/// `--update` renders new references with many samples per pixel.
+///
+/// ```lang-none
+/// cargo run --release --bin rt_regression
+/// cargo run --release --bin rt_regression --update
+/// ```
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut update = false;
+    let mut scene_dir = String::from("./scene");
+    let mut reference_dir = String::from("./rendering/reference");
+    let mut samples = None;
+
+    let mut args = env::args();
+    args.next();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or(format!("missing value of {}\n{}", arg, USAGE))
+        };
+        match arg.as_str() {
+            "--update" => update = true,
+            "--scenes" => scene_dir = value()?,
+            "--references" => reference_dir = value()?,
+            "--samples" => samples = Some(value()?.parse::<usize>()?),
+            _ => return Err(format!("unknown argument {}\n{}", arg, USAGE).into()),
+        }
+    }
+    let scene_dir = Path::new(&scene_dir);
+    let reference_dir = Path::new(&reference_dir);
+
+    if update {
+        fs::create_dir_all(reference_dir)?;
+        let (cx, cy) = REGRESSION_SIZE;
+        for name in REGRESSION_SCENES {
+            let reference = RegressionReference::render_scene(
+                scene_dir,
+                name,
+                cx,
+                cy,
+                samples.unwrap_or(REFERENCE_SAMPLES),
+                REFERENCE_SEED,
+            )?;
+            let path = RegressionReference::path(reference_dir, name);
+            reference.save(&path)?;
+            println!("saved {}", path.display());
+        }
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for name in REGRESSION_SCENES {
+        let reference = RegressionReference::load(&RegressionReference::path(reference_dir, name))?;
+        let result = RegressionReference::render_scene(
+            scene_dir,
+            name,
+            reference.cx,
+            reference.cy,
+            samples.unwrap_or(TEST_SAMPLES),
+            TEST_SEED,
+        )?;
+        let report = result.compare(&reference, &ConfidenceSettings::default())?;
+        println!(
+            "{}: {} ({} of {} pixels outside of the confidence interval, max z {:.2} at {:?}, image z {:.2})",
+            name,
+            if report.passed { "passed" } else { "FAILED" },
+            report.outliers,
+            report.pixels,
+            report.max_z,
+            report.max_z_pixel,
+            report.image_z
+        );
+        if !report.passed {
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        println!("{} of {} scenes failed", failed, REGRESSION_SCENES.len());
+        process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use ray_tracing_core::core::Scene;
+use ray_tracing_utility::aov::AovType;
+use ray_tracing_utility::denoise::DenoiseSettings;
+use ray_tracing_utility::render;
+use ray_tracing_utility::serialization::json;
+use ray_tracing_utility::view::{CheckpointSettings, DisplaySettings, SamplerType, ViewModel};
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+const USAGE: &str =
+    "usage: rt_render <scene.json> [--view <view_model.json>] [--output <image.png|.hdr|.pfm|.exr>] \
+[--size <cx>x<cy>] [--samples <samples per pass>] [--repetitions <passes>] [--threads <threads>] \
+[--time-limit <seconds>] [--aovs <albedo,normal,depth,position,object_id,material_id,direct,indirect>] [--denoise] \
+[--checkpoint <file>] [--checkpoint-interval <seconds>]";
+
+/// Renders a scene without a window and saves the image.
+/// The format of the image is chosen by the extension of the output file.
+/// `.hdr`, `.pfm` and `.exr` files store the linear colors, any other file is saved as PNG.
+/// The arbitrary output variables (`--aovs`) are stored as channels of an `.exr` file,
+/// respectively as separate images `<output>_<aov>.<extension>`.
+/// `--denoise` filters the image with the albedo, normal and depth buffers.
+/// `--checkpoint` writes the state of the render to a file at intervals (default 60 seconds)
+/// and at the end. If the file exists, the render is resumed from it.
+/// If `diagnostics` is set in the configuration of the scene, the sources of the NaN and infinite samples are printed.
+///
+/// ```lang-none
+/// cargo run --release --bin rt_render ./scene/TestSceneSimple.json --view ./scene/TestConfiguration.json --output ./temp/simple.png
+/// cargo run --release --bin rt_render ./scene/Room.json --output ./temp/room.exr
+/// cargo run --release --bin rt_render ./scene/Room.json --size 200x200 --samples 10 --repetitions 20 --time-limit 60
+/// cargo run --release --bin rt_render ./scene/Room.json --output ./temp/room.exr --aovs albedo,normal,depth
+/// cargo run --release --bin rt_render ./scene/Room.json --samples 4 --repetitions 4 --denoise
+/// cargo run --release --bin rt_render ./scene/RoomGlassSphere.json --checkpoint ./temp/room.checkpoint --time-limit 3600
+/// ```
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut view_model = ViewModel {
+        cx: 400,
+        cy: 200,
+        repetitions_threads: 2,
+        repetitions: 100,
+        samples: 10,
+        seed: None,
+        sampler: SamplerType::Independent,
+        adaptive_sampling: None,
+        display: DisplaySettings::default(),
+        aovs: Vec::default(),
+        denoise: None,
+    };
+    let mut scene_path = None;
+    let mut output_path = None;
+    let mut size = None;
+    let mut samples = None;
+    let mut repetitions = None;
+    let mut threads = None;
+    let mut time_limit = None;
+    let mut aovs = None;
+    let mut denoise = false;
+    let mut checkpoint = None;
+    let mut checkpoint_interval = Duration::from_secs(60);
+
+    let mut args = env::args();
+    args.next();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or(format!("missing value of {}\n{}", arg, USAGE))
+        };
+        match arg.as_str() {
+            "--view" => view_model = json::deserialize_view_model(&fs::read_to_string(value()?)?)?,
+            "--output" => output_path = Some(value()?),
+            "--size" => size = Some(parse_size(&value()?)?),
+            "--samples" => samples = Some(value()?.parse::<usize>()?),
+            "--repetitions" => repetitions = Some(value()?.parse::<usize>()?),
+            "--threads" => threads = Some(value()?.parse::<usize>()?),
+            "--time-limit" => time_limit = Some(Duration::from_secs_f64(value()?.parse()?)),
+            "--aovs" => aovs = Some(parse_aovs(&value()?)?),
+            "--denoise" => denoise = true,
+            "--checkpoint" => checkpoint = Some(PathBuf::from(value()?)),
+            "--checkpoint-interval" => {
+                checkpoint_interval = Duration::from_secs_f64(value()?.parse()?)
+            }
+            _ if !arg.starts_with("--") && scene_path.is_none() => scene_path = Some(arg),
+            _ => return Err(format!("unknown argument {}\n{}", arg, USAGE).into()),
+        }
+    }
+    let scene_path = scene_path.ok_or(USAGE)?;
+    if let Some((cx, cy)) = size {
+        view_model.cx = cx;
+        view_model.cy = cy;
+    }
+    if let Some(samples) = samples {
+        view_model.samples = samples;
+    }
+    if let Some(repetitions) = repetitions {
+        view_model.repetitions = repetitions;
+    }
+    if let Some(threads) = threads {
+        view_model.repetitions_threads = threads;
+    }
+    if let Some(aovs) = aovs {
+        view_model.aovs = aovs;
+    }
+    if denoise && view_model.denoise.is_none() {
+        view_model.denoise = Some(DenoiseSettings::default());
+    }
+    if view_model.repetitions_threads == 0 {
+        return Err("the number of threads has to be at least 1".into());
+    }
+
+    let output_path = match output_path {
+        Some(path) => path,
+        None => format!(
+            "./temp/{}.png",
+            render::image_file_name(
+                Path::new(&scene_path)
+                    .file_stem()
+                    .unwrap()
+                    .to_str()
+                    .unwrap(),
+                &view_model
+            )
+        ),
+    };
+    if let Some(parent) = Path::new(&output_path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let scene = Arc::new(render::load_scene(Path::new(&scene_path), view_model.seed)?);
+    println!("start");
+    let start_time = SystemTime::now();
+    let checkpoint = checkpoint.map(|path| CheckpointSettings {
+        path,
+        interval: checkpoint_interval,
+        resume: true,
+    });
+    let image = render::render_with_checkpoint(view_model, scene.clone(), time_limit, checkpoint)?;
+    println!(
+        "rendered in {} seconds",
+        start_time.elapsed()?.as_millis() as f64 / 1000.0
+    );
+    image.save(&output_path)?;
+    println!("saved {}", output_path);
+    if scene.configuration.diagnostics {
+        print_diagnostics(&scene);
+    }
+    Ok(())
+}
+
+/// Prints the sources of the NaN and infinite samples, see `Configuration::diagnostics`
+fn print_diagnostics(scene: &Scene) {
+    let report = scene.diagnostics.report();
+    if report.is_empty() {
+        println!("no NaN or infinite samples");
+    }
+    for (source, count) in report {
+        match source {
+            Some(source) => println!(
+                "material {}, geometry {}: {} NaN, {} infinite samples",
+                source.material_id, source.object_id, count.nan, count.infinite
+            ),
+            None => println!(
+                "unknown source: {} NaN, {} infinite samples",
+                count.nan, count.infinite
+            ),
+        }
+    }
+}
+
+fn parse_size(size: &str) -> Result<(usize, usize), Box<dyn Error>> {
+    match size.split_once('x') {
+        Some((cx, cy)) => Ok((cx.parse()?, cy.parse()?)),
+        None => Err(format!("invalid size {}, expected <cx>x<cy>", size).into()),
+    }
+}
+
+fn parse_aovs(aovs: &str) -> Result<Vec<AovType>, Box<dyn Error>> {
+    aovs.split(',')
+        .map(|name| {
+            AovType::from_name(name.trim())
+                .ok_or_else(|| format!("unknown arbitrary output variable {}", name).into())
+        })
+        .collect()
+}
@@ -0,0 +1,297 @@
+use crate::image;
+use exr::prelude::{
+    AnyChannel, AnyChannels, Encoding, FlatSamples, Image, Layer, LayerAttributes, WritableImage,
+};
+use ray_tracing_core::core::AovRecord;
+use ray_tracing_core::types::{ColorRGB, FSize};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// Arbitrary output variable, which is rendered in addition to the color of the pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum AovType {
+    /// Color of the surface at the first hit
+    Albedo,
+
+    /// Shading normal at the first hit
+    Normal,
+
+    /// Ray parameter `t` of the first hit
+    Depth,
+
+    /// World position of the first hit
+    Position,
+
+    /// Id of the geometry at the first hit
+    ObjectId,
+
+    /// Id of the material at the first hit
+    MaterialId,
+
+    /// Light, which is emitted by the first hit or reaches the camera after 1 bounce
+    Direct,
+
+    /// Light, which reaches the camera after 2 or more bounces
+    Indirect,
+}
+
+impl AovType {
+    pub const ALL: [AovType; 8] = [
+        AovType::Albedo,
+        AovType::Normal,
+        AovType::Depth,
+        AovType::Position,
+        AovType::ObjectId,
+        AovType::MaterialId,
+        AovType::Direct,
+        AovType::Indirect,
+    ];
+
+    /// Name of the buffer, which is used in file names and as layer name
+    pub fn name(&self) -> &'static str {
+        match self {
+            AovType::Albedo => "albedo",
+            AovType::Normal => "normal",
+            AovType::Depth => "depth",
+            AovType::Position => "position",
+            AovType::ObjectId => "object_id",
+            AovType::MaterialId => "material_id",
+            AovType::Direct => "direct",
+            AovType::Indirect => "indirect",
+        }
+    }
+
+    /// Finds the type by its name
+    pub fn from_name(name: &str) -> Option<AovType> {
+        AovType::ALL.iter().find(|t| t.name() == name).copied()
+    }
+
+    /// Number of channels of the buffer: 3 for colors and vectors, 1 for the depth and the ids
+    pub fn channels(&self) -> usize {
+        match self {
+            AovType::Depth | AovType::ObjectId | AovType::MaterialId => 1,
+            _ => 3,
+        }
+    }
+
+    fn values(&self, aov_record: &AovRecord) -> [FSize; 3] {
+        let vector = |v: ColorRGB| [v.x, v.y, v.z];
+        match self {
+            AovType::Albedo => vector(aov_record.albedo),
+            AovType::Normal => vector(aov_record.normal),
+            AovType::Depth => [aov_record.depth, 0.0, 0.0],
+            AovType::Position => vector(aov_record.position),
+            AovType::ObjectId => [aov_record.object_id as FSize, 0.0, 0.0],
+            AovType::MaterialId => [aov_record.material_id as FSize, 0.0, 0.0],
+            AovType::Direct => vector(aov_record.direct),
+            AovType::Indirect => vector(aov_record.indirect),
+        }
+    }
+}
+
+/// Float buffer of an arbitrary output variable, the channels of a pixel are interleaved
+#[derive(Debug, Clone, PartialEq)]
+pub struct AovBuffer {
+    pub aov_type: AovType,
+    pub data: Vec<f32>,
+}
+
+impl AovBuffer {
+    /// Converts the buffer to colors, single channels are replicated to gray
+    pub fn to_colors(&self) -> Vec<ColorRGB> {
+        match self.aov_type.channels() {
+            1 => self
+                .data
+                .iter()
+                .map(|v| ColorRGB::new(*v as FSize, *v as FSize, *v as FSize))
+                .collect(),
+            _ => self
+                .data
+                .chunks_exact(3)
+                .map(|c| ColorRGB::new(c[0] as FSize, c[1] as FSize, c[2] as FSize))
+                .collect(),
+        }
+    }
+}
+
+/// Named float buffers of the arbitrary output variables of an image
+#[derive(Debug, Clone, PartialEq)]
+pub struct AovBuffers {
+    pub cx: usize,
+    pub cy: usize,
+    pub buffers: Vec<AovBuffer>,
+}
+
+impl AovBuffers {
+    /// Collects the buffers of `aov_types` from the records of the pixels
+    pub fn new(
+        cx: usize,
+        cy: usize,
+        aov_types: &[AovType],
+        aov_records: &[AovRecord],
+    ) -> AovBuffers {
+        let buffers = aov_types
+            .iter()
+            .map(|aov_type| AovBuffer {
+                aov_type: *aov_type,
+                data: aov_records
+                    .iter()
+                    .flat_map(|r| aov_type.values(r)[..aov_type.channels()].to_vec())
+                    .map(|v| v as f32)
+                    .collect(),
+            })
+            .collect();
+        AovBuffers { cx, cy, buffers }
+    }
+
+    pub fn get(&self, aov_type: AovType) -> Option<&AovBuffer> {
+        self.buffers.iter().find(|b| b.aov_type == aov_type)
+    }
+
+    /// Saves each buffer to a separate high dynamic range image `{base_name}_{aov name}.{extension}`
+    /// and returns the names of the files
+    pub fn save_images(
+        &self,
+        base_name: &str,
+        extension: &str,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut file_names = Vec::default();
+        for buffer in self.buffers.iter() {
+            let file_name = format!("{}_{}.{}", base_name, buffer.aov_type.name(), extension);
+            image::save_hdr_image(&file_name, self.cx, self.cy, &buffer.to_colors())?;
+            file_names.push(file_name);
+        }
+        Ok(file_names)
+    }
+
+    /// Saves the colors of the image and the buffers to one OpenEXR file with 32 bit float channels.
+    /// The colors are stored in the channels `R`, `G`, `B`, the buffers in the channels `{aov name}.R`, ...
+    /// respectively `{aov name}`, if the buffer has a single channel.
+    pub fn save_exr(&self, filename: &str, pixel_color: &[ColorRGB]) -> Result<(), Box<dyn Error>> {
+        if pixel_color.len() != self.cx * self.cy {
+            return Err("the number of pixels does not match the size of the image".into());
+        }
+        let channel =
+            |name: &str, values: Vec<f32>| AnyChannel::new(name, FlatSamples::F32(values));
+        let mut channels = Vec::default();
+        for (i, name) in ["R", "G", "B"].iter().enumerate() {
+            channels.push(channel(
+                name,
+                pixel_color.iter().map(|c| c[i] as f32).collect(),
+            ));
+        }
+        for buffer in self.buffers.iter() {
+            let name = buffer.aov_type.name();
+            match buffer.aov_type.channels() {
+                1 => channels.push(channel(name, buffer.data.clone())),
+                n => {
+                    for (i, component) in ["R", "G", "B"].iter().enumerate() {
+                        channels.push(channel(
+                            &format!("{}.{}", name, component),
+                            buffer.data.iter().skip(i).step_by(n).copied().collect(),
+                        ));
+                    }
+                }
+            }
+        }
+        let layer = Layer::new(
+            (self.cx, self.cy),
+            LayerAttributes::named("rendering"),
+            Encoding::FAST_LOSSLESS,
+            AnyChannels::sort(channels.into()),
+        );
+        Image::from_layer(layer).write().to_file(filename)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod aov_test {
+    use super::*;
+    use exr::prelude::read_first_flat_layer_from_file;
+    use ray_tracing_core::types::{Point3, Vector3};
+    use std::env;
+    use std::fs;
+
+    fn test_records() -> Vec<AovRecord> {
+        (0..6)
+            .map(|i| AovRecord {
+                albedo: ColorRGB::new(0.5, 0.25, i as FSize),
+                normal: Vector3::new(0.0, 1.0, 0.0),
+                depth: i as FSize * 2.0,
+                position: Point3::new(i as FSize, 0.0, -1.0),
+                object_id: 10 + i,
+                material_id: 20,
+                direct: ColorRGB::new(1.0, 1.0, 1.0),
+                indirect: ColorRGB::new(0.5, 0.5, 0.5),
+                emitted: ColorRGB::new(0.0, 0.0, 0.0),
+                non_finite_source: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn aov_type_test() {
+        for aov_type in AovType::ALL {
+            assert_eq!(AovType::from_name(aov_type.name()), Some(aov_type));
+        }
+        assert_eq!(AovType::from_name("color"), None);
+        let aov_types: Vec<AovType> =
+            serde_json::from_str(r#"["Albedo", "Depth", "ObjectId"]"#).unwrap();
+        assert_eq!(
+            aov_types,
+            vec![AovType::Albedo, AovType::Depth, AovType::ObjectId]
+        );
+    }
+
+    #[test]
+    fn aov_buffers_test() {
+        let buffers = AovBuffers::new(
+            3,
+            2,
+            &[AovType::Albedo, AovType::Depth, AovType::ObjectId],
+            &test_records(),
+        );
+        assert_eq!(buffers.buffers.len(), 3);
+        let albedo = buffers.get(AovType::Albedo).unwrap();
+        assert_eq!(albedo.data.len(), 3 * 2 * 3);
+        assert_eq!(&albedo.data[3..6], &[0.5, 0.25, 1.0]);
+        let depth = buffers.get(AovType::Depth).unwrap();
+        assert_eq!(depth.data, vec![0.0, 2.0, 4.0, 6.0, 8.0, 10.0]);
+        assert_eq!(depth.to_colors()[1], ColorRGB::new(2.0, 2.0, 2.0));
+        assert_eq!(buffers.get(AovType::ObjectId).unwrap().data[5], 15.0);
+        assert!(buffers.get(AovType::Normal).is_none());
+    }
+
+    #[test]
+    fn save_exr_test() {
+        let buffers = AovBuffers::new(3, 2, &[AovType::Normal, AovType::Depth], &test_records());
+        let pixel_color: Vec<ColorRGB> = (0..6)
+            .map(|i| ColorRGB::new(i as FSize, 0.5, 0.25))
+            .collect();
+        let filename = env::temp_dir()
+            .join(format!("rt_aov_test_{}.exr", std::process::id()))
+            .display()
+            .to_string();
+        buffers.save_exr(&filename, &pixel_color).unwrap();
+        let image = read_first_flat_layer_from_file(&filename);
+        fs::remove_file(&filename).unwrap();
+
+        let image = image.unwrap();
+        let channels = &image.layer_data.channel_data.list;
+        let names: Vec<String> = channels.iter().map(|c| c.name.to_string()).collect();
+        for name in ["R", "G", "B", "normal.R", "normal.G", "normal.B", "depth"] {
+            assert!(names.contains(&name.to_string()));
+        }
+        let values = |name: &str| -> Vec<f32> {
+            let channel = channels
+                .iter()
+                .find(|c| c.name.to_string() == name)
+                .unwrap();
+            channel.sample_data.values_as_f32().collect()
+        };
+        assert_eq!(values("R"), vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(values("normal.G"), vec![1.0; 6]);
+        assert_eq!(values("depth"), vec![0.0, 2.0, 4.0, 6.0, 8.0, 10.0]);
+    }
+}
@@ -0,0 +1,385 @@
+use ray_tracing_core::display;
+use ray_tracing_core::types::FSize;
+use std::error::Error;
+
+/// Metric of the difference between an image and a reference image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    /// Root mean square error of the 8 bit channels, normalized to [0, 1]. Lower is better.
+    Rmse,
+
+    /// Peak signal to noise ratio in decibel. Higher is better.
+    Psnr,
+
+    /// Mean structural similarity index of the luma. 1 for equal images, higher is better.
+    Ssim,
+
+    /// Mean perceptual error in [0, 1], in the style of NVIDIA's FLIP. Lower is better.
+    Flip,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 4] = [Metric::Rmse, Metric::Psnr, Metric::Ssim, Metric::Flip];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::Rmse => "rmse",
+            Metric::Psnr => "psnr",
+            Metric::Ssim => "ssim",
+            Metric::Flip => "flip",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Metric> {
+        Metric::ALL.iter().find(|m| m.name() == name).copied()
+    }
+
+    /// Threshold, which is used if no threshold is specified
+    pub fn default_threshold(&self) -> FSize {
+        match self {
+            Metric::Rmse => 0.05,
+            Metric::Psnr => 26.0,
+            Metric::Ssim => 0.9,
+            Metric::Flip => 0.1,
+        }
+    }
+
+    /// Checks whether the value is worse than the threshold.
+    /// The errors must not be above the threshold, the similarities must not be below the threshold.
+    pub fn exceeds(&self, value: FSize, threshold: FSize) -> bool {
+        match self {
+            Metric::Rmse | Metric::Flip => value > threshold,
+            Metric::Psnr | Metric::Ssim => value < threshold,
+        }
+    }
+}
+
+/// Error metrics of an image compared to a reference image
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageComparison {
+    pub cx: usize,
+    pub cy: usize,
+    pub rmse: FSize,
+    pub psnr: FSize,
+    pub ssim: FSize,
+    pub flip: FSize,
+
+    /// Perceptual error of each pixel in [0, 1]
+    pub error_map: Vec<FSize>,
+}
+
+impl ImageComparison {
+    /// Standard deviation of the gaussian windows of the structural similarity
+    const SSIM_SIGMA: FSize = 1.5;
+
+    /// Standard deviation of the gaussian filter, which approximates the contrast sensitivity of the eye
+    const FLIP_SIGMA: FSize = 1.0;
+
+    /// Exponent of the compression of the color differences
+    const FLIP_COLOR_EXPONENT: FSize = 0.7;
+
+    /// Compares 2 images with 8 bit RGBA pixels, as they are returned by [`crate::image::load_image`].
+    /// The alpha channels are ignored.
+    pub fn new(
+        cx: usize,
+        cy: usize,
+        pixel_data: &[u8],
+        reference_data: &[u8],
+    ) -> Result<ImageComparison, Box<dyn Error>> {
+        if pixel_data.len() != cx * cy * 4 || reference_data.len() != cx * cy * 4 {
+            return Err("the number of pixels does not match the size of the image".into());
+        }
+        let image = ImageComparison::to_rgb(pixel_data);
+        let reference = ImageComparison::to_rgb(reference_data);
+
+        let squared_error = image
+            .iter()
+            .zip(reference.iter())
+            .map(|(a, b)| (0..3).map(|i| (a[i] - b[i]) * (a[i] - b[i])).sum::<FSize>())
+            .sum::<FSize>()
+            / FSize::max(1.0, (cx * cy * 3) as FSize);
+        let rmse = squared_error.sqrt();
+        let psnr = if rmse > 0.0 {
+            -20.0 * rmse.log10()
+        } else {
+            FSize::INFINITY
+        };
+        let ssim = ImageComparison::ssim(cx, cy, &image, &reference);
+        let error_map = ImageComparison::flip_error_map(cx, cy, &image, &reference);
+        let flip = error_map.iter().sum::<FSize>() / FSize::max(1.0, error_map.len() as FSize);
+        Ok(ImageComparison {
+            cx,
+            cy,
+            rmse,
+            psnr,
+            ssim,
+            flip,
+            error_map,
+        })
+    }
+
+    pub fn value(&self, metric: Metric) -> FSize {
+        match metric {
+            Metric::Rmse => self.rmse,
+            Metric::Psnr => self.psnr,
+            Metric::Ssim => self.ssim,
+            Metric::Flip => self.flip,
+        }
+    }
+
+    /// False color image of the error map with 8 bit RGBA pixels,
+    /// black for equal pixels, over purple and orange to light yellow for the maximum error
+    pub fn difference_image(&self) -> Vec<u8> {
+        const COLOR_MAP: [[FSize; 3]; 5] = [
+            [0.0, 0.0, 0.0],
+            [0.35, 0.05, 0.5],
+            [0.8, 0.2, 0.4],
+            [1.0, 0.6, 0.1],
+            [1.0, 1.0, 0.75],
+        ];
+        self.error_map
+            .iter()
+            .flat_map(|e| {
+                let x = e.clamp(0.0, 1.0) * (COLOR_MAP.len() - 1) as FSize;
+                let i = usize::min(x as usize, COLOR_MAP.len() - 2);
+                let w = x - i as FSize;
+                let (a, b) = (COLOR_MAP[i], COLOR_MAP[i + 1]);
+                let channel = |c: usize| ((a[c] * (1.0 - w) + b[c] * w) * 255.0).round() as u8;
+                [channel(0), channel(1), channel(2), 255]
+            })
+            .collect()
+    }
+
+    fn to_rgb(pixel_data: &[u8]) -> Vec<[FSize; 3]> {
+        pixel_data
+            .chunks_exact(4)
+            .map(|p| {
+                [
+                    p[0] as FSize / 255.0,
+                    p[1] as FSize / 255.0,
+                    p[2] as FSize / 255.0,
+                ]
+            })
+            .collect()
+    }
+
+    /// Mean structural similarity of the luma, with gaussian weighted windows
+    fn ssim(cx: usize, cy: usize, image: &[[FSize; 3]], reference: &[[FSize; 3]]) -> FSize {
+        const C1: FSize = 0.01 * 0.01;
+        const C2: FSize = 0.03 * 0.03;
+        let luma = |rgb: &[FSize; 3]| 0.299 * rgb[0] + 0.587 * rgb[1] + 0.114 * rgb[2];
+        let x: Vec<FSize> = image.iter().map(luma).collect();
+        let y: Vec<FSize> = reference.iter().map(luma).collect();
+        let product = |a: &[FSize], b: &[FSize]| -> Vec<FSize> {
+            a.iter().zip(b.iter()).map(|(a, b)| a * b).collect()
+        };
+        let blur = |values: &[FSize]| gaussian_blur(cx, cy, values, ImageComparison::SSIM_SIGMA);
+        let mean_x = blur(&x);
+        let mean_y = blur(&y);
+        let mean_xx = blur(&product(&x, &x));
+        let mean_yy = blur(&product(&y, &y));
+        let mean_xy = blur(&product(&x, &y));
+        let sum = (0..x.len())
+            .map(|i| {
+                let (mx, my) = (mean_x[i], mean_y[i]);
+                let variance_x = mean_xx[i] - mx * mx;
+                let variance_y = mean_yy[i] - my * my;
+                let covariance = mean_xy[i] - mx * my;
+                ((2.0 * mx * my + C1) * (2.0 * covariance + C2))
+                    / ((mx * mx + my * my + C1) * (variance_x + variance_y + C2))
+            })
+            .sum::<FSize>();
+        sum / FSize::max(1.0, x.len() as FSize)
+    }
+
+    /// Simplified FLIP error of each pixel.
+    ///
+    /// The images are filtered in CIELAB space and the color error is the compressed HyAB distance,
+    /// relative to the distance between green and blue. The error is amplified by the difference of the
+    /// gradients of the lightness, so that missing or additional edges are weighted more.
+    fn flip_error_map(
+        cx: usize,
+        cy: usize,
+        image: &[[FSize; 3]],
+        reference: &[[FSize; 3]],
+    ) -> Vec<FSize> {
+        let filtered_lab = |rgb: &[[FSize; 3]]| -> Vec<[FSize; 3]> {
+            let lab: Vec<[FSize; 3]> = rgb
+                .iter()
+                .map(|c| {
+                    lab_from_linear([
+                        display::srgb_to_linear(c[0]),
+                        display::srgb_to_linear(c[1]),
+                        display::srgb_to_linear(c[2]),
+                    ])
+                })
+                .collect();
+            let channels: Vec<Vec<FSize>> = (0..3)
+                .map(|i| {
+                    let channel: Vec<FSize> = lab.iter().map(|c| c[i]).collect();
+                    gaussian_blur(cx, cy, &channel, ImageComparison::FLIP_SIGMA)
+                })
+                .collect();
+            (0..lab.len())
+                .map(|i| [channels[0][i], channels[1][i], channels[2][i]])
+                .collect()
+        };
+        let image_lab = filtered_lab(image);
+        let reference_lab = filtered_lab(reference);
+        let max_distance = hyab_distance(
+            &lab_from_linear([0.0, 1.0, 0.0]),
+            &lab_from_linear([0.0, 0.0, 1.0]),
+        )
+        .powf(ImageComparison::FLIP_COLOR_EXPONENT);
+        let image_gradient = lightness_gradient(cx, cy, &image_lab);
+        let reference_gradient = lightness_gradient(cx, cy, &reference_lab);
+        (0..image_lab.len())
+            .map(|i| {
+                let color_error = FSize::min(
+                    1.0,
+                    hyab_distance(&image_lab[i], &reference_lab[i])
+                        .powf(ImageComparison::FLIP_COLOR_EXPONENT)
+                        / max_distance,
+                );
+                let feature_error = FSize::min(
+                    1.0,
+                    ((image_gradient[i] - reference_gradient[i]).abs() / FSize::sqrt(2.0)).sqrt(),
+                );
+                if color_error > 0.0 {
+                    color_error.powf(1.0 - feature_error)
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
+}
+
+/// Converts linear sRGB to CIELAB with the D65 white point
+fn lab_from_linear(rgb: [FSize; 3]) -> [FSize; 3] {
+    let x = (0.4124 * rgb[0] + 0.3576 * rgb[1] + 0.1805 * rgb[2]) / 0.95047;
+    let y = 0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2];
+    let z = (0.0193 * rgb[0] + 0.1192 * rgb[1] + 0.9505 * rgb[2]) / 1.08883;
+    let f = |t: FSize| {
+        if t > 216.0 / 24389.0 {
+            t.cbrt()
+        } else {
+            t * 841.0 / 108.0 + 4.0 / 29.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+/// Sum of the absolute difference of the lightness and the euclidean distance of the chromaticity
+fn hyab_distance(a: &[FSize; 3], b: &[FSize; 3]) -> FSize {
+    let (da, db) = (a[1] - b[1], a[2] - b[2]);
+    (a[0] - b[0]).abs() + (da * da + db * db).sqrt()
+}
+
+/// Magnitude of the central differences of the lightness, normalized to [0, 1]
+fn lightness_gradient(cx: usize, cy: usize, lab: &[[FSize; 3]]) -> Vec<FSize> {
+    let lightness = |x: usize, y: usize| lab[y * cx + x][0] / 100.0;
+    let mut gradient = Vec::with_capacity(cx * cy);
+    for y in 0..cy {
+        for x in 0..cx {
+            let dx = lightness(usize::min(x + 1, cx - 1), y) - lightness(x.saturating_sub(1), y);
+            let dy = lightness(x, usize::min(y + 1, cy - 1)) - lightness(x, y.saturating_sub(1));
+            gradient.push((dx * dx + dy * dy).sqrt() / 2.0);
+        }
+    }
+    gradient
+}
+
+/// Separable gaussian filter. The weights are normalized at the borders of the image.
+fn gaussian_blur(cx: usize, cy: usize, values: &[FSize], sigma: FSize) -> Vec<FSize> {
+    let radius = (3.0 * sigma).ceil() as isize;
+    let weights: Vec<FSize> = (-radius..=radius)
+        .map(|d| FSize::exp(-((d * d) as FSize) / (2.0 * sigma * sigma)))
+        .collect();
+    let filter = |source: &[FSize], horizontal: bool| -> Vec<FSize> {
+        let mut target = Vec::with_capacity(source.len());
+        for y in 0..cy as isize {
+            for x in 0..cx as isize {
+                let mut sum = 0.0;
+                let mut weight_sum = 0.0;
+                for d in -radius..=radius {
+                    let (nx, ny) = if horizontal { (x + d, y) } else { (x, y + d) };
+                    if nx < 0 || ny < 0 || nx >= cx as isize || ny >= cy as isize {
+                        continue;
+                    }
+                    let w = weights[(d + radius) as usize];
+                    sum += source[ny as usize * cx + nx as usize] * w;
+                    weight_sum += w;
+                }
+                target.push(sum / weight_sum);
+            }
+        }
+        target
+    };
+    filter(&filter(values, true), false)
+}
+
+#[cfg(test)]
+mod compare_test {
+    use super::*;
+
+    fn test_image(cx: usize, cy: usize, offset: u8) -> Vec<u8> {
+        (0..cx * cy)
+            .flat_map(|i| {
+                let v = ((i * 37) % 200) as u8 + offset;
+                [v, v / 2, 255 - v, 255]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn metric_test() {
+        for metric in Metric::ALL {
+            assert_eq!(Metric::from_name(metric.name()), Some(metric));
+        }
+        assert_eq!(Metric::from_name("mse"), None);
+        assert!(Metric::Flip.exceeds(0.2, 0.1));
+        assert!(!Metric::Rmse.exceeds(0.01, 0.05));
+        assert!(Metric::Ssim.exceeds(0.8, 0.9));
+        assert!(!Metric::Psnr.exceeds(30.0, 26.0));
+    }
+
+    #[test]
+    fn equal_images_test() {
+        let image = test_image(8, 6, 0);
+        let comparison = ImageComparison::new(8, 6, &image, &image).unwrap();
+        assert_eq!(comparison.rmse, 0.0);
+        assert_eq!(comparison.psnr, FSize::INFINITY);
+        assert!((comparison.ssim - 1.0).abs() < 1.0e-9);
+        assert_eq!(comparison.flip, 0.0);
+        for metric in Metric::ALL {
+            assert!(!metric.exceeds(comparison.value(metric), metric.default_threshold()));
+        }
+    }
+
+    #[test]
+    fn different_images_test() {
+        let (cx, cy) = (8, 6);
+        let reference = test_image(cx, cy, 0);
+        let image = test_image(cx, cy, 51);
+        let comparison = ImageComparison::new(cx, cy, &image, &reference).unwrap();
+        // red and blue differ by 0.2, green by 0.1
+        let rmse = FSize::sqrt((0.2 * 0.2 * 2.0 + 0.1 * 0.1) / 3.0);
+        assert!((comparison.rmse - rmse).abs() < 0.01);
+        assert!((comparison.psnr + 20.0 * rmse.log10()).abs() < 0.2);
+        assert!(comparison.ssim < 0.99);
+        assert!(comparison.flip > 0.05 && comparison.flip <= 1.0);
+        assert!(Metric::Flip.exceeds(comparison.flip, 0.01));
+
+        let difference = comparison.difference_image();
+        assert_eq!(difference.len(), cx * cy * 4);
+        assert!(difference.chunks_exact(4).any(|p| p[0] > 0));
+    }
+
+    #[test]
+    fn size_mismatch_test() {
+        let image = test_image(8, 6, 0);
+        assert!(ImageComparison::new(8, 5, &image, &image).is_err());
+    }
+}
@@ -0,0 +1,295 @@
+use crate::aov::{AovBuffers, AovType};
+use ray_tracing_core::types;
+use ray_tracing_core::types::{ColorRGB, FSize};
+use serde::{Deserialize, Serialize};
+
+/// Feature buffers, which are required by the [`Denoiser`]
+pub const FEATURE_AOVS: [AovType; 3] = [AovType::Albedo, AovType::Normal, AovType::Depth];
+
+/// Parameters of the joint bilateral filter of the [`Denoiser`]
+///
+/// The weight of a neighbor pixel is the product of gaussian weights of its distance and of the
+/// differences of its color, albedo, normal and depth. Larger sigmas blur more, smaller sigmas preserve more edges.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct DenoiseSettings {
+    /// Radius of the filter in pixels
+    #[serde(default = "DenoiseSettings::default_radius")]
+    pub radius: usize,
+
+    /// Standard deviation of the distance in pixels
+    #[serde(default = "DenoiseSettings::default_sigma_spatial")]
+    pub sigma_spatial: FSize,
+
+    /// Standard deviation of the difference of the tone mapped colors
+    #[serde(default = "DenoiseSettings::default_sigma_color")]
+    pub sigma_color: FSize,
+
+    /// Standard deviation of the difference of the albedos
+    #[serde(default = "DenoiseSettings::default_sigma_albedo")]
+    pub sigma_albedo: FSize,
+
+    /// Standard deviation of `1 - cos(angle)` between the normals
+    #[serde(default = "DenoiseSettings::default_sigma_normal")]
+    pub sigma_normal: FSize,
+
+    /// Standard deviation of the difference of the depths, relative to the depth of the filtered pixel
+    #[serde(default = "DenoiseSettings::default_sigma_depth")]
+    pub sigma_depth: FSize,
+
+    /// If set, the live preview of the viewer shows the denoised image
+    #[serde(default)]
+    pub preview: bool,
+}
+
+impl DenoiseSettings {
+    fn default_radius() -> usize {
+        5
+    }
+
+    fn default_sigma_spatial() -> FSize {
+        3.0
+    }
+
+    fn default_sigma_color() -> FSize {
+        0.5
+    }
+
+    fn default_sigma_albedo() -> FSize {
+        0.1
+    }
+
+    fn default_sigma_normal() -> FSize {
+        0.1
+    }
+
+    fn default_sigma_depth() -> FSize {
+        0.05
+    }
+}
+
+impl Default for DenoiseSettings {
+    fn default() -> DenoiseSettings {
+        DenoiseSettings {
+            radius: DenoiseSettings::default_radius(),
+            sigma_spatial: DenoiseSettings::default_sigma_spatial(),
+            sigma_color: DenoiseSettings::default_sigma_color(),
+            sigma_albedo: DenoiseSettings::default_sigma_albedo(),
+            sigma_normal: DenoiseSettings::default_sigma_normal(),
+            sigma_depth: DenoiseSettings::default_sigma_depth(),
+            preview: false,
+        }
+    }
+}
+
+/// Edge preserving filter of the noisy radiance, guided by the albedo, normal and depth feature buffers
+///
+/// The radiance is divided by the albedo before filtering and multiplied by the albedo afterwards,
+/// so that the details of the textures are not blurred. Missing feature buffers are ignored.
+pub struct Denoiser {
+    settings: DenoiseSettings,
+}
+
+impl Denoiser {
+    pub fn new(settings: DenoiseSettings) -> Denoiser {
+        Denoiser { settings }
+    }
+
+    /// Filters the linear colors of an image with the feature buffers of the image
+    pub fn denoise(&self, pixel_color: &[ColorRGB], features: &AovBuffers) -> Vec<ColorRGB> {
+        let (cx, cy) = (features.cx, features.cy);
+        let colors = |aov_type| features.get(aov_type).map(|b| b.to_colors());
+        let albedo = colors(AovType::Albedo);
+        let normal = colors(AovType::Normal);
+        let depth = features.get(AovType::Depth).map(|b| &b.data);
+
+        // irradiance, the albedo is restored after filtering
+        let demodulate = |i: usize, c: &ColorRGB| match &albedo {
+            Some(albedo) => {
+                let a = albedo[i];
+                ColorRGB::new(
+                    Denoiser::divide(c.x, a.x),
+                    Denoiser::divide(c.y, a.y),
+                    Denoiser::divide(c.z, a.z),
+                )
+            }
+            None => *c,
+        };
+        let irradiance: Vec<ColorRGB> = pixel_color
+            .iter()
+            .enumerate()
+            .map(|(i, c)| demodulate(i, c))
+            .collect();
+        let tone_mapped: Vec<ColorRGB> = irradiance
+            .iter()
+            .map(|c| {
+                ColorRGB::new(
+                    Denoiser::tone_map(c.x),
+                    Denoiser::tone_map(c.y),
+                    Denoiser::tone_map(c.z),
+                )
+            })
+            .collect();
+
+        let settings = &self.settings;
+        let radius = settings.radius as isize;
+        let gauss = |d2: FSize, sigma: FSize| FSize::exp(-d2 / (2.0 * sigma * sigma));
+        let mut result = Vec::with_capacity(cx * cy);
+        for y in 0..cy {
+            for x in 0..cx {
+                let i = y * cx + x;
+                let mut sum = ColorRGB::new(0.0, 0.0, 0.0);
+                let mut weight_sum = 0.0;
+                for dy in -radius..=radius {
+                    let ny = y as isize + dy;
+                    if ny < 0 || ny >= cy as isize {
+                        continue;
+                    }
+                    for dx in -radius..=radius {
+                        let nx = x as isize + dx;
+                        if nx < 0 || nx >= cx as isize {
+                            continue;
+                        }
+                        let j = ny as usize * cx + nx as usize;
+                        let mut w = gauss((dx * dx + dy * dy) as FSize, settings.sigma_spatial)
+                            * gauss(
+                                types::distance_square(tone_mapped[i], tone_mapped[j]),
+                                settings.sigma_color,
+                            );
+                        if let Some(albedo) = &albedo {
+                            w *= gauss(
+                                types::distance_square(albedo[i], albedo[j]),
+                                settings.sigma_albedo,
+                            );
+                        }
+                        if let Some(normal) = &normal {
+                            let (a, b) = (normal[i], normal[j]);
+                            let d = 1.0 - (a.x * b.x + a.y * b.y + a.z * b.z);
+                            w *= gauss(d * d, settings.sigma_normal);
+                        }
+                        if let Some(depth) = depth {
+                            let d = (depth[i] - depth[j]) as FSize
+                                / FSize::max(depth[i].abs() as FSize, 0.001);
+                            w *= gauss(d * d, settings.sigma_depth);
+                        }
+                        if w.is_finite() && irradiance[j].x.is_finite() {
+                            sum = sum + irradiance[j] * w;
+                            weight_sum += w;
+                        }
+                    }
+                }
+                let filtered = if weight_sum > 0.0 {
+                    sum / weight_sum
+                } else {
+                    irradiance[i]
+                };
+                result.push(match &albedo {
+                    Some(albedo) => Denoiser::remodulate(&filtered, &albedo[i]),
+                    None => filtered,
+                });
+            }
+        }
+        result
+    }
+
+    const MIN_ALBEDO: FSize = 0.01;
+
+    fn divide(c: FSize, a: FSize) -> FSize {
+        c / FSize::max(a, Denoiser::MIN_ALBEDO)
+    }
+
+    fn remodulate(c: &ColorRGB, a: &ColorRGB) -> ColorRGB {
+        ColorRGB::new(
+            c.x * FSize::max(a.x, Denoiser::MIN_ALBEDO),
+            c.y * FSize::max(a.y, Denoiser::MIN_ALBEDO),
+            c.z * FSize::max(a.z, Denoiser::MIN_ALBEDO),
+        )
+    }
+
+    fn tone_map(x: FSize) -> FSize {
+        FSize::max(0.0, x) / (1.0 + FSize::max(0.0, x))
+    }
+}
+
+#[cfg(test)]
+mod denoise_test {
+    use super::*;
+    use ray_tracing_core::core::AovRecord;
+    use ray_tracing_core::random;
+    use ray_tracing_core::types::Vector3;
+
+    /// Image with a vertical edge between a dark and a bright half, with noise
+    fn test_image(cx: usize, cy: usize) -> (Vec<ColorRGB>, AovBuffers) {
+        random::set_seed(Some(1));
+        let mut pixel_color = Vec::default();
+        let mut records = Vec::default();
+        for _ in 0..cy {
+            for x in 0..cx {
+                let left = x < cx / 2;
+                let value = if left { 0.2 } else { 0.8 };
+                let noise = random::generate_size() * 0.4 - 0.2;
+                pixel_color.push(ColorRGB::new(value + noise, value + noise, value + noise));
+                let mut record = AovRecord::empty();
+                record.albedo = ColorRGB::new(1.0, 1.0, 1.0);
+                record.normal = if left {
+                    Vector3::new(1.0, 0.0, 0.0)
+                } else {
+                    Vector3::new(0.0, 0.0, 1.0)
+                };
+                record.depth = 2.0;
+                records.push(record);
+            }
+        }
+        random::set_seed(None);
+        (
+            pixel_color,
+            AovBuffers::new(cx, cy, &FEATURE_AOVS, &records),
+        )
+    }
+
+    fn mean_error(pixel_color: &[ColorRGB], cx: usize) -> FSize {
+        pixel_color
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let expected = if i % cx < cx / 2 { 0.2 } else { 0.8 };
+                FSize::abs(c.x - expected)
+            })
+            .sum::<FSize>()
+            / pixel_color.len() as FSize
+    }
+
+    #[test]
+    fn denoise_test() {
+        let (cx, cy) = (16, 8);
+        let (pixel_color, features) = test_image(cx, cy);
+        let denoised = Denoiser::new(DenoiseSettings::default()).denoise(&pixel_color, &features);
+        assert_eq!(denoised.len(), cx * cy);
+        // the noise is reduced and the edge is preserved by the normals
+        assert!(mean_error(&denoised, cx) < mean_error(&pixel_color, cx) * 0.5);
+        for y in 0..cy {
+            assert!(denoised[y * cx + cx / 2 - 1].x < 0.45);
+            assert!(denoised[y * cx + cx / 2].x > 0.55);
+        }
+    }
+
+    #[test]
+    fn denoise_without_features_test() {
+        let (cx, cy) = (16, 8);
+        let (pixel_color, _) = test_image(cx, cy);
+        let features = AovBuffers::new(cx, cy, &[], &[]);
+        let denoised = Denoiser::new(DenoiseSettings::default()).denoise(&pixel_color, &features);
+        assert!(mean_error(&denoised, cx) < mean_error(&pixel_color, cx));
+    }
+
+    #[test]
+    fn deserialize_test() {
+        let settings: DenoiseSettings = serde_json::from_str(r#"{ "radius": 2 }"#).unwrap();
+        assert_eq!(
+            settings,
+            DenoiseSettings {
+                radius: 2,
+                ..DenoiseSettings::default()
+            }
+        );
+    }
+}
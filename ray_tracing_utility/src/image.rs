@@ -1,6 +1,12 @@
+use image::codecs::hdr::{HdrDecoder, HdrEncoder};
 use image::io::Reader;
-use image::{ColorType, DynamicImage, ImageFormat};
+use image::{ColorType, DynamicImage, ImageFormat, Rgb};
+use ray_tracing_core::types::{ColorRGB, FSize};
 use std::error::Error;
+use std::fs;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
 
 pub fn save_image(filename: &str, cx: usize, cy: usize, pixel_data: &Vec<u8>) {
     image::save_buffer_with_format(
@@ -22,3 +28,189 @@ pub fn load_image(filename: &str) -> Result<(usize, usize, Vec<u8>), Box<dyn Err
         DynamicImage::ImageRgba8(rgba_image).into_bytes(),
     ))
 }
+
+fn file_extension(filename: &str) -> String {
+    Path::new(filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// Check whether the file name has the extension of a high dynamic range format (`.hdr`, `.pfm` or `.exr`)
+pub fn is_hdr_file_name(filename: &str) -> bool {
+    matches!(file_extension(filename).as_str(), "hdr" | "pfm" | "exr")
+}
+
+/// Saves the linear colors of an image with floating point channels.
+/// The format is chosen by the extension of the file name:
+/// Radiance RGBE (`.hdr`), portable float map (`.pfm`) or OpenEXR with 32 bit float channels (`.exr`).
+pub fn save_hdr_image(
+    filename: &str,
+    cx: usize,
+    cy: usize,
+    pixel_color: &[ColorRGB],
+) -> Result<(), Box<dyn Error>> {
+    if pixel_color.len() != cx * cy {
+        return Err("the number of pixels does not match the size of the image".into());
+    }
+    match file_extension(filename).as_str() {
+        "hdr" => {
+            let data: Vec<Rgb<f32>> = pixel_color
+                .iter()
+                .map(|c| Rgb([c[0] as f32, c[1] as f32, c[2] as f32]))
+                .collect();
+            HdrEncoder::new(BufWriter::new(File::create(filename)?)).encode(&data, cx, cy)?;
+        }
+        "pfm" => {
+            let mut file = BufWriter::new(File::create(filename)?);
+            // negative scale: little endian, the rows are stored from the bottom to the top
+            write!(file, "PF\n{} {}\n-1.0\n", cx, cy)?;
+            for y in (0..cy).rev() {
+                for c in pixel_color[y * cx..(y + 1) * cx].iter() {
+                    for i in 0..3 {
+                        file.write_all(&(c[i] as f32).to_le_bytes())?;
+                    }
+                }
+            }
+            file.flush()?;
+        }
+        "exr" => {
+            let data: Vec<u8> = pixel_color
+                .iter()
+                .flat_map(|c| [c[0] as f32, c[1] as f32, c[2] as f32])
+                .flat_map(|v| v.to_ne_bytes())
+                .collect();
+            image::save_buffer_with_format(
+                filename,
+                &data,
+                cx as u32,
+                cy as u32,
+                ColorType::Rgb32F,
+                ImageFormat::OpenExr,
+            )?;
+        }
+        _ => return Err(format!("{} is not a high dynamic range image file", filename).into()),
+    }
+    Ok(())
+}
+
+/// Loads the linear colors of an image with floating point channels (`.hdr`, `.pfm` or `.exr`)
+pub fn load_hdr_image(filename: &str) -> Result<(usize, usize, Vec<ColorRGB>), Box<dyn Error>> {
+    match file_extension(filename).as_str() {
+        "pfm" => return load_pfm_image(filename),
+        "hdr" => {
+            // the generic decoder converts RGBE images to 8 bit
+            let decoder = HdrDecoder::new(BufReader::new(File::open(filename)?))?;
+            let metadata = decoder.metadata();
+            return Ok((
+                metadata.width as usize,
+                metadata.height as usize,
+                decoder
+                    .read_image_hdr()?
+                    .iter()
+                    .map(|p| ColorRGB::new(p[0] as FSize, p[1] as FSize, p[2] as FSize))
+                    .collect(),
+            ));
+        }
+        _ => (),
+    }
+    let rgb_image = Reader::open(filename)?.decode()?.to_rgb32f();
+    Ok((
+        rgb_image.width() as usize,
+        rgb_image.height() as usize,
+        rgb_image
+            .pixels()
+            .map(|p| ColorRGB::new(p[0] as FSize, p[1] as FSize, p[2] as FSize))
+            .collect(),
+    ))
+}
+
+fn load_pfm_image(filename: &str) -> Result<(usize, usize, Vec<ColorRGB>), Box<dyn Error>> {
+    let data = fs::read(filename)?;
+    // the header consists of 3 whitespace separated tokens: format, size and scale
+    let mut tokens = Vec::default();
+    let mut position = 0;
+    while tokens.len() < 4 && position < data.len() {
+        while position < data.len() && data[position].is_ascii_whitespace() {
+            position += 1;
+        }
+        let start = position;
+        while position < data.len() && !data[position].is_ascii_whitespace() {
+            position += 1;
+        }
+        tokens.push(std::str::from_utf8(&data[start..position])?.to_string());
+    }
+    if tokens.len() < 4 || tokens[0] != "PF" {
+        return Err(format!("{} is not a RGB portable float map", filename).into());
+    }
+    let cx: usize = tokens[1].parse()?;
+    let cy: usize = tokens[2].parse()?;
+    let scale: f32 = tokens[3].parse()?;
+    // a single whitespace character follows the scale
+    let pixels = &data[position + 1..];
+    if pixels.len() < cx * cy * 12 {
+        return Err(format!("{} is truncated", filename).into());
+    }
+    let mut pixel_color = vec![ColorRGB::new(0.0, 0.0, 0.0); cx * cy];
+    for (i, value) in pixels.chunks_exact(4).take(cx * cy * 3).enumerate() {
+        let bytes = [value[0], value[1], value[2], value[3]];
+        let value = if scale < 0.0 {
+            f32::from_le_bytes(bytes)
+        } else {
+            f32::from_be_bytes(bytes)
+        };
+        let (x, y) = ((i / 3) % cx, cy - 1 - (i / 3) / cx);
+        pixel_color[y * cx + x][i % 3] = value as FSize;
+    }
+    Ok((cx, cy, pixel_color))
+}
+
+#[cfg(test)]
+mod image_test {
+    use super::*;
+    use ray_tracing_core::test;
+    use std::env;
+
+    #[test]
+    fn is_hdr_file_name_test() {
+        assert!(is_hdr_file_name("image.hdr"));
+        assert!(is_hdr_file_name("./temp/image.PFM"));
+        assert!(is_hdr_file_name("image.exr"));
+        assert!(!is_hdr_file_name("image.png"));
+        assert!(!is_hdr_file_name("image"));
+    }
+
+    #[test]
+    fn save_and_load_hdr_image_test() {
+        let (cx, cy) = (3, 2);
+        let pixel_color: Vec<ColorRGB> = (0..cx * cy)
+            .map(|i| ColorRGB::new(i as FSize * 0.5, 10.0, 0.25 + i as FSize * 100.0))
+            .collect();
+        for extension in ["pfm", "exr", "hdr"] {
+            let filename = env::temp_dir()
+                .join(format!(
+                    "rt_image_test_{}.{}",
+                    std::process::id(),
+                    extension
+                ))
+                .display()
+                .to_string();
+            save_hdr_image(&filename, cx, cy, &pixel_color).unwrap();
+            let loaded = load_hdr_image(&filename);
+            fs::remove_file(&filename).unwrap();
+
+            let (loaded_cx, loaded_cy, loaded_color) = loaded.unwrap();
+            assert_eq!((loaded_cx, loaded_cy), (cx, cy));
+            // RGBE has a shared exponent and 8 bit mantissas
+            let tolerance = if extension == "hdr" { 0.01 } else { 0.00001 };
+            for (expected, actual) in pixel_color.iter().zip(loaded_color.iter()) {
+                let scale = FSize::max(1.0, expected[0].max(expected[1]).max(expected[2]));
+                for i in 0..3 {
+                    test::assert_eq_float(actual[i] / scale, expected[i] / scale, tolerance);
+                }
+            }
+        }
+        assert!(save_hdr_image("image.png", cx, cy, &pixel_color).is_err());
+    }
+}
@@ -10,6 +10,7 @@
 //! ## Process
 //!
 //! ```rust
+//! use ray_tracing_core::display::DisplayPipeline;
 //! use ray_tracing_core::random;
 //! use ray_tracing_core::test::TestSceneSimple;
 //! use ray_tracing_core::types::ColorRGB;
@@ -22,6 +23,7 @@
 //!     let samples = 10;
 //!     let scene = TestSceneSimple::new().scene;
 //!     let ray_trace_iter = IteratorExp2::new(cx, cy);
+//!     let display = DisplayPipeline::default();
 //!
 //!     let mut pixel_data: Vec<u8> = Vec::with_capacity(cx * cy * 4);
 //!     pixel_data.resize(cx * cy * 4, 0);
@@ -36,10 +38,7 @@
 //!         c = c / samples as FSize;
 //!
 //!         let i = (y * cx) + x;
-//!         pixel_data[i * 4] = (c[0].sqrt() * 255.0).round() as u8;
-//!         pixel_data[i * 4 + 1] = (c[1].sqrt() * 255.0).round() as u8;
-//!         pixel_data[i * 4 + 2] = (c[2].sqrt() * 255.0).round() as u8;
-//!         pixel_data[i * 4 + 3] = 255;
+//!         pixel_data[i * 4..i * 4 + 4].copy_from_slice(&display.to_rgba8(&c, x, y));
 //!     }
 //!
 //!     // [...]
@@ -105,8 +104,14 @@
 //! }
 //!```
 
+pub mod aov;
+pub mod compare;
+pub mod denoise;
 pub mod image;
 pub mod iterator;
+pub mod obj;
+pub mod regression;
+pub mod render;
 pub mod serialization;
 pub mod thread;
 pub mod view;
@@ -0,0 +1,488 @@
+//! Wavefront OBJ/MTL import
+//!
+//! Loads the polygons of an `.obj` file as triangle meshes.
+//! Faces are grouped by their `usemtl` material and each group becomes one
+//! [`TriangleMesh`](ray_tracing_core::geometry::shape::TriangleMesh).
+//! Polygons with more than 3 vertices are triangulated as a fan.
+//!
+//! The materials of the `mtllib` files are mapped onto the existing material types:
+//!
+//! - `Ke` not black: [`DiffuseLight`](ray_tracing_core::material::DiffuseLight) with the emission color
+//! - `illum` 4, 6, 7 or 9, or `d` less than 1: [`Dielectric`](ray_tracing_core::material::Dielectric)
+//!   with the refraction index `Ni` and the transmission filter `Tf`
+//! - `illum` 3, or black `Kd` and not black `Ks`: [`Metal`](ray_tracing_core::material::Metal)
+//!   with the albedo `Ks` and a fuzziness derived from `Ns`
+//! - otherwise: [`Lambertian`](ray_tracing_core::material::Lambertian) with the albedo `map_Kd`
+//!   ([`BitmapTexture`](ray_tracing_core::texture::BitmapTexture)) or `Kd`
+
+use crate::serialization::texture::BitmapFile;
+use ray_tracing_core::geometry::collection::GeometryList;
+use ray_tracing_core::geometry::shape::TriangleMesh;
+use ray_tracing_core::geometry::Geometry;
+use ray_tracing_core::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use ray_tracing_core::texture::{ConstantTexture, Texture};
+use ray_tracing_core::types::{ColorRGB, ColorRGBA, FSize, Point3, Vector2, Vector3};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::str::SplitWhitespace;
+use std::sync::Arc;
+
+/// Corner of a face: indices of the position, the texture coordinate and the normal vector
+type FaceVertex = (usize, Option<usize>, Option<usize>);
+
+/// Faces of a single material
+pub struct ObjGroup {
+    pub material: Option<String>,
+    pub faces: Vec<[FaceVertex; 3]>,
+}
+
+/// Content of an `.obj` file
+pub struct ObjData {
+    pub positions: Vec<Point3>,
+    pub uvs: Vec<Vector2>,
+    pub normals: Vec<Vector3>,
+    pub groups: Vec<ObjGroup>,
+    pub material_libraries: Vec<String>,
+}
+
+/// Material definition of an `.mtl` file
+#[derive(Debug, Clone, PartialEq)]
+pub struct MtlMaterial {
+    pub name: String,
+    pub kd: ColorRGB,
+    pub ks: ColorRGB,
+    pub ke: ColorRGB,
+    pub tf: ColorRGB,
+    pub ns: FSize,
+    pub ni: FSize,
+    pub d: FSize,
+    pub illum: usize,
+    pub map_kd: Option<String>,
+}
+
+impl MtlMaterial {
+    pub fn new(name: &str) -> MtlMaterial {
+        MtlMaterial {
+            name: String::from(name),
+            kd: ColorRGB::new(0.8, 0.8, 0.8),
+            ks: ColorRGB::new(0.0, 0.0, 0.0),
+            ke: ColorRGB::new(0.0, 0.0, 0.0),
+            tf: ColorRGB::new(1.0, 1.0, 1.0),
+            ns: 0.0,
+            ni: 1.0,
+            d: 1.0,
+            illum: 2,
+            map_kd: None,
+        }
+    }
+
+    /// Create the ray tracing material.
+    /// Texture files are resolved relative to `root_path`.
+    pub fn to_material(&self, root_path: &Path) -> Result<Arc<dyn Material>, Box<dyn Error>> {
+        let is_black = |c: &ColorRGB| c.x <= 0.0 && c.y <= 0.0 && c.z <= 0.0;
+        let constant_texture = |c: &ColorRGB| -> Arc<dyn Texture> {
+            Arc::new(ConstantTexture::new(ColorRGBA::new(c.x, c.y, c.z, 1.0)))
+        };
+        let material: Arc<dyn Material> = if !is_black(&self.ke) {
+            Arc::new(DiffuseLight::new(constant_texture(&self.ke)))
+        } else if matches!(self.illum, 4 | 6 | 7 | 9) || self.d < 1.0 {
+            Arc::new(Dielectric::new(
+                self.ni..self.ni,
+                constant_texture(&self.tf),
+            ))
+        } else if self.illum == 3 || (is_black(&self.kd) && !is_black(&self.ks)) {
+            let fuzz = FSize::clamp(1.0 - self.ns / 1000.0, 0.0, 1.0);
+            Arc::new(Metal::new(fuzz, constant_texture(&self.ks)))
+        } else {
+            let albedo: Arc<dyn Texture> = match &self.map_kd {
+                Some(filename) => {
+                    let filename = root_path.join(filename);
+                    Arc::new(BitmapFile::file_to_texture(&String::from(
+                        filename.to_str().ok_or("invalid texture path")?,
+                    ))?)
+                }
+                None => constant_texture(&self.kd),
+            };
+            Arc::new(Lambertian::new(albedo))
+        };
+        Ok(material)
+    }
+}
+
+fn parse_values(values: SplitWhitespace, line: usize) -> Result<Vec<FSize>, Box<dyn Error>> {
+    values
+        .map(|v| {
+            v.parse::<FSize>()
+                .map_err(|_| format!("line {}: invalid number '{}'", line, v).into())
+        })
+        .collect()
+}
+
+fn parse_color(values: SplitWhitespace, line: usize) -> Result<ColorRGB, Box<dyn Error>> {
+    let v = parse_values(values, line)?;
+    match v.len() {
+        1 => Ok(ColorRGB::new(v[0], v[0], v[0])),
+        3 => Ok(ColorRGB::new(v[0], v[1], v[2])),
+        _ => Err(format!("line {}: invalid color", line).into()),
+    }
+}
+
+fn parse_scalar(values: SplitWhitespace, line: usize) -> Result<FSize, Box<dyn Error>> {
+    match parse_values(values, line)?[..] {
+        [v] => Ok(v),
+        _ => Err(format!("line {}: invalid value", line).into()),
+    }
+}
+
+/// Convert a 1 based (or negative relative) OBJ index to a 0 based index
+fn parse_index(index: &str, count: usize, line: usize) -> Result<usize, Box<dyn Error>> {
+    let i = index
+        .parse::<i64>()
+        .map_err(|_| format!("line {}: invalid index '{}'", line, index))?;
+    let i = if i < 0 { count as i64 + i } else { i - 1 };
+    if i < 0 || i >= count as i64 {
+        return Err(format!("line {}: index '{}' out of range", line, index).into());
+    }
+    Ok(i as usize)
+}
+
+fn parse_face_vertex(
+    vertex: &str,
+    data: &ObjData,
+    line: usize,
+) -> Result<FaceVertex, Box<dyn Error>> {
+    let mut indices = vertex.split('/');
+    let position = parse_index(indices.next().unwrap_or(""), data.positions.len(), line)?;
+    let uv = match indices.next() {
+        Some(i) if !i.is_empty() => Some(parse_index(i, data.uvs.len(), line)?),
+        _ => None,
+    };
+    let normal = match indices.next() {
+        Some(i) if !i.is_empty() => Some(parse_index(i, data.normals.len(), line)?),
+        _ => None,
+    };
+    Ok((position, uv, normal))
+}
+
+/// Parse the content of an `.obj` file
+pub fn parse_obj(source: &str) -> Result<ObjData, Box<dyn Error>> {
+    let mut data = ObjData {
+        positions: Vec::default(),
+        uvs: Vec::default(),
+        normals: Vec::default(),
+        groups: Vec::default(),
+        material_libraries: Vec::default(),
+    };
+    let mut current_material: Option<String> = None;
+    for (line_index, line) in source.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        match keyword {
+            "v" => match parse_values(tokens, line_number)?[..] {
+                [x, y, z] | [x, y, z, _] => data.positions.push(Point3::new(x, y, z)),
+                _ => return Err(format!("line {}: invalid vertex", line_number).into()),
+            },
+            "vt" => match parse_values(tokens, line_number)?[..] {
+                [u] => data.uvs.push(Vector2::new(u, 0.0)),
+                [u, v] | [u, v, _] => data.uvs.push(Vector2::new(u, v)),
+                _ => return Err(format!("line {}: invalid texture coordinate", line_number).into()),
+            },
+            "vn" => match parse_values(tokens, line_number)?[..] {
+                [x, y, z] => data.normals.push(Vector3::new(x, y, z)),
+                _ => return Err(format!("line {}: invalid normal vector", line_number).into()),
+            },
+            "f" => {
+                let face = tokens
+                    .map(|v| parse_face_vertex(v, &data, line_number))
+                    .collect::<Result<Vec<FaceVertex>, Box<dyn Error>>>()?;
+                if face.len() < 3 {
+                    return Err(
+                        format!("line {}: face with less than 3 vertices", line_number).into(),
+                    );
+                }
+                if data.groups.last().map(|g| &g.material) != Some(&current_material) {
+                    data.groups.push(ObjGroup {
+                        material: current_material.clone(),
+                        faces: Vec::default(),
+                    });
+                }
+                let group = data.groups.last_mut().unwrap();
+                for i in 1..face.len() - 1 {
+                    group.faces.push([face[0], face[i], face[i + 1]]);
+                }
+            }
+            "usemtl" => current_material = Some(tokens.collect::<Vec<&str>>().join(" ")),
+            "mtllib" => data.material_libraries.extend(tokens.map(String::from)),
+            _ => (),
+        }
+    }
+    Ok(data)
+}
+
+/// Parse the content of an `.mtl` file
+pub fn parse_mtl(source: &str) -> Result<Vec<MtlMaterial>, Box<dyn Error>> {
+    let mut materials: Vec<MtlMaterial> = Vec::default();
+    for (line_index, line) in source.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        if keyword == "newmtl" {
+            materials.push(MtlMaterial::new(&tokens.collect::<Vec<&str>>().join(" ")));
+            continue;
+        }
+        let material = match materials.last_mut() {
+            Some(material) => material,
+            None => continue,
+        };
+        match keyword {
+            "Kd" => material.kd = parse_color(tokens, line_number)?,
+            "Ks" => material.ks = parse_color(tokens, line_number)?,
+            "Ke" => material.ke = parse_color(tokens, line_number)?,
+            "Tf" => material.tf = parse_color(tokens, line_number)?,
+            "Ns" => material.ns = parse_scalar(tokens, line_number)?,
+            "Ni" => material.ni = parse_scalar(tokens, line_number)?,
+            "d" => material.d = parse_scalar(tokens, line_number)?,
+            "Tr" => material.d = 1.0 - parse_scalar(tokens, line_number)?,
+            "illum" => material.illum = parse_scalar(tokens, line_number)? as usize,
+            "map_Kd" => material.map_kd = tokens.last().map(String::from),
+            _ => (),
+        }
+    }
+    Ok(materials)
+}
+
+/// Create a triangle mesh from the faces of a group.
+/// Normal vectors and texture coordinates are only used if they are specified for all face vertices.
+fn group_to_mesh(data: &ObjData, group: &ObjGroup, material: Arc<dyn Material>) -> TriangleMesh {
+    let has_uvs = group.faces.iter().flatten().all(|v| v.1.is_some());
+    let has_normals = group.faces.iter().flatten().all(|v| v.2.is_some());
+    let mut vertex_map = HashMap::<FaceVertex, usize>::default();
+    let mut vertices = Vec::default();
+    let mut uvs = Vec::default();
+    let mut normals = Vec::default();
+    let mut indices = Vec::with_capacity(group.faces.len());
+    for face in group.faces.iter() {
+        let mut triangle = [0; 3];
+        for (i, corner) in face.iter().enumerate() {
+            triangle[i] = *vertex_map.entry(*corner).or_insert_with(|| {
+                vertices.push(data.positions[corner.0]);
+                if has_uvs {
+                    uvs.push(data.uvs[corner.1.unwrap()]);
+                }
+                if has_normals {
+                    normals.push(data.normals[corner.2.unwrap()]);
+                }
+                vertices.len() - 1
+            });
+        }
+        indices.push(triangle);
+    }
+    TriangleMesh::new(vertices, normals, uvs, indices, material)
+}
+
+/// Create the geometry of an `.obj` file.
+/// Material libraries and textures are resolved relative to `root_path`.
+pub fn obj_to_geometry(
+    id: usize,
+    data: &ObjData,
+    root_path: &Path,
+) -> Result<Arc<dyn Geometry>, Box<dyn Error>> {
+    let mut mtl_materials = HashMap::<String, MtlMaterial>::default();
+    for library in data.material_libraries.iter() {
+        let filename = root_path.join(library);
+        let source = match fs::read_to_string(&filename) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("error reading file: {}", filename.display());
+                return Err(e.into());
+            }
+        };
+        for material in parse_mtl(&source)? {
+            mtl_materials.insert(material.name.clone(), material);
+        }
+    }
+    let mut materials = HashMap::<Option<String>, Arc<dyn Material>>::default();
+    let mut list: Vec<Arc<dyn Geometry>> = Vec::default();
+    for group in data.groups.iter() {
+        if !materials.contains_key(&group.material) {
+            let material = match group.material.as_ref().and_then(|m| mtl_materials.get(m)) {
+                Some(material) => material.to_material(root_path)?,
+                None => MtlMaterial::new("").to_material(root_path)?,
+            };
+            materials.insert(group.material.clone(), material);
+        }
+        list.push(Arc::new(group_to_mesh(
+            data,
+            group,
+            materials[&group.material].clone(),
+        )));
+    }
+    Ok(Arc::new(GeometryList { id, list }))
+}
+
+/// Load an `.obj` file and its material libraries
+pub fn load_obj(id: usize, filename: &str) -> Result<Arc<dyn Geometry>, Box<dyn Error>> {
+    let source = match fs::read_to_string(filename) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("error reading file: {}", filename);
+            return Err(e.into());
+        }
+    };
+    let data = parse_obj(&source)?;
+    let root_path = Path::new(filename)
+        .parent()
+        .unwrap_or_else(|| Path::new(""));
+    obj_to_geometry(id, &data, root_path)
+}
+
+#[cfg(test)]
+mod obj_test {
+    use super::*;
+    use crate::serialization::material::SerializeMaterial;
+    use crate::serialization::{RayTracingObject, Value};
+    use ray_tracing_core::math::Ray;
+    use ray_tracing_core::test;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    static QUAD_OBJ: &str = r#"
+        # quad and triangle
+        v 0.0 0.0 0.0
+        v 1.0 0.0 0.0
+        v 1.0 1.0 0.0
+        v 0.0 1.0 0.0
+        vt 0.0 0.0
+        vt 1.0 0.0
+        vt 1.0 1.0
+        vt 0.0 1.0
+        vn 0.0 0.0 1.0
+        usemtl red
+        f 1/1/1 2/2/1 3/3/1 4/4/1
+        usemtl light
+        f -4 -3 -1
+    "#;
+
+    static QUAD_MTL: &str = r#"
+        newmtl red
+        Kd 0.8 0.1 0.1
+        newmtl light
+        Ke 4 4 4
+        newmtl glass
+        Ni 1.5
+        illum 7
+        newmtl gold
+        Kd 0 0 0
+        Ks 1.0 0.8 0.3
+        Ns 900
+    "#;
+
+    #[test]
+    fn parse_obj_test() {
+        let data = parse_obj(QUAD_OBJ).unwrap();
+        assert_eq!(data.positions.len(), 4);
+        assert_eq!(data.uvs.len(), 4);
+        assert_eq!(data.normals.len(), 1);
+        assert_eq!(data.groups.len(), 2);
+        assert_eq!(data.groups[0].material, Some(String::from("red")));
+        assert_eq!(
+            data.groups[0].faces,
+            vec![
+                [
+                    (0, Some(0), Some(0)),
+                    (1, Some(1), Some(0)),
+                    (2, Some(2), Some(0))
+                ],
+                [
+                    (0, Some(0), Some(0)),
+                    (2, Some(2), Some(0)),
+                    (3, Some(3), Some(0))
+                ],
+            ]
+        );
+        assert_eq!(
+            data.groups[1].faces,
+            vec![[(0, None, None), (1, None, None), (3, None, None)]]
+        );
+    }
+
+    #[test]
+    fn parse_obj_index_error_test() {
+        assert!(parse_obj("v 0 0 0\nv 1 0 0\nf 1 2 3").is_err());
+        assert!(parse_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2").is_err());
+    }
+
+    #[test]
+    fn parse_mtl_test() {
+        let materials = parse_mtl(QUAD_MTL).unwrap();
+        assert_eq!(materials.len(), 4);
+        test::assert_eq_vector3(&materials[0].kd, &ColorRGB::new(0.8, 0.1, 0.1), 0.001);
+        test::assert_eq_vector3(&materials[1].ke, &ColorRGB::new(4.0, 4.0, 4.0), 0.001);
+        test::assert_eq_float(materials[2].ni, 1.5, 0.001);
+        assert_eq!(materials[2].illum, 7);
+        test::assert_eq_float(materials[3].ns, 900.0, 0.001);
+    }
+
+    fn serialize_material(m: Arc<dyn Material>) -> RayTracingObject {
+        let object_map = Rc::new(RefCell::new(HashMap::<usize, RayTracingObject>::default()));
+        m.accept(&mut SerializeMaterial {
+            object_map: object_map.clone(),
+        })
+        .unwrap();
+        let o = object_map.borrow()[&m.get_id()].clone();
+        o
+    }
+
+    #[test]
+    fn mtl_to_material_test() {
+        let materials = parse_mtl(QUAD_MTL).unwrap();
+        let root_path = Path::new("");
+        match serialize_material(materials[0].to_material(root_path).unwrap()) {
+            RayTracingObject::Lambertian(_) => (),
+            _ => panic!("lambertian expected"),
+        }
+        match serialize_material(materials[1].to_material(root_path).unwrap()) {
+            RayTracingObject::DiffuseLight(_) => (),
+            _ => panic!("diffuse light expected"),
+        }
+        match serialize_material(materials[2].to_material(root_path).unwrap()) {
+            RayTracingObject::Dielectric(d) => assert_eq!(d.ref_idx, Value::Range((1.5, 1.5))),
+            _ => panic!("dielectric expected"),
+        }
+        match serialize_material(materials[3].to_material(root_path).unwrap()) {
+            RayTracingObject::Metal(m) => match m.fuzz {
+                Value::Scalar(fuzz) => test::assert_eq_float(fuzz, 0.1, 0.001),
+                _ => panic!("scalar expected"),
+            },
+            _ => panic!("metal expected"),
+        }
+    }
+
+    #[test]
+    fn obj_to_geometry_test() {
+        let data = parse_obj(QUAD_OBJ).unwrap();
+        let g = obj_to_geometry(7, &data, Path::new("")).unwrap();
+        assert_eq!(g.get_id(), 7);
+        let ray = Ray::new_ray(Vector3::new(0.75, 0.25, 1.0), Vector3::new(0.0, 0.0, -1.0));
+        match g.hit(&ray, 0.0..10.0) {
+            Some(hit_record) => {
+                test::assert_eq_float(hit_record.t, 1.0, 0.001);
+                test::assert_eq_float(hit_record.uv.u, 0.75, 0.001);
+                test::assert_eq_float(hit_record.uv.v, 0.25, 0.001);
+                test::assert_eq_vector3(&hit_record.normal, &Vector3::new(0.0, 0.0, 1.0), 0.001);
+            }
+            None => panic!("no result"),
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use crate::render;
+use ray_tracing_core::core::Scene;
+use ray_tracing_core::random;
+use ray_tracing_core::test::SampleStatistics;
+use ray_tracing_core::types::{ColorRGB, FSize};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Bundled scenes, which are compared with the stored references
+pub const REGRESSION_SCENES: [&str; 6] = [
+    "TestSceneSimple",
+    "Room",
+    "Materials1",
+    "MaterialDielectric1",
+    "TextureChecker",
+    "SpheresFog",
+];
+
+/// Size of the regression renders
+pub const REGRESSION_SIZE: (usize, usize) = (16, 8);
+
+/// Samples per pixel of the references
+pub const REFERENCE_SAMPLES: usize = 16384;
+
+/// Samples per pixel of the renders, which are compared with the references
+pub const TEST_SAMPLES: usize = 256;
+
+/// Seed of the random generation, while the scenes are loaded
+pub const SCENE_SEED: u64 = 1;
+
+pub const REFERENCE_SEED: u64 = 1;
+
+/// The renders, which are compared with the references, use a different seed,
+/// so that their samples are independent of the samples of the references
+pub const TEST_SEED: u64 = 2;
+
+/// Parameters of the per pixel comparison of a render with a reference
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConfidenceSettings {
+    /// Width of the confidence interval of the difference of the means in standard errors
+    pub z: FSize,
+
+    /// Absolute tolerance, for pixels without variance
+    pub tolerance: FSize,
+
+    /// Fraction of the pixels, which may be outside of the confidence interval.
+    /// Pixels with rare bright samples have heavy tailed distributions and the variance is underestimated.
+    pub outlier_fraction: FSize,
+}
+
+impl Default for ConfidenceSettings {
+    fn default() -> ConfidenceSettings {
+        ConfidenceSettings {
+            z: 4.0,
+            tolerance: 0.0001,
+            outlier_fraction: 0.05,
+        }
+    }
+}
+
+/// Result of the comparison of a render with a reference
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegressionReport {
+    /// Number of compared pixels
+    pub pixels: usize,
+
+    /// Number of pixels with a channel outside of the confidence interval
+    pub outliers: usize,
+
+    /// Largest difference of the means of a channel in standard errors and the pixel, at which it occurs
+    pub max_z: FSize,
+    pub max_z_pixel: (usize, usize),
+
+    /// Largest difference of the mean of a channel over all pixels in standard errors.
+    /// A small bias of all pixels is detected, even if the pixels are too noisy to detect it.
+    pub image_z: FSize,
+
+    pub passed: bool,
+}
+
+/// Per pixel mean and variance of the samples of a render with a fixed seed
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RegressionReference {
+    pub cx: usize,
+    pub cy: usize,
+    pub samples: usize,
+    pub seed: u64,
+
+    /// Mean of the samples of each pixel
+    pub mean: Vec<[f32; 3]>,
+
+    /// Unbiased variance of the samples of each pixel
+    pub variance: Vec<[f32; 3]>,
+}
+
+impl RegressionReference {
+    /// Renders the scene with `samples` jittered samples per pixel.
+    /// The random number generation of each pixel is seeded, so the result is reproducible.
+    pub fn render(
+        scene: &Scene,
+        cx: usize,
+        cy: usize,
+        samples: usize,
+        seed: u64,
+    ) -> Result<RegressionReference, Box<dyn Error>> {
+        let scene = scene.from_scene_and_aspect(cx as FSize / cy as FSize)?;
+        let mut mean = Vec::with_capacity(cx * cy);
+        let mut variance = Vec::with_capacity(cx * cy);
+        for y in 0..cy {
+            for x in 0..cx {
+                let statistics =
+                    random::with_seed(Some(random::derive_seed(seed, x, y, 0)), || {
+                        let mut statistics = SampleStatistics::new();
+                        for _ in 0..samples {
+                            let u = (x as FSize + random::generate_size()) / cx as FSize;
+                            let v = 1.0 - (y as FSize + random::generate_size()) / cy as FSize;
+                            statistics.add(scene.ray_trace_color(u, v));
+                        }
+                        statistics
+                    });
+                let to_f32 = |c: ColorRGB| [c.x as f32, c.y as f32, c.z as f32];
+                mean.push(to_f32(statistics.mean));
+                variance.push(to_f32(statistics.variance()));
+            }
+        }
+        Ok(RegressionReference {
+            cx,
+            cy,
+            samples,
+            seed,
+            mean,
+            variance,
+        })
+    }
+
+    /// Loads the scene `{name}.json` from `scene_dir` and renders it like [`RegressionReference::render`].
+    /// The scene is loaded with a fixed seed, so that random objects and textures are independent of `seed`.
+    pub fn render_scene(
+        scene_dir: &Path,
+        name: &str,
+        cx: usize,
+        cy: usize,
+        samples: usize,
+        seed: u64,
+    ) -> Result<RegressionReference, Box<dyn Error>> {
+        let scene =
+            render::load_scene(&scene_dir.join(format!("{}.json", name)), Some(SCENE_SEED))?;
+        RegressionReference::render(&scene, cx, cy, samples, seed)
+    }
+
+    /// File of the reference of a scene
+    pub fn path(reference_dir: &Path, name: &str) -> PathBuf {
+        reference_dir.join(format!("{}.json", name))
+    }
+
+    pub fn load(path: &Path) -> Result<RegressionReference, Box<dyn Error>> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Compares the per pixel means with the means of the reference.
+    /// A pixel is an outlier, if the difference of the means of a channel is outside of the confidence interval,
+    /// which is computed from the pooled variance of both renders. The pooled variance is dominated by
+    /// the reference, which is a better estimate, if the render has too few samples to see rare bright paths.
+    /// The render fails, if there are too many outliers or if the mean of the whole image is outside of
+    /// its confidence interval.
+    pub fn compare(
+        &self,
+        reference: &RegressionReference,
+        settings: &ConfidenceSettings,
+    ) -> Result<RegressionReport, Box<dyn Error>> {
+        if (self.cx, self.cy) != (reference.cx, reference.cy) {
+            return Err(format!(
+                "the size {}x{} does not match the size of the reference {}x{}",
+                self.cx, self.cy, reference.cx, reference.cy
+            )
+            .into());
+        }
+        let z_score = |difference: FSize, standard_error: FSize| {
+            let excess = FSize::max(0.0, difference.abs() - settings.tolerance);
+            if excess == 0.0 {
+                0.0
+            } else if standard_error > 0.0 {
+                excess / standard_error
+            } else {
+                FSize::INFINITY
+            }
+        };
+        let (n, n_reference) = (self.samples as FSize, reference.samples as FSize);
+        let mut report = RegressionReport {
+            pixels: self.cx * self.cy,
+            outliers: 0,
+            max_z: 0.0,
+            max_z_pixel: (0, 0),
+            image_z: 0.0,
+            passed: true,
+        };
+        let mut sum_difference = [0.0; 3];
+        let mut sum_squared_error = [0.0; 3];
+        for i in 0..self.cx * self.cy {
+            let mut outlier = false;
+            for c in 0..3 {
+                let difference = self.mean[i][c] as FSize - reference.mean[i][c] as FSize;
+                let pooled_variance = (self.variance[i][c] as FSize * FSize::max(0.0, n - 1.0)
+                    + reference.variance[i][c] as FSize * FSize::max(0.0, n_reference - 1.0))
+                    / FSize::max(1.0, n + n_reference - 2.0);
+                let squared_error = pooled_variance
+                    * (1.0 / FSize::max(1.0, n) + 1.0 / FSize::max(1.0, n_reference));
+                sum_difference[c] += difference;
+                sum_squared_error[c] += squared_error;
+                let z = z_score(difference, squared_error.sqrt());
+                outlier = outlier || z > settings.z || z.is_nan();
+                if z > report.max_z {
+                    report.max_z = z;
+                    report.max_z_pixel = (i % self.cx, i / self.cx);
+                }
+            }
+            if outlier {
+                report.outliers += 1;
+            }
+        }
+        // the pixels are independent, the variance of the sum is the sum of the variances
+        let pixels = FSize::max(1.0, report.pixels as FSize);
+        for c in 0..3 {
+            let z = z_score(
+                sum_difference[c] / pixels,
+                sum_squared_error[c].sqrt() / pixels,
+            );
+            report.image_z = FSize::max(report.image_z, z);
+        }
+        report.passed = report.outliers as FSize
+            <= report.pixels as FSize * settings.outlier_fraction
+            && report.image_z <= settings.z;
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod regression_test {
+    use super::*;
+    use ray_tracing_core::test::TestSceneSimple;
+
+    #[test]
+    fn compare_test() {
+        let scene = TestSceneSimple::new().scene;
+        let reference = RegressionReference::render(&scene, 8, 4, 256, REFERENCE_SEED).unwrap();
+        let result = RegressionReference::render(&scene, 8, 4, 32, TEST_SEED).unwrap();
+        let settings = ConfidenceSettings::default();
+        let report = result.compare(&reference, &settings).unwrap();
+        assert_eq!(report.pixels, 8 * 4);
+        assert!(report.passed, "{:?}", report);
+
+        // a renderer, which is 10% too bright
+        let mut broken = result.clone();
+        for mean in broken.mean.iter_mut() {
+            for c in mean.iter_mut() {
+                *c *= 1.1;
+            }
+        }
+        let report = broken.compare(&reference, &settings).unwrap();
+        assert!(!report.passed, "{:?}", report);
+
+        let small = RegressionReference::render(&scene, 4, 4, 1, TEST_SEED).unwrap();
+        assert!(small.compare(&reference, &settings).is_err());
+    }
+
+    #[test]
+    fn render_seeded_test() {
+        let scene = TestSceneSimple::new().scene;
+        let render = |seed| RegressionReference::render(&scene, 4, 2, 8, seed).unwrap();
+        assert_eq!(render(TEST_SEED), render(TEST_SEED));
+        assert_ne!(render(TEST_SEED).mean, render(REFERENCE_SEED).mean);
+    }
+
+    /// Renders small versions of the bundled scenes and compares them with the stored references.
+    /// The references are created with `rt_regression --update`.
+    #[test]
+    fn scene_regression_test() {
+        for name in REGRESSION_SCENES {
+            let reference = RegressionReference::load(&RegressionReference::path(
+                Path::new("../rendering/reference"),
+                name,
+            ))
+            .unwrap();
+            let result = RegressionReference::render_scene(
+                Path::new("../scene"),
+                name,
+                reference.cx,
+                reference.cy,
+                TEST_SAMPLES,
+                TEST_SEED,
+            )
+            .unwrap();
+            let report = result
+                .compare(&reference, &ConfidenceSettings::default())
+                .unwrap();
+            assert!(report.passed, "{}: {:?}", name, report);
+        }
+    }
+}
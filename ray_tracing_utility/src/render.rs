@@ -0,0 +1,352 @@
+use crate::aov::AovBuffers;
+use crate::image;
+use crate::serialization::core;
+use crate::serialization::core::DeserializeOptions;
+use crate::serialization::json;
+use crate::view::{CheckpointSettings, HeadlessView, ViewModel, Viewer};
+use ray_tracing_core::core::Scene;
+use ray_tracing_core::random;
+use ray_tracing_core::types::ColorRGB;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Image, which is rendered by [`render`]
+pub struct RenderedImage {
+    pub cx: usize,
+    pub cy: usize,
+
+    /// RGBA pixel data with gamma 2
+    pub pixel_data: Vec<u8>,
+
+    /// Linear colors of the pixels
+    pub pixel_color: Vec<ColorRGB>,
+
+    /// Arbitrary output variables, if they are requested by the view model
+    pub aovs: Option<AovBuffers>,
+}
+
+impl RenderedImage {
+    /// Saves the image. High dynamic range formats (`.hdr`, `.pfm`, `.exr`) store the linear colors,
+    /// any other file is saved as PNG.
+    ///
+    /// The arbitrary output variables are stored as additional channels of an `.exr` file.
+    /// For the other formats each variable is saved to a separate image `{name}_{aov name}.{extension}`,
+    /// where the extension of a PNG image is replaced by `exr`.
+    pub fn save(&self, filename: &str) -> Result<(), Box<dyn Error>> {
+        let extension = Path::new(filename)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        match &self.aovs {
+            Some(aovs) if extension == "exr" => {
+                return aovs.save_exr(filename, &self.pixel_color);
+            }
+            Some(aovs) => {
+                let base_name = filename.strip_suffix(&format!(".{}", extension));
+                let aov_extension = if image::is_hdr_file_name(filename) {
+                    extension.as_str()
+                } else {
+                    "exr"
+                };
+                aovs.save_images(base_name.unwrap_or(filename), aov_extension)?;
+            }
+            None => (),
+        }
+        if image::is_hdr_file_name(filename) {
+            image::save_hdr_image(filename, self.cx, self.cy, &self.pixel_color)
+        } else {
+            image::save_image(filename, self.cx, self.cy, &self.pixel_data);
+            Ok(())
+        }
+    }
+}
+
+/// Extension of the file name of a view model, which belongs to the scene with the same name
+pub const VIEW_MODEL_EXTENSION: &str = ".view.json";
+
+/// Scene of a batch render and its view model
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchJob {
+    pub name: String,
+    pub scene_path: PathBuf,
+
+    /// View model of the scene (`<name>.view.json`), if there is no view model the default view model is used
+    pub view_model_path: Option<PathBuf>,
+}
+
+/// Name of the image file of a rendered scene, without extension: `{name}_{cx}x{cy}_{samples}_samples`
+pub fn image_file_name(name: &str, view_model: &ViewModel) -> String {
+    format!(
+        "{}_{}x{}_{}_samples",
+        name,
+        view_model.cx,
+        view_model.cy,
+        view_model.repetitions * view_model.samples
+    )
+}
+
+/// Finds the scenes in a directory, sorted by name.
+/// JSON files, which are not scenes (e.g. view models), are ignored.
+pub fn find_batch_jobs(dir: &Path) -> Result<Vec<BatchJob>, Box<dyn Error>> {
+    let mut jobs = Vec::default();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let file_name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(file_name) => file_name.to_string(),
+            None => continue,
+        };
+        if !file_name.ends_with(".json") || file_name.ends_with(VIEW_MODEL_EXTENSION) {
+            continue;
+        }
+        if serde_json::from_str::<core::Scene>(&fs::read_to_string(&path)?).is_err() {
+            continue;
+        }
+        let name = file_name.trim_end_matches(".json").to_string();
+        let view_model_path = dir.join(format!("{}{}", name, VIEW_MODEL_EXTENSION));
+        jobs.push(BatchJob {
+            name,
+            scene_path: path,
+            view_model_path: if view_model_path.is_file() {
+                Some(view_model_path)
+            } else {
+                None
+            },
+        });
+    }
+    jobs.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(jobs)
+}
+
+/// Loads a scene from a JSON file.
+/// Relative paths of files in the scene (bitmaps, meshes) are resolved relative to the directory of the scene file.
+/// If `seed` is set, the random parts of the scene are generated deterministically.
+pub fn load_scene(file_path: &Path, seed: Option<u64>) -> Result<Scene, Box<dyn Error>> {
+    let json_dir = match file_path.parent() {
+        Some(path) if path.is_absolute() => path.to_path_buf(),
+        Some(path) => env::current_dir()?.join(path),
+        None => env::current_dir()?,
+    };
+    let json_scene = fs::read_to_string(file_path)?;
+    let options = DeserializeOptions::form_path(json_dir.as_path());
+    random::with_seed(seed, || {
+        json::deserialize_scene_with_options(&json_scene, &options)
+    })
+}
+
+/// Renders a scene without a window.
+/// The progress is printed to stdout. If `time_limit` is set, the image rendered so far is returned,
+/// when the time is elapsed.
+pub fn render(
+    view_model: ViewModel,
+    scene: Arc<Scene>,
+    time_limit: Option<Duration>,
+) -> Result<RenderedImage, Box<dyn Error>> {
+    render_with_checkpoint(view_model, scene, time_limit, None)
+}
+
+/// Renders a scene without a window like [`render`] and writes checkpoints of the render.
+/// If the checkpoint settings request it, the render is resumed from an existing checkpoint.
+pub fn render_with_checkpoint(
+    view_model: ViewModel,
+    scene: Arc<Scene>,
+    time_limit: Option<Duration>,
+    checkpoint: Option<CheckpointSettings>,
+) -> Result<RenderedImage, Box<dyn Error>> {
+    let mut viewer = Viewer::new(
+        view_model,
+        scene,
+        Arc::new(HeadlessView::new()),
+        Box::new(|_, _, _, _| ()),
+    )?;
+    viewer.set_time_limit(time_limit);
+    viewer.set_checkpoint(checkpoint)?;
+    let (cx, cy, pixel_data) = viewer.run()?;
+    Ok(RenderedImage {
+        cx,
+        cy,
+        pixel_data,
+        pixel_color: viewer.pixel_color().clone(),
+        aovs: viewer.aov_buffers(),
+    })
+}
+
+#[cfg(test)]
+mod render_test {
+    use super::*;
+    use crate::aov::AovType;
+    use crate::denoise::DenoiseSettings;
+    use crate::view::Checkpoint;
+    use crate::view::{DisplaySettings, SamplerType};
+    use ray_tracing_core::display::DisplayPipeline;
+    use ray_tracing_core::test;
+    use ray_tracing_core::test::TestSceneSimple;
+
+    fn test_view_model() -> ViewModel {
+        ViewModel {
+            cx: 8,
+            cy: 4,
+            repetitions_threads: 2,
+            repetitions: 2,
+            samples: 2,
+            seed: Some(1),
+            sampler: SamplerType::Independent,
+            adaptive_sampling: None,
+            display: DisplaySettings::default(),
+            aovs: Vec::default(),
+            denoise: None,
+        }
+    }
+
+    #[test]
+    fn render_test() {
+        let scene = Arc::new(TestSceneSimple::new().scene);
+        let image = render(test_view_model(), scene.clone(), None).unwrap();
+        assert_eq!((image.cx, image.cy), (8, 4));
+        assert_eq!(image.pixel_data.len(), 8 * 4 * 4);
+        assert_eq!(image.pixel_color.len(), 8 * 4);
+        assert!(image.pixel_data.chunks(4).all(|p| p[3] == 255));
+        let display = DisplayPipeline::default();
+        for (i, (c, p)) in image
+            .pixel_color
+            .iter()
+            .zip(image.pixel_data.chunks(4))
+            .enumerate()
+        {
+            assert_eq!(display.to_rgba8(c, i % 8, i / 8), p);
+        }
+        let image_2 = render(test_view_model(), scene, None).unwrap();
+        assert_eq!(image.pixel_data, image_2.pixel_data);
+    }
+
+    #[test]
+    fn render_aovs_test() {
+        let scene = Arc::new(TestSceneSimple::new().scene);
+        let view_model = ViewModel {
+            cx: 16,
+            cy: 8,
+            aovs: vec![AovType::Albedo, AovType::Depth, AovType::Direct],
+            ..test_view_model()
+        };
+        let image = render(view_model, scene.clone(), None).unwrap();
+        let aovs = image.aovs.unwrap();
+        assert_eq!((aovs.cx, aovs.cy), (16, 8));
+        assert_eq!(aovs.buffers.len(), 3);
+        assert_eq!(aovs.get(AovType::Albedo).unwrap().data.len(), 16 * 8 * 3);
+        assert_eq!(aovs.get(AovType::Depth).unwrap().data.len(), 16 * 8);
+        // the center of the image shows the red sphere
+        let albedo = aovs.get(AovType::Albedo).unwrap().to_colors();
+        test::assert_eq_vector3(&albedo[4 * 16 + 8], &ColorRGB::new(0.5, 0.1, 0.1), 0.000001);
+        assert!(aovs.get(AovType::Depth).unwrap().data[4 * 16 + 8] > 0.0);
+
+        let image = render(test_view_model(), scene, None).unwrap();
+        assert!(image.aovs.is_none());
+    }
+
+    #[test]
+    fn render_denoise_test() {
+        let scene = Arc::new(TestSceneSimple::new().scene);
+        let view_model = ViewModel {
+            denoise: Some(DenoiseSettings::default()),
+            ..test_view_model()
+        };
+        let image = render(view_model, scene.clone(), None).unwrap();
+        assert!(image.aovs.is_none());
+        assert_eq!(image.pixel_color.len(), 8 * 4);
+        assert!(image
+            .pixel_color
+            .iter()
+            .all(|c| c.x.is_finite() && c.y.is_finite() && c.z.is_finite()));
+        let noisy = render(test_view_model(), scene, None).unwrap();
+        assert!(image.pixel_color != noisy.pixel_color);
+    }
+
+    #[test]
+    fn render_checkpoint_test() {
+        let scene = Arc::new(TestSceneSimple::new().scene);
+        let path = env::temp_dir().join(format!(
+            "rt_render_checkpoint_test_{}.bin",
+            std::process::id()
+        ));
+        let settings = CheckpointSettings {
+            path: path.clone(),
+            interval: Duration::from_secs(3600),
+            resume: true,
+        };
+        let view_model = ViewModel {
+            repetitions: 4,
+            ..test_view_model()
+        };
+        let full = render(view_model.clone(), scene.clone(), None).unwrap();
+
+        // render the first half of the passes, then resume until all passes are rendered
+        let half = ViewModel {
+            repetitions: 2,
+            ..view_model.clone()
+        };
+        render_with_checkpoint(half, scene.clone(), None, Some(settings.clone())).unwrap();
+        let first_pass = Checkpoint::load(&path).map(|c| c.first_pass());
+        let resumed = render_with_checkpoint(
+            view_model.clone(),
+            scene.clone(),
+            None,
+            Some(settings.clone()),
+        );
+        let other_size = ViewModel {
+            cx: 4,
+            ..view_model
+        };
+        let mismatch = render_with_checkpoint(other_size, scene, None, Some(settings));
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(first_pass.unwrap(), 3);
+        assert_eq!(resumed.unwrap().pixel_color, full.pixel_color);
+        assert!(mismatch.is_err());
+    }
+
+    #[test]
+    fn image_file_name_test() {
+        assert_eq!(
+            image_file_name("Room", &test_view_model()),
+            "Room_8x4_4_samples"
+        );
+    }
+
+    #[test]
+    fn find_batch_jobs_test() {
+        let dir = env::temp_dir().join(format!("rt_batch_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let scene = json::serialize_scene(&TestSceneSimple::new().scene).unwrap();
+        let view_model = json::serialize_view_model(&test_view_model()).unwrap();
+        fs::write(dir.join("B.json"), &scene).unwrap();
+        fs::write(dir.join("A.json"), &scene).unwrap();
+        fs::write(dir.join("A.view.json"), &view_model).unwrap();
+        fs::write(dir.join("Configuration.json"), &view_model).unwrap();
+        fs::write(dir.join("readme.txt"), "").unwrap();
+        let jobs = find_batch_jobs(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let jobs = jobs.unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].name, "A");
+        assert_eq!(jobs[0].view_model_path, Some(dir.join("A.view.json")));
+        assert_eq!(jobs[1].name, "B");
+        assert_eq!(jobs[1].scene_path, dir.join("B.json"));
+        assert_eq!(jobs[1].view_model_path, None);
+    }
+
+    #[test]
+    fn render_time_limit_test() {
+        let scene = Arc::new(TestSceneSimple::new().scene);
+        let view_model = ViewModel {
+            repetitions: 100000,
+            ..test_view_model()
+        };
+        let image = render(view_model, scene, Some(Duration::from_millis(100))).unwrap();
+        assert_eq!(image.pixel_data.len(), image.cx * image.cy * 4);
+    }
+}
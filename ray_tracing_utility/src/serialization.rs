@@ -1,7 +1,9 @@
 use self::core::*;
 use self::environment::Sky;
 use self::geometry::collection::Collection;
+use self::geometry::csg::CSGNode;
 use self::geometry::instancing::*;
+use self::geometry::obj_file::ObjFile;
 use self::geometry::shape::*;
 use self::geometry::volume::*;
 use self::material::*;
@@ -297,11 +299,16 @@ pub enum RayTracingObject {
     XZRect(XZRect),
     YZRect(YZRect),
     Cuboid(Cuboid),
+    Triangle(Triangle),
+    TriangleMesh(TriangleMesh),
+    ObjFile(ObjFile),
     FlipNormals(FlipNormals),
     RotateX(RotateX),
     RotateY(RotateY),
     RotateZ(RotateZ),
     Translate(Translate),
+    Transform(Transform),
+    CSGNode(CSGNode),
     ConstantMedium(ConstantMedium),
 }
 
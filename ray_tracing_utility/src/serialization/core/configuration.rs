@@ -1,19 +1,207 @@
 use crate::serialization::IdConstructor;
 use ray_tracing_core::core;
+use ray_tracing_core::integrator;
+use ray_tracing_core::types::FSize;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
 
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum LightSelection {
+    Uniform,
+    SolidAngle,
+    Power,
+}
+
+impl LightSelection {
+    pub fn from_light_selection(s: core::LightSelection) -> LightSelection {
+        match s {
+            core::LightSelection::Uniform => LightSelection::Uniform,
+            core::LightSelection::SolidAngle => LightSelection::SolidAngle,
+            core::LightSelection::Power => LightSelection::Power,
+        }
+    }
+
+    pub fn to_light_selection(&self) -> core::LightSelection {
+        match self {
+            LightSelection::Uniform => core::LightSelection::Uniform,
+            LightSelection::SolidAngle => core::LightSelection::SolidAngle,
+            LightSelection::Power => core::LightSelection::Power,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum RussianRoulette {
+    Disabled,
+    Throughput,
+}
+
+impl RussianRoulette {
+    pub fn from_russian_roulette(r: integrator::RussianRoulette) -> RussianRoulette {
+        match r {
+            integrator::RussianRoulette::Disabled => RussianRoulette::Disabled,
+            integrator::RussianRoulette::Throughput => RussianRoulette::Throughput,
+        }
+    }
+
+    pub fn to_russian_roulette(&self) -> integrator::RussianRoulette {
+        match self {
+            RussianRoulette::Disabled => integrator::RussianRoulette::Disabled,
+            RussianRoulette::Throughput => integrator::RussianRoulette::Throughput,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum DebugMode {
+    Normal,
+    UV,
+    Depth { maximum_depth: FSize },
+    MaterialId,
+    GeometryId,
+    TraversalCost { maximum: usize },
+    IntersectionCount { maximum: usize },
+    BackFace,
+}
+
+impl DebugMode {
+    pub fn from_debug_mode(m: integrator::DebugMode) -> DebugMode {
+        match m {
+            integrator::DebugMode::Normal => DebugMode::Normal,
+            integrator::DebugMode::UV => DebugMode::UV,
+            integrator::DebugMode::Depth { maximum_depth } => DebugMode::Depth { maximum_depth },
+            integrator::DebugMode::MaterialId => DebugMode::MaterialId,
+            integrator::DebugMode::GeometryId => DebugMode::GeometryId,
+            integrator::DebugMode::TraversalCost { maximum } => {
+                DebugMode::TraversalCost { maximum }
+            }
+            integrator::DebugMode::IntersectionCount { maximum } => {
+                DebugMode::IntersectionCount { maximum }
+            }
+            integrator::DebugMode::BackFace => DebugMode::BackFace,
+        }
+    }
+
+    pub fn to_debug_mode(&self) -> integrator::DebugMode {
+        match *self {
+            DebugMode::Normal => integrator::DebugMode::Normal,
+            DebugMode::UV => integrator::DebugMode::UV,
+            DebugMode::Depth { maximum_depth } => integrator::DebugMode::Depth { maximum_depth },
+            DebugMode::MaterialId => integrator::DebugMode::MaterialId,
+            DebugMode::GeometryId => integrator::DebugMode::GeometryId,
+            DebugMode::TraversalCost { maximum } => {
+                integrator::DebugMode::TraversalCost { maximum }
+            }
+            DebugMode::IntersectionCount { maximum } => {
+                integrator::DebugMode::IntersectionCount { maximum }
+            }
+            DebugMode::BackFace => integrator::DebugMode::BackFace,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum IntegratorType {
+    PathTracer,
+    RecursivePathTracer,
+    DirectLighting,
+    AmbientOcclusion { distance: FSize },
+    DebugView { mode: DebugMode },
+}
+
+impl IntegratorType {
+    pub fn from_integrator_type(t: integrator::IntegratorType) -> IntegratorType {
+        match t {
+            integrator::IntegratorType::PathTracer => IntegratorType::PathTracer,
+            integrator::IntegratorType::RecursivePathTracer => IntegratorType::RecursivePathTracer,
+            integrator::IntegratorType::DirectLighting => IntegratorType::DirectLighting,
+            integrator::IntegratorType::AmbientOcclusion { distance } => {
+                IntegratorType::AmbientOcclusion { distance }
+            }
+            integrator::IntegratorType::DebugView { mode } => IntegratorType::DebugView {
+                mode: DebugMode::from_debug_mode(mode),
+            },
+        }
+    }
+
+    pub fn to_integrator_type(&self) -> integrator::IntegratorType {
+        match *self {
+            IntegratorType::PathTracer => integrator::IntegratorType::PathTracer,
+            IntegratorType::RecursivePathTracer => integrator::IntegratorType::RecursivePathTracer,
+            IntegratorType::DirectLighting => integrator::IntegratorType::DirectLighting,
+            IntegratorType::AmbientOcclusion { distance } => {
+                integrator::IntegratorType::AmbientOcclusion { distance }
+            }
+            IntegratorType::DebugView { mode } => integrator::IntegratorType::DebugView {
+                mode: mode.to_debug_mode(),
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct Configuration {
     pub id: IdConstructor,
     pub maximum_depth: usize,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+
+    #[serde(default = "Configuration::default_light_selection")]
+    pub light_selection: LightSelection,
+
+    #[serde(default)]
+    pub next_event_estimation: bool,
+
+    #[serde(default = "Configuration::default_integrator")]
+    pub integrator: IntegratorType,
+
+    #[serde(default = "Configuration::default_russian_roulette")]
+    pub russian_roulette: RussianRoulette,
+
+    #[serde(default = "Configuration::default_russian_roulette_minimum_depth")]
+    pub russian_roulette_minimum_depth: usize,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clamp_direct: Option<FSize>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clamp_indirect: Option<FSize>,
+
+    #[serde(default)]
+    pub diagnostics: bool,
 }
 
 impl Configuration {
+    fn default_light_selection() -> LightSelection {
+        LightSelection::SolidAngle
+    }
+
+    fn default_integrator() -> IntegratorType {
+        IntegratorType::PathTracer
+    }
+
+    fn default_russian_roulette() -> RussianRoulette {
+        RussianRoulette::Disabled
+    }
+
+    fn default_russian_roulette_minimum_depth() -> usize {
+        5
+    }
+
     pub fn from_configuration(c: &core::Configuration) -> Result<Configuration, Box<dyn Error>> {
         Ok(Configuration {
             id: IdConstructor::Single(c.id),
             maximum_depth: c.maximum_depth,
+            seed: c.seed,
+            light_selection: LightSelection::from_light_selection(c.light_selection),
+            next_event_estimation: c.next_event_estimation,
+            integrator: IntegratorType::from_integrator_type(c.integrator),
+            russian_roulette: RussianRoulette::from_russian_roulette(c.russian_roulette),
+            russian_roulette_minimum_depth: c.russian_roulette_minimum_depth,
+            clamp_direct: c.clamp_direct,
+            clamp_indirect: c.clamp_indirect,
+            diagnostics: c.diagnostics,
         })
     }
 
@@ -21,6 +209,15 @@ impl Configuration {
         Ok(core::Configuration {
             id: self.id.get_id(index),
             maximum_depth: self.maximum_depth,
+            seed: self.seed,
+            light_selection: self.light_selection.to_light_selection(),
+            next_event_estimation: self.next_event_estimation,
+            integrator: self.integrator.to_integrator_type(),
+            russian_roulette: self.russian_roulette.to_russian_roulette(),
+            russian_roulette_minimum_depth: self.russian_roulette_minimum_depth,
+            clamp_direct: self.clamp_direct,
+            clamp_indirect: self.clamp_indirect,
+            diagnostics: self.diagnostics,
         })
     }
 }
@@ -34,6 +231,7 @@ mod configuration_test {
         let cc = core::Configuration::new(100);
         let c = Configuration::from_configuration(&cc).unwrap();
         assert_eq!(cc.maximum_depth, c.maximum_depth);
+        assert_eq!(c.light_selection, LightSelection::SolidAngle);
     }
 
     #[test]
@@ -41,8 +239,61 @@ mod configuration_test {
         let c = Configuration {
             id: IdConstructor::Single(0),
             maximum_depth: 100,
+            seed: Some(5),
+            light_selection: LightSelection::Power,
+            next_event_estimation: true,
+            integrator: IntegratorType::AmbientOcclusion { distance: 2.0 },
+            russian_roulette: RussianRoulette::Throughput,
+            russian_roulette_minimum_depth: 3,
+            clamp_direct: Some(10.0),
+            clamp_indirect: None,
+            diagnostics: true,
         };
         let cc = c.to_configuration(0).unwrap();
         assert_eq!(c.maximum_depth, cc.maximum_depth);
+        assert_eq!(cc.seed, Some(5));
+        assert_eq!(cc.light_selection, core::LightSelection::Power);
+        assert!(cc.next_event_estimation);
+        assert_eq!(cc.russian_roulette, integrator::RussianRoulette::Throughput);
+        assert_eq!(cc.russian_roulette_minimum_depth, 3);
+        assert_eq!(cc.clamp_direct, Some(10.0));
+        assert_eq!(cc.clamp_indirect, None);
+        assert!(cc.diagnostics);
+        assert_eq!(
+            cc.integrator,
+            integrator::IntegratorType::AmbientOcclusion { distance: 2.0 }
+        );
+    }
+
+    #[test]
+    fn configuration_default_light_selection() {
+        let c: Configuration = serde_json::from_str(r#"{"id": 1, "maximum_depth": 10}"#).unwrap();
+        assert_eq!(c.light_selection, LightSelection::SolidAngle);
+        assert!(!c.next_event_estimation);
+        assert_eq!(c.integrator, IntegratorType::PathTracer);
+        assert_eq!(c.russian_roulette, RussianRoulette::Disabled);
+        assert_eq!(c.russian_roulette_minimum_depth, 5);
+        assert_eq!(c.clamp_direct, None);
+        assert!(!c.diagnostics);
+
+        let c: Configuration = serde_json::from_str(
+            r#"{"id": 1, "maximum_depth": 10, "integrator": {"AmbientOcclusion": {"distance": 5.0}}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            c.integrator,
+            IntegratorType::AmbientOcclusion { distance: 5.0 }
+        );
+
+        let c: Configuration = serde_json::from_str(
+            r#"{"id": 1, "maximum_depth": 10, "integrator": {"DebugView": {"mode": {"TraversalCost": {"maximum": 100}}}}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            c.to_configuration(0).unwrap().integrator,
+            integrator::IntegratorType::DebugView {
+                mode: integrator::DebugMode::TraversalCost { maximum: 100 }
+            }
+        );
     }
 }
@@ -6,6 +6,7 @@ use ray_tracing_core::core;
 use ray_tracing_core::environment;
 use ray_tracing_core::geometry;
 use ray_tracing_core::material;
+use ray_tracing_core::random;
 use ray_tracing_core::texture;
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
@@ -28,6 +29,10 @@ pub struct Scene {
     #[serde(default = "Scene::default_light_node")]
     pub light_node_id: usize,
 
+    /// Additional lights, which are sampled explicitly
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub light_node_ids: Vec<usize>,
+
     pub objects: Vec<RayTracingObject>,
 }
 
@@ -74,6 +79,9 @@ impl Scene {
             collection: None,
         };
         s.world.accept(&mut sh)?;
+        for light in s.lights.lights.iter() {
+            light.accept(&mut sh)?;
+        }
         drop(sh);
         //let objects = object_map.take().into_iter().map(|(_id, obj)| obj).collect();
         let mut tuples: Vec<(usize, RayTracingObject)> = object_map.take().into_iter().collect();
@@ -85,6 +93,7 @@ impl Scene {
             sky_id: s.sky.get_id(),
             configuration_id: s.configuration.id,
             light_node_id: 0,
+            light_node_ids: s.lights.lights.iter().map(|l| l.get_id()).collect(),
             objects: objects,
         })
     }
@@ -140,9 +149,26 @@ impl Scene {
         }
     }
 
+    /// Creates the scene. If the configuration of the scene has a seed, the random numbers,
+    /// which are used during construction (e.g. noise textures and BVH splits), are generated deterministically.
     pub fn to_scene_with_options(
         &self,
         deserialize_options: &DeserializeOptions,
+    ) -> Result<core::Scene, Box<dyn Error>> {
+        let seed = self.objects.iter().find_map(|obj| match obj {
+            RayTracingObject::Configuration(c)
+                if c.id.get_range().contains(&self.configuration_id) =>
+            {
+                c.seed
+            }
+            _ => None,
+        });
+        random::with_seed(seed, || self.create_scene(deserialize_options))
+    }
+
+    fn create_scene(
+        &self,
+        deserialize_options: &DeserializeOptions,
     ) -> Result<core::Scene, Box<dyn Error>> {
         let mut configuration_map = HashMap::<usize, core::Configuration>::default();
         let mut camera_map = HashMap::<usize, Arc<core::Camera>>::default();
@@ -366,6 +392,27 @@ impl Scene {
                         )
                     })
                 }
+                RayTracingObject::Triangle(h) => {
+                    Scene::insert_geometry(&mut object_map, &h.id, h, |h, i, _| {
+                        Arc::new(
+                            h.to_shape(i, Scene::get_material(&material_map, &h.material, i))
+                                .unwrap(),
+                        )
+                    })
+                }
+                RayTracingObject::TriangleMesh(h) => {
+                    Scene::insert_geometry(&mut object_map, &h.id, h, |h, i, _| {
+                        Arc::new(
+                            h.to_shape(i, Scene::get_material(&material_map, &h.material, i))
+                                .unwrap(),
+                        )
+                    })
+                }
+                RayTracingObject::ObjFile(h) => {
+                    Scene::insert_geometry(&mut object_map, &h.id, h, |h, i, _| {
+                        h.to_geometry(i, &deserialize_options.root_path).unwrap()
+                    })
+                }
                 RayTracingObject::ConstantMedium(h) => {
                     Scene::insert_geometry(&mut object_map, &h.id, h, |h, i, om| {
                         Arc::new(
@@ -418,24 +465,43 @@ impl Scene {
                         )
                     })
                 }
+                RayTracingObject::Transform(h) => {
+                    Scene::insert_geometry(&mut object_map, &h.id, h, |h, i, om| {
+                        Arc::new(
+                            h.to_geometry(i, Scene::get_geometry(om, &h.node, i))
+                                .unwrap(),
+                        )
+                    })
+                }
+                RayTracingObject::CSGNode(h) => {
+                    Scene::insert_geometry(&mut object_map, &h.id, h, |h, i, om| {
+                        Arc::new(
+                            h.to_geometry(
+                                i,
+                                Scene::get_geometry(om, &h.left, i),
+                                Scene::get_geometry(om, &h.right, i),
+                            )
+                            .unwrap(),
+                        )
+                    })
+                }
                 _ => (),
             };
         }
-        let light = if self.light_node_id > 0 {
-            Some(Scene::get_geometry(
-                &object_map,
-                &IdReference::Single(self.light_node_id),
-                0,
-            ))
-        } else {
-            None
-        };
+        let mut light_ids = self.light_node_ids.clone();
+        if self.light_node_id > 0 {
+            light_ids.insert(0, self.light_node_id);
+        }
+        let lights = light_ids
+            .iter()
+            .map(|id| Scene::get_geometry(&object_map, &IdReference::Single(*id), 0))
+            .collect();
         Ok(core::Scene::new(
             Scene::get_configuration(&configuration_map, &self.configuration_id),
             Scene::get_camera(&camera_map, &self.camera_id),
             Scene::get_environment(&environment_map, &self.sky_id),
             Scene::get_geometry(&object_map, &IdReference::Single(self.root_node_id), 0),
-            light,
+            lights,
         ))
     }
 
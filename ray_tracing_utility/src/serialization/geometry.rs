@@ -20,6 +20,12 @@ pub mod volume;
 /// Implementation of hit able instancing objects
 pub mod instancing;
 
+/// Serialization of geometry imported from files
+pub mod obj_file;
+
+/// Serialization of constructive solid geometry
+pub mod csg;
+
 pub struct SerializeGeometry {
     pub object_map: Rc<RefCell<HashMap<usize, RayTracingObject>>>,
     pub collection: Option<(usize, collection::Collection)>,
@@ -103,6 +109,11 @@ impl geometry::Visitor for SerializeGeometry {
         n: &geometry::collection::BVHNode,
     ) -> Result<(), Box<dyn Error>> {
         self.create_collection(n.id)?;
+        if let Some(ref mut c) = self.collection {
+            if c.0 == n.id {
+                c.1.bvh_builder = collection::BVHBuilder::from_builder(n.builder);
+            }
+        }
         self.add_node(n.left.clone())?;
         self.add_node(n.right.clone())?;
         Ok(())
@@ -117,6 +128,22 @@ impl geometry::Visitor for SerializeGeometry {
         Ok(())
     }
 
+    fn visit_collection_linear_bvh(
+        &mut self,
+        l: &geometry::collection::LinearBVH,
+    ) -> Result<(), Box<dyn Error>> {
+        if !self.object_map.borrow().contains_key(&l.id) {
+            for n in l.primitives.iter().chain(l.unbounded.iter()) {
+                self.add_node(n.clone())?;
+            }
+            self.object_map.borrow_mut().insert(
+                l.id,
+                RayTracingObject::Collection(collection::Collection::from_linear_bvh(l)?),
+            );
+        };
+        Ok(())
+    }
+
     fn visit_shape_sphere(&mut self, s: &geometry::shape::Sphere) -> Result<(), Box<dyn Error>> {
         self.add_to_collection(s.id)?;
         if !self.object_map.borrow().contains_key(&s.id) {
@@ -192,6 +219,36 @@ impl geometry::Visitor for SerializeGeometry {
         Ok(())
     }
 
+    fn visit_shape_triangle(
+        &mut self,
+        t: &geometry::shape::Triangle,
+    ) -> Result<(), Box<dyn Error>> {
+        self.add_to_collection(t.id)?;
+        if !self.object_map.borrow().contains_key(&t.id) {
+            self.add_material(t.material.clone())?;
+            self.object_map.borrow_mut().insert(
+                t.id,
+                RayTracingObject::Triangle(shape::Triangle::from_shape(t)?),
+            );
+        };
+        Ok(())
+    }
+
+    fn visit_shape_triangle_mesh(
+        &mut self,
+        m: &geometry::shape::TriangleMesh,
+    ) -> Result<(), Box<dyn Error>> {
+        self.add_to_collection(m.id)?;
+        if !self.object_map.borrow().contains_key(&m.id) {
+            self.add_material(m.material.clone())?;
+            self.object_map.borrow_mut().insert(
+                m.id,
+                RayTracingObject::TriangleMesh(shape::TriangleMesh::from_shape(m)?),
+            );
+        };
+        Ok(())
+    }
+
     fn visit_volume_constant_medium(
         &mut self,
         v: &geometry::volume::ConstantMedium,
@@ -282,6 +339,34 @@ impl geometry::Visitor for SerializeGeometry {
         };
         Ok(())
     }
+
+    fn visit_instancing_transform(
+        &mut self,
+        i: &geometry::instancing::Transform,
+    ) -> Result<(), Box<dyn Error>> {
+        self.add_to_collection(i.id)?;
+        if !self.object_map.borrow().contains_key(&i.id) {
+            self.add_geometry(i.node.clone())?;
+            self.object_map.borrow_mut().insert(
+                i.id,
+                RayTracingObject::Transform(instancing::Transform::from_geometry(i)?),
+            );
+        };
+        Ok(())
+    }
+
+    fn visit_csg_node(&mut self, n: &geometry::csg::CSGNode) -> Result<(), Box<dyn Error>> {
+        self.add_to_collection(n.id)?;
+        if !self.object_map.borrow().contains_key(&n.id) {
+            self.add_geometry(n.left.clone())?;
+            self.add_geometry(n.right.clone())?;
+            self.object_map.borrow_mut().insert(
+                n.id,
+                RayTracingObject::CSGNode(csg::CSGNode::from_geometry(n)?),
+            );
+        };
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,6 @@
 use crate::serialization::{IdConstructor, IdReference};
 use ray_tracing_core::geometry::collection;
-use ray_tracing_core::geometry::collection::{BVHNode, GeometryList};
+use ray_tracing_core::geometry::collection::{BVHNode, GeometryList, LinearBVH};
 use ray_tracing_core::geometry::Geometry;
 use ray_tracing_core::types::FSize;
 use serde::{Deserialize, Serialize};
@@ -8,13 +8,46 @@ use std::error::Error;
 use std::ops::Range;
 use std::sync::Arc;
 
+/// Construction strategy of the bounding volume hierarchy
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum BVHBuilder {
+    RandomMedian,
+    SAH,
+}
+
+impl BVHBuilder {
+    pub fn from_builder(b: collection::BVHBuilder) -> BVHBuilder {
+        match b {
+            collection::BVHBuilder::RandomMedian => BVHBuilder::RandomMedian,
+            collection::BVHBuilder::SAH => BVHBuilder::SAH,
+        }
+    }
+
+    pub fn to_builder(&self) -> collection::BVHBuilder {
+        match self {
+            BVHBuilder::RandomMedian => collection::BVHBuilder::RandomMedian,
+            BVHBuilder::SAH => collection::BVHBuilder::SAH,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct Collection {
     pub id: IdConstructor,
 
+    /// If set, the objects are stored in a plain list, otherwise a bounding volume hierarchy is built.
+    /// Ignored if `linear_bvh` is set.
     #[serde(default = "Collection::default_bvh_tree")]
     pub bvh_tree: bool,
 
+    #[serde(default = "Collection::default_bvh_builder")]
+    pub bvh_builder: BVHBuilder,
+
+    /// Flatten the hierarchy to a linear array of nodes, which is built with the surface area heuristic.
+    /// If set, `bvh_tree` and `bvh_builder` are ignored.
+    #[serde(default)]
+    pub linear_bvh: bool,
+
     pub object_id_list: Vec<IdReference>,
 }
 
@@ -23,10 +56,16 @@ impl Collection {
         true
     }
 
+    fn default_bvh_builder() -> BVHBuilder {
+        BVHBuilder::RandomMedian
+    }
+
     pub fn new(id: usize) -> Result<Collection, Box<dyn Error>> {
         Ok(Collection {
             id: IdConstructor::Single(id),
             bvh_tree: true,
+            bvh_builder: Collection::default_bvh_builder(),
+            linear_bvh: false,
             object_id_list: Vec::default(),
         })
     }
@@ -35,6 +74,8 @@ impl Collection {
         Ok(Collection {
             id: IdConstructor::Single(l.id),
             bvh_tree: false,
+            bvh_builder: Collection::default_bvh_builder(),
+            linear_bvh: false,
             object_id_list: l
                 .list
                 .iter()
@@ -43,6 +84,21 @@ impl Collection {
         })
     }
 
+    pub fn from_linear_bvh(l: &collection::LinearBVH) -> Result<Collection, Box<dyn Error>> {
+        Ok(Collection {
+            id: IdConstructor::Single(l.id),
+            bvh_tree: false,
+            bvh_builder: BVHBuilder::SAH,
+            linear_bvh: true,
+            object_id_list: l
+                .primitives
+                .iter()
+                .chain(l.unbounded.iter())
+                .map(|h| IdReference::Single(h.get_id()))
+                .collect(),
+        })
+    }
+
     pub fn add(&mut self, h: Arc<dyn Geometry>) -> Result<(), Box<dyn Error>> {
         self.object_id_list.push(IdReference::Single(h.get_id()));
         Ok(())
@@ -53,10 +109,12 @@ impl Collection {
         list: &Vec<Arc<dyn Geometry>>,
         t_range: Range<FSize>,
     ) -> Result<Arc<dyn Geometry>, Box<dyn Error>> {
-        if self.bvh_tree {
-            Ok(Arc::new(GeometryList::new(list)))
+        if self.linear_bvh {
+            self.to_linear_bvh(list, t_range)
+        } else if self.bvh_tree {
+            self.to_list(list)
         } else {
-            Ok(BVHNode::new(list, t_range))
+            self.to_bvh_tree(list, t_range)
         }
     }
 
@@ -72,16 +130,68 @@ impl Collection {
         list: &Vec<Arc<dyn Geometry>>,
         t_range: Range<FSize>,
     ) -> Result<Arc<dyn Geometry>, Box<dyn Error>> {
-        Ok(BVHNode::new(list, t_range))
+        Ok(BVHNode::new_id_with_builder(
+            self.id.get_id(0),
+            self.bvh_builder.to_builder(),
+            list,
+            t_range,
+        ))
+    }
+
+    pub fn to_linear_bvh(
+        &self,
+        list: &Vec<Arc<dyn Geometry>>,
+        t_range: Range<FSize>,
+    ) -> Result<Arc<dyn Geometry>, Box<dyn Error>> {
+        Ok(Arc::new(LinearBVH::new_id(
+            self.id.get_id(0),
+            list,
+            t_range,
+        )))
     }
 }
 
 #[cfg(test)]
 mod collection_test {
     use super::*;
+    use crate::serialization::core::Scene;
+    use crate::serialization::geometry::SerializeGeometry;
+    use crate::serialization::RayTracingObject;
     use ray_tracing_core::geometry::shape;
     use ray_tracing_core::material;
     use ray_tracing_core::types::Point3;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::fs;
+    use std::rc::Rc;
+
+    /// Serializes the collection `g`, to inspect the kind of collection, which was built
+    fn serialize_collection(g: &Arc<dyn Geometry>) -> Collection {
+        let object_map = Rc::new(RefCell::new(HashMap::default()));
+        g.accept(&mut SerializeGeometry {
+            object_map: object_map.clone(),
+            collection: None,
+        })
+        .unwrap();
+        let collection = match object_map.borrow().get(&g.get_id()) {
+            Some(RayTracingObject::Collection(c)) => c.clone(),
+            _ => panic!("{} isn't a collection", g.get_id()),
+        };
+        collection
+    }
+
+    fn sphere_list(count: usize) -> Vec<Arc<dyn Geometry>> {
+        let m = Arc::new(material::NoMaterial::new());
+        (0..count)
+            .map(|i| -> Arc<dyn Geometry> {
+                Arc::new(shape::Sphere::new(
+                    Point3::new(i as FSize, 0.0, 0.0),
+                    0.5,
+                    m.clone(),
+                ))
+            })
+            .collect()
+    }
 
     #[test]
     fn collection_test_form_list() {
@@ -105,9 +215,87 @@ mod collection_test {
         assert_eq!(c.object_id_list[1], IdReference::Single(s2_id));
     }
 
-    // TODO to_list test
+    #[test]
+    fn collection_test_to_collection() {
+        let m = Arc::new(material::NoMaterial::new());
+        let list: Vec<Arc<dyn Geometry>> = vec![
+            Arc::new(shape::Sphere::new(
+                Point3::new(-1.0, 0.0, 0.0),
+                1.0,
+                m.clone(),
+            )),
+            Arc::new(shape::Sphere::new(Point3::new(1.0, 0.0, 0.0), 1.0, m)),
+        ];
+        let c: Collection = serde_json::from_str(
+            r#"{ "id": 5, "bvh_tree": false, "bvh_builder": "SAH", "object_id_list": [1, 2] }"#,
+        )
+        .unwrap();
+        assert!(!c.bvh_tree);
+        assert_eq!(c.bvh_builder, BVHBuilder::SAH);
+        let g = c.to_collection(&list, 0.0..0.0).unwrap();
+        assert_eq!(g.get_id(), 5);
+        let c: Collection =
+            serde_json::from_str(r#"{ "id": 6, "object_id_list": [1, 2] }"#).unwrap();
+        assert!(c.bvh_tree);
+        assert_eq!(c.bvh_builder, BVHBuilder::RandomMedian);
+        assert!(c.to_collection(&list, 0.0..0.0).is_ok());
+    }
 
-    // TODO to_bvh_tree test
+    #[test]
+    fn collection_test_linear_bvh() {
+        let m = Arc::new(material::NoMaterial::new());
+        let list: Vec<Arc<dyn Geometry>> = vec![
+            Arc::new(shape::Sphere::new(
+                Point3::new(-1.0, 0.0, 0.0),
+                1.0,
+                m.clone(),
+            )),
+            Arc::new(shape::Sphere::new(Point3::new(1.0, 0.0, 0.0), 1.0, m)),
+        ];
+        let c: Collection = serde_json::from_str(
+            r#"{ "id": 7, "bvh_tree": false, "linear_bvh": true, "object_id_list": [1, 2] }"#,
+        )
+        .unwrap();
+        assert!(c.linear_bvh);
+        let g = c.to_collection(&list, 0.0..0.0).unwrap();
+        assert_eq!(g.get_id(), 7);
+        assert!(serialize_collection(&g).linear_bvh);
+        // `linear_bvh` takes precedence over `bvh_tree`
+        for json in [
+            r#"{ "id": 8, "linear_bvh": true, "object_id_list": [1, 2] }"#,
+            r#"{ "id": 9, "bvh_tree": true, "linear_bvh": true, "object_id_list": [1, 2] }"#,
+        ] {
+            let c: Collection = serde_json::from_str(json).unwrap();
+            assert!(c.bvh_tree);
+            let g = c.to_collection(&list, 0.0..0.0).unwrap();
+            assert!(serialize_collection(&g).linear_bvh);
+        }
+        let l = LinearBVH::new(&list, 0.0..0.0);
+        let c = Collection::from_linear_bvh(&l).unwrap();
+        assert!(!c.bvh_tree && c.linear_bvh);
+        assert_eq!(c.object_id_list.len(), 2);
+    }
+
+    #[test]
+    fn collection_test_spheres_scene() {
+        let scene: Scene =
+            serde_json::from_str(&fs::read_to_string("../scene/Spheres.json").unwrap()).unwrap();
+        let c = scene
+            .objects
+            .iter()
+            .find_map(|obj| match obj {
+                RayTracingObject::Collection(c) if c.id.get_id(0) == 1001 => Some(c),
+                _ => None,
+            })
+            .unwrap();
+        let g = c
+            .to_collection(&sphere_list(c.object_id_list.len()), 0.0..0.0)
+            .unwrap();
+        // only a bounding volume hierarchy serializes its builder
+        let c = serialize_collection(&g);
+        assert!(!c.linear_bvh);
+        assert_eq!(c.bvh_builder, BVHBuilder::SAH);
+    }
 
     // TODO test
 }
@@ -0,0 +1,103 @@
+use crate::serialization::{IdConstructor, IdReference};
+use ray_tracing_core::geometry::csg;
+use ray_tracing_core::geometry::Geometry;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum CSGOperation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl CSGOperation {
+    pub fn from_operation(o: csg::CSGOperation) -> CSGOperation {
+        match o {
+            csg::CSGOperation::Union => CSGOperation::Union,
+            csg::CSGOperation::Intersection => CSGOperation::Intersection,
+            csg::CSGOperation::Difference => CSGOperation::Difference,
+        }
+    }
+
+    pub fn to_operation(&self) -> csg::CSGOperation {
+        match self {
+            CSGOperation::Union => csg::CSGOperation::Union,
+            CSGOperation::Intersection => csg::CSGOperation::Intersection,
+            CSGOperation::Difference => csg::CSGOperation::Difference,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CSGNode {
+    pub id: IdConstructor,
+    pub operation: CSGOperation,
+    pub left: IdReference,
+    pub right: IdReference,
+}
+
+impl CSGNode {
+    pub fn from_geometry(n: &csg::CSGNode) -> Result<CSGNode, Box<dyn Error>> {
+        Ok(CSGNode {
+            id: IdConstructor::Single(n.id),
+            operation: CSGOperation::from_operation(n.operation),
+            left: IdReference::Single(n.left.get_id()),
+            right: IdReference::Single(n.right.get_id()),
+        })
+    }
+
+    pub fn to_geometry(
+        &self,
+        index: usize,
+        left: Arc<dyn Geometry>,
+        right: Arc<dyn Geometry>,
+    ) -> Result<csg::CSGNode, Box<dyn Error>> {
+        Ok(csg::CSGNode::new_id(
+            self.id.get_id(index),
+            self.operation.to_operation(),
+            left,
+            right,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod csg_test {
+    use super::*;
+    use ray_tracing_core::geometry::shape;
+    use ray_tracing_core::material;
+    use ray_tracing_core::types::Point3;
+
+    fn sphere() -> Arc<dyn Geometry> {
+        Arc::new(shape::Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            1.0,
+            Arc::new(material::NoMaterial::new()),
+        ))
+    }
+
+    #[test]
+    fn csg_test_from_csg_node() {
+        let l = sphere();
+        let r = sphere();
+        let (l_id, r_id) = (l.get_id(), r.get_id());
+        let n = csg::CSGNode::new(csg::CSGOperation::Difference, l, r);
+        let c = CSGNode::from_geometry(&n).unwrap();
+        assert_eq!(c.operation, CSGOperation::Difference);
+        assert_eq!(c.left, IdReference::Single(l_id));
+        assert_eq!(c.right, IdReference::Single(r_id));
+    }
+
+    #[test]
+    fn csg_test_to_csg_node() {
+        let c: CSGNode = serde_json::from_str(
+            r#"{ "id": 3, "operation": "Intersection", "left": 1, "right": 2 }"#,
+        )
+        .unwrap();
+        let n = c.to_geometry(0, sphere(), sphere()).unwrap();
+        assert_eq!(n.id, 3);
+        assert_eq!(n.operation, csg::CSGOperation::Intersection);
+    }
+}
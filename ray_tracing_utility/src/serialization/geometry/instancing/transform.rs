@@ -0,0 +1,192 @@
+use crate::serialization::{IdConstructor, IdReference, Value};
+use ray_tracing_core::geometry::instancing;
+use ray_tracing_core::geometry::Geometry;
+use ray_tracing_core::types::{FSize, Matrix4, Vector3, Vector4};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::sync::Arc;
+
+/// Rotation by `angle` degrees around `axis`
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Rotation {
+    pub axis: Value,
+    pub angle: Value,
+}
+
+/// Transformation by a raw matrix or by a translation, rotation and scale description
+///
+/// The `matrix` is specified as 4 rows. Alternatively `translate`, `rotate` and `scale`
+/// can be specified. The geometry is scaled first, then rotated and finally translated.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Transform {
+    pub id: IdConstructor,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matrix: Option<[[FSize; 4]; 4]>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translate: Option<Value>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotate: Option<Rotation>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<Value>,
+
+    pub node: IdReference,
+}
+
+impl Transform {
+    pub fn from_geometry(i: &instancing::Transform) -> Result<Transform, Box<dyn Error>> {
+        let mut matrix = [[0.0; 4]; 4];
+        for (row, m_row) in matrix.iter_mut().enumerate() {
+            for (column, value) in m_row.iter_mut().enumerate() {
+                *value = i.matrix[column][row];
+            }
+        }
+        Ok(Transform {
+            id: IdConstructor::Single(i.id),
+            matrix: Some(matrix),
+            translate: None,
+            rotate: None,
+            scale: None,
+            node: IdReference::Single(i.node.get_id()),
+        })
+    }
+
+    pub fn to_matrix(&self) -> Result<Matrix4, Box<dyn Error>> {
+        match self.matrix {
+            Some(m) => {
+                if self.translate.is_some() || self.rotate.is_some() || self.scale.is_some() {
+                    return Err(
+                        "a transformation is either a matrix or translate, rotate and scale".into(),
+                    );
+                }
+                let column = |c: usize| Vector4::new(m[0][c], m[1][c], m[2][c], m[3][c]);
+                Ok(Matrix4::new(column(0), column(1), column(2), column(3)))
+            }
+            None => {
+                let translation = match &self.translate {
+                    Some(t) => t.to_vector3()?,
+                    None => Vector3::new(0.0, 0.0, 0.0),
+                };
+                let (axis, angle) = match &self.rotate {
+                    Some(r) => (r.axis.to_vector3()?, r.angle.to_value()?.to_radians()),
+                    None => (Vector3::new(0.0, 1.0, 0.0), 0.0),
+                };
+                let scale = match &self.scale {
+                    Some(s) => s.to_vector3()?,
+                    None => Vector3::new(1.0, 1.0, 1.0),
+                };
+                Ok(instancing::Transform::trs_matrix(
+                    translation,
+                    axis,
+                    angle,
+                    scale,
+                ))
+            }
+        }
+    }
+
+    pub fn to_geometry(
+        &self,
+        index: usize,
+        node: Arc<dyn Geometry>,
+    ) -> Result<instancing::Transform, Box<dyn Error>> {
+        instancing::Transform::new_id(self.id.get_id(index), self.to_matrix()?, node)
+    }
+}
+
+#[cfg(test)]
+mod transform_test {
+    use super::*;
+    use ray_tracing_core::geometry::shape;
+    use ray_tracing_core::material;
+    use ray_tracing_core::test;
+    use ray_tracing_core::types::Point3;
+
+    fn sphere() -> Arc<dyn Geometry> {
+        Arc::new(shape::Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            1.0,
+            Arc::new(material::NoMaterial::new()),
+        ))
+    }
+
+    #[test]
+    fn transform_test_from_transform() {
+        let s = sphere();
+        let s_id = s.get_id();
+        let m = instancing::Transform::trs_matrix(
+            Vector3::new(1.0, 2.0, 3.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            0.0,
+            Vector3::new(2.0, 2.0, 2.0),
+        );
+        let i = instancing::Transform::new(m, s).unwrap();
+        let n = Transform::from_geometry(&i).unwrap();
+        assert_eq!(
+            n.matrix,
+            Some([
+                [2.0, 0.0, 0.0, 1.0],
+                [0.0, 2.0, 0.0, 2.0],
+                [0.0, 0.0, 2.0, 3.0],
+                [0.0, 0.0, 0.0, 1.0]
+            ])
+        );
+        assert_eq!(n.node, IdReference::Single(s_id));
+    }
+
+    #[test]
+    fn transform_test_to_transform_matrix() {
+        let t = Transform {
+            id: IdConstructor::Single(0),
+            matrix: Some([
+                [2.0, 0.0, 0.0, 1.0],
+                [0.0, 2.0, 0.0, 2.0],
+                [0.0, 0.0, 2.0, 3.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ]),
+            translate: None,
+            rotate: None,
+            scale: None,
+            node: IdReference::Single(1),
+        };
+        let i = t.to_geometry(0, sphere()).unwrap();
+        test::assert_eq_vector4(&i.matrix[3], &Vector4::new(1.0, 2.0, 3.0, 1.0), 0.001);
+        test::assert_eq_vector4(&i.inverse[0], &Vector4::new(0.5, 0.0, 0.0, 0.0), 0.001);
+    }
+
+    #[test]
+    fn transform_test_to_transform_trs() {
+        let t = Transform {
+            id: IdConstructor::Single(0),
+            matrix: None,
+            translate: Some(Value::Vector3((1.0, 2.0, 3.0))),
+            rotate: Some(Rotation {
+                axis: Value::Vector3((0.0, 0.0, 1.0)),
+                angle: Value::Scalar(90.0),
+            }),
+            scale: Some(Value::Scalar(2.0)),
+            node: IdReference::Single(1),
+        };
+        let i = t.to_geometry(0, sphere()).unwrap();
+        test::assert_eq_vector4(&i.matrix[0], &Vector4::new(0.0, 2.0, 0.0, 0.0), 0.001);
+        test::assert_eq_vector4(&i.matrix[1], &Vector4::new(-2.0, 0.0, 0.0, 0.0), 0.001);
+        test::assert_eq_vector4(&i.matrix[2], &Vector4::new(0.0, 0.0, 2.0, 0.0), 0.001);
+        test::assert_eq_vector4(&i.matrix[3], &Vector4::new(1.0, 2.0, 3.0, 1.0), 0.001);
+    }
+
+    #[test]
+    fn transform_test_not_invertible() {
+        let t = Transform {
+            id: IdConstructor::Single(0),
+            matrix: None,
+            translate: None,
+            rotate: None,
+            scale: Some(Value::Vector3((1.0, 0.0, 1.0))),
+            node: IdReference::Single(1),
+        };
+        assert!(t.to_geometry(0, sphere()).is_err());
+    }
+}
@@ -0,0 +1,61 @@
+use crate::obj;
+use crate::serialization::IdConstructor;
+use ray_tracing_core::geometry::Geometry;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Geometry and materials of a Wavefront `.obj` file
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ObjFile {
+    pub id: IdConstructor,
+    pub filename: String,
+}
+
+impl ObjFile {
+    pub fn to_geometry(
+        &self,
+        index: usize,
+        root_path: &Option<String>,
+    ) -> Result<Arc<dyn Geometry>, Box<dyn Error>> {
+        let filename = match root_path {
+            Some(root_path) => {
+                let path = Path::new(&self.filename);
+                if !path.is_absolute() {
+                    String::from(Path::new(&root_path).join(path).to_str().unwrap())
+                } else {
+                    self.filename.clone()
+                }
+            }
+            None => self.filename.clone(),
+        };
+        obj::load_obj(self.id.get_id(index), &filename)
+    }
+}
+
+#[cfg(test)]
+mod obj_file_test {
+    use super::*;
+    use ray_tracing_core::math::Ray;
+    use ray_tracing_core::test;
+    use ray_tracing_core::types::Vector3;
+
+    #[test]
+    fn obj_file_to_geometry() {
+        let of = ObjFile {
+            id: IdConstructor::Single(3),
+            filename: "model/triangle.obj".to_string(),
+        };
+        let g = match of.to_geometry(0, &Some("../resource".to_string())) {
+            Ok(g) => g,
+            Err(e) => panic!("read file error {}", e),
+        };
+        assert_eq!(g.get_id(), 3);
+        let ray = Ray::new_ray(Vector3::new(0.25, 0.25, 1.0), Vector3::new(0.0, 0.0, -1.0));
+        match g.hit(&ray, 0.0..10.0) {
+            Some(hit_record) => test::assert_eq_float(hit_record.t, 1.0, 0.001),
+            None => panic!("no result"),
+        }
+    }
+}
@@ -15,3 +15,9 @@ pub use self::yz_rect::YZRect;
 
 mod cuboid;
 pub use self::cuboid::Cuboid;
+
+mod triangle;
+pub use self::triangle::Triangle;
+
+mod triangle_mesh;
+pub use self::triangle_mesh::TriangleMesh;
@@ -0,0 +1,87 @@
+use crate::serialization::{IdConstructor, IdReference, Value};
+use ray_tracing_core::geometry::shape;
+use ray_tracing_core::material::Material;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Triangle {
+    pub id: IdConstructor,
+    pub v0: Value,
+    pub v1: Value,
+    pub v2: Value,
+    pub material: IdReference,
+}
+
+impl Triangle {
+    pub fn from_shape(t: &shape::Triangle) -> Result<Triangle, Box<dyn Error>> {
+        Ok(Triangle {
+            id: IdConstructor::Single(t.id),
+            v0: Value::from_point3(t.vertices[0])?,
+            v1: Value::from_point3(t.vertices[1])?,
+            v2: Value::from_point3(t.vertices[2])?,
+            material: IdReference::Single(t.material.get_id()),
+        })
+    }
+
+    pub fn to_shape(
+        &self,
+        index: usize,
+        material: Arc<dyn Material>,
+    ) -> Result<shape::Triangle, Box<dyn Error>> {
+        Ok(shape::Triangle::new_id(
+            self.id.get_id(index),
+            [
+                self.v0.to_point3()?,
+                self.v1.to_point3()?,
+                self.v2.to_point3()?,
+            ],
+            material,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod triangle_test {
+    use super::*;
+    use ray_tracing_core::material;
+    use ray_tracing_core::test;
+    use ray_tracing_core::types::Point3;
+
+    #[test]
+    fn triangle_test_from_triangle() {
+        let m = Arc::new(material::NoMaterial::new());
+        let m_id = m.id;
+        let ts = shape::Triangle::new(
+            [
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+            ],
+            m.clone(),
+        );
+        let t = Triangle::from_shape(&ts).unwrap();
+        assert_eq!(t.v0, Value::Vector3((0.0, 0.0, 0.0)));
+        assert_eq!(t.v1, Value::Vector3((1.0, 0.0, 0.0)));
+        assert_eq!(t.v2, Value::Vector3((0.0, 1.0, 0.0)));
+        assert_eq!(t.material, IdReference::Single(m_id));
+    }
+
+    #[test]
+    fn triangle_test_to_triangle() {
+        let t = Triangle {
+            id: IdConstructor::Single(0),
+            v0: Value::Vector3((0.0, 0.0, 0.0)),
+            v1: Value::Vector3((1.0, 0.0, 0.0)),
+            v2: Value::Vector3((0.0, 1.0, 0.0)),
+            material: IdReference::Single(1),
+        };
+        let ts = t
+            .to_shape(0, Arc::new(material::NoMaterial::new()))
+            .unwrap();
+        test::assert_eq_vector3(&ts.vertices[0], &Point3::new(0.0, 0.0, 0.0), 0.001);
+        test::assert_eq_vector3(&ts.vertices[1], &Point3::new(1.0, 0.0, 0.0), 0.001);
+        test::assert_eq_vector3(&ts.vertices[2], &Point3::new(0.0, 1.0, 0.0), 0.001);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::serialization::{IdConstructor, IdReference};
+use ray_tracing_core::geometry::shape;
+use ray_tracing_core::material::Material;
+use ray_tracing_core::types::{FSize, Point3, Vector2, Vector3};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TriangleMesh {
+    pub id: IdConstructor,
+    pub vertices: Vec<(FSize, FSize, FSize)>,
+
+    #[serde(default)]
+    pub normals: Vec<(FSize, FSize, FSize)>,
+
+    #[serde(default)]
+    pub uvs: Vec<(FSize, FSize)>,
+
+    pub indices: Vec<(usize, usize, usize)>,
+    pub material: IdReference,
+}
+
+impl TriangleMesh {
+    pub fn from_shape(m: &shape::TriangleMesh) -> Result<TriangleMesh, Box<dyn Error>> {
+        Ok(TriangleMesh {
+            id: IdConstructor::Single(m.id),
+            vertices: m.vertices.iter().map(|v| (v.x, v.y, v.z)).collect(),
+            normals: m.normals.iter().map(|n| (n.x, n.y, n.z)).collect(),
+            uvs: m.uvs.iter().map(|uv| (uv.x, uv.y)).collect(),
+            indices: m.indices.iter().map(|i| (i[0], i[1], i[2])).collect(),
+            material: IdReference::Single(m.material.get_id()),
+        })
+    }
+
+    pub fn to_shape(
+        &self,
+        index: usize,
+        material: Arc<dyn Material>,
+    ) -> Result<shape::TriangleMesh, Box<dyn Error>> {
+        if !self.normals.is_empty() && self.normals.len() != self.vertices.len() {
+            return Err("number of normal vectors does not match the number of vertices".into());
+        }
+        if !self.uvs.is_empty() && self.uvs.len() != self.vertices.len() {
+            return Err(
+                "number of texture coordinates does not match the number of vertices".into(),
+            );
+        }
+        if self
+            .indices
+            .iter()
+            .any(|i| i.0.max(i.1).max(i.2) >= self.vertices.len())
+        {
+            return Err("triangle mesh index out of range".into());
+        }
+        Ok(shape::TriangleMesh::new_id(
+            self.id.get_id(index),
+            self.vertices
+                .iter()
+                .map(|v| Point3::new(v.0, v.1, v.2))
+                .collect(),
+            self.normals
+                .iter()
+                .map(|n| Vector3::new(n.0, n.1, n.2))
+                .collect(),
+            self.uvs.iter().map(|uv| Vector2::new(uv.0, uv.1)).collect(),
+            self.indices.iter().map(|i| [i.0, i.1, i.2]).collect(),
+            material,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod triangle_mesh_test {
+    use super::*;
+    use ray_tracing_core::material;
+    use ray_tracing_core::test;
+
+    #[test]
+    fn triangle_mesh_test_from_triangle_mesh() {
+        let m = Arc::new(material::NoMaterial::new());
+        let m_id = m.id;
+        let ms = shape::TriangleMesh::new(
+            vec![
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+            ],
+            Vec::default(),
+            vec![
+                Vector2::new(0.0, 0.0),
+                Vector2::new(1.0, 0.0),
+                Vector2::new(0.0, 1.0),
+            ],
+            vec![[0, 1, 2]],
+            m.clone(),
+        );
+        let t = TriangleMesh::from_shape(&ms).unwrap();
+        assert_eq!(t.vertices.len(), 3);
+        assert_eq!(t.vertices[1], (1.0, 0.0, 0.0));
+        assert_eq!(t.normals.len(), 0);
+        assert_eq!(t.uvs[2], (0.0, 1.0));
+        assert_eq!(t.indices, vec![(0, 1, 2)]);
+        assert_eq!(t.material, IdReference::Single(m_id));
+    }
+
+    #[test]
+    fn triangle_mesh_test_to_triangle_mesh() {
+        let t = TriangleMesh {
+            id: IdConstructor::Single(0),
+            vertices: vec![(0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.0)],
+            normals: vec![(0.0, 0.0, 1.0), (0.0, 0.0, 1.0), (0.0, 0.0, 1.0)],
+            uvs: Vec::default(),
+            indices: vec![(0, 1, 2)],
+            material: IdReference::Single(1),
+        };
+        let ms = t
+            .to_shape(0, Arc::new(material::NoMaterial::new()))
+            .unwrap();
+        assert_eq!(ms.len(), 1);
+        test::assert_eq_vector3(&ms.vertices[2], &Point3::new(0.0, 1.0, 0.0), 0.001);
+        test::assert_eq_vector3(&ms.normals[0], &Vector3::new(0.0, 0.0, 1.0), 0.001);
+    }
+
+    #[test]
+    fn triangle_mesh_test_index_out_of_range() {
+        let t = TriangleMesh {
+            id: IdConstructor::Single(0),
+            vertices: vec![(0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.0)],
+            normals: Vec::default(),
+            uvs: Vec::default(),
+            indices: vec![(0, 1, 3)],
+            material: IdReference::Single(1),
+        };
+        assert!(t
+            .to_shape(0, Arc::new(material::NoMaterial::new()))
+            .is_err());
+    }
+}
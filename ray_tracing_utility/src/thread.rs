@@ -3,3 +3,6 @@ pub use self::ray_trace_thread::{RayTraceResult, RayTraceThread, RayTraceThreadD
 
 mod ray_trace_process;
 pub use self::ray_trace_process::RayTraceProcess;
+
+mod pixel_statistics;
+pub use self::pixel_statistics::{AdaptiveSampling, PixelStatistics};
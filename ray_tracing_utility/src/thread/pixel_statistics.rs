@@ -0,0 +1,165 @@
+use ray_tracing_core::types::{ColorRGB, FSize};
+use serde::{Deserialize, Serialize};
+
+/// Parameters of the adaptive sampling
+///
+/// A pixel is converged, if the estimated error of its mean luminance in the displayed (gamma 2) space
+/// drops below `threshold`. Converged pixels are skipped and the saved samples are spent on the noisy pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct AdaptiveSampling {
+    /// Maximum error of a converged pixel, e.g. 0.005 is about 1 step of an 8 bit color channel
+    pub threshold: FSize,
+
+    /// Minimum number of samples of a pixel, before the error is tested
+    pub min_samples: usize,
+
+    /// Maximum number of samples of a pixel
+    pub max_samples: usize,
+}
+
+/// Sums of the luminance of the samples of each pixel
+#[derive(Clone)]
+pub struct PixelStatistics {
+    pub cx: usize,
+    pub cy: usize,
+    sum: Vec<FSize>,
+    sum_squared: Vec<FSize>,
+    samples: Vec<usize>,
+    converged: Vec<bool>,
+}
+
+impl PixelStatistics {
+    pub fn new(cx: usize, cy: usize) -> PixelStatistics {
+        PixelStatistics {
+            cx,
+            cy,
+            sum: vec![0.0; cx * cy],
+            sum_squared: vec![0.0; cx * cy],
+            samples: vec![0; cx * cy],
+            converged: vec![false; cx * cy],
+        }
+    }
+
+    /// Relative luminance of a linear color
+    pub fn luminance(color: &ColorRGB) -> FSize {
+        0.2126 * color[0] + 0.7152 * color[1] + 0.0722 * color[2]
+    }
+
+    /// Adds the sum and the sum of the squares of the luminance of `samples` samples of the pixel (`x`, `y`)
+    pub fn add_samples(
+        &mut self,
+        x: usize,
+        y: usize,
+        samples: usize,
+        sum: FSize,
+        sum_squared: FSize,
+    ) {
+        let i = y * self.cx + x;
+        self.samples[i] += samples;
+        self.sum[i] += sum;
+        self.sum_squared[i] += sum_squared;
+    }
+
+    /// Adds the samples of an other statistic
+    pub fn merge(&mut self, other: &PixelStatistics) {
+        for i in 0..self.samples.len() {
+            self.samples[i] += other.samples[i];
+            self.sum[i] += other.sum[i];
+            self.sum_squared[i] += other.sum_squared[i];
+        }
+    }
+
+    /// Number of samples of the pixel (`x`, `y`)
+    pub fn samples(&self, x: usize, y: usize) -> usize {
+        self.samples[y * self.cx + x]
+    }
+
+    /// Total number of samples of all pixels
+    pub fn total_samples(&self) -> usize {
+        self.samples.iter().sum()
+    }
+
+    /// Check whether the pixel (`x`, `y`) needs no more samples
+    pub fn is_converged(&self, x: usize, y: usize) -> bool {
+        self.converged[y * self.cx + x]
+    }
+
+    /// Estimated error of the mean luminance of the pixel (`x`, `y`) in the displayed space.
+    /// The standard error of the mean is scaled by the derivative of the square root, which is applied for display.
+    pub fn error(&self, x: usize, y: usize) -> FSize {
+        let i = y * self.cx + x;
+        if self.samples[i] < 2 {
+            return FSize::MAX;
+        }
+        let n = self.samples[i] as FSize;
+        let mean = self.sum[i] / n;
+        let variance = FSize::max(0.0, (self.sum_squared[i] - mean * self.sum[i]) / (n - 1.0));
+        (variance / n).sqrt() / (2.0 * FSize::max(mean, 0.0001).sqrt())
+    }
+
+    /// Updates the converged pixels and returns the number of pixels, which need more samples
+    pub fn update_converged(&mut self, settings: &AdaptiveSampling) -> usize {
+        let mut active = 0;
+        for y in 0..self.cy {
+            for x in 0..self.cx {
+                let i = y * self.cx + x;
+                self.converged[i] = self.samples[i] >= settings.max_samples
+                    || (self.samples[i] >= settings.min_samples
+                        && self.error(x, y) < settings.threshold);
+                if !self.converged[i] {
+                    active += 1;
+                }
+            }
+        }
+        active
+    }
+}
+
+#[cfg(test)]
+mod pixel_statistics_test {
+    use super::*;
+
+    #[test]
+    fn error_test() {
+        let mut statistics = PixelStatistics::new(2, 1);
+        for _ in 0..100 {
+            statistics.add_samples(0, 0, 1, 0.5, 0.25);
+        }
+        for i in 0..100 {
+            let l = if i % 2 == 0 { 0.0 } else { 1.0 };
+            statistics.add_samples(1, 0, 1, l, l * l);
+        }
+        assert_eq!(statistics.samples(1, 0), 100);
+        assert_eq!(statistics.total_samples(), 200);
+        assert!(statistics.error(0, 0) < 0.000001);
+        assert!(statistics.error(1, 0) > 0.01);
+    }
+
+    #[test]
+    fn update_converged_test() {
+        let mut statistics = PixelStatistics::new(3, 1);
+        let mut other = PixelStatistics::new(3, 1);
+        for i in 0..16 {
+            let l = if i % 2 == 0 { 0.0 } else { 1.0 };
+            statistics.add_samples(0, 0, 1, 0.5, 0.25);
+            statistics.add_samples(1, 0, 1, l, l * l);
+            other.add_samples(2, 0, 1, l, l * l);
+        }
+        statistics.merge(&other);
+        let settings = AdaptiveSampling {
+            threshold: 0.01,
+            min_samples: 8,
+            max_samples: 64,
+        };
+        assert_eq!(statistics.update_converged(&settings), 2);
+        assert!(statistics.is_converged(0, 0));
+        assert!(!statistics.is_converged(1, 0));
+
+        let settings = AdaptiveSampling {
+            max_samples: 16,
+            ..settings
+        };
+        assert_eq!(statistics.update_converged(&settings), 0);
+        assert!(statistics.is_converged(2, 0));
+    }
+}
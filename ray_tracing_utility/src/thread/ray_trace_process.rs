@@ -1,9 +1,13 @@
 use crate::iterator::ViewportIterator;
-use crate::thread::{RayTraceResult, RayTraceThread, RayTraceThreadData, Viewport};
+use crate::thread::{
+    AdaptiveSampling, PixelStatistics, RayTraceResult, RayTraceThread, RayTraceThreadData, Viewport,
+};
 use ray_tracing_core::core::Scene;
+use ray_tracing_core::random;
+use ray_tracing_core::sampler::Sampler;
 use std::sync::mpsc;
 use std::sync::mpsc::Receiver;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
 use std::thread::JoinHandle;
 
@@ -12,6 +16,8 @@ pub struct RayTraceProcess {
     repetitions_threads: usize,
     repetitions: usize,
     samples: usize,
+    adaptive_sampling: Option<AdaptiveSampling>,
+    first_pass: usize,
     rx: Receiver<RayTraceResult>,
     thread_handles: Vec<JoinHandle<()>>,
     finished: Arc<Mutex<bool>>,
@@ -34,38 +40,118 @@ impl RayTraceProcess {
                 scene,
                 iterator,
                 tx,
+                seed: None,
+                sampler: None,
+                sampler_seed: random::generate_seed(),
+                statistics: None,
+                stop: Arc::new(Mutex::new(false)),
+                aovs: false,
             },
             repetitions_threads,
             repetitions,
             samples,
+            adaptive_sampling: None,
+            first_pass: 0,
             rx,
             thread_handles: Vec::default(),
             finished: Arc::new(Mutex::new(false)),
         }
     }
 
+    /// Seeds the random number generation of the render threads, to get reproducible results
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.thread_data.seed = seed;
+    }
+
+    /// Sets the generator of the samples of the pixels, `None` for independent random numbers
+    pub fn set_sampler(&mut self, sampler: Option<Arc<dyn Sampler>>) {
+        self.thread_data.sampler = sampler;
+    }
+
+    /// Activates adaptive sampling.
+    /// The passes are repeated until all pixels are converged or the samples of all `repetitions` are spent.
+    pub fn set_adaptive_sampling(&mut self, adaptive_sampling: Option<AdaptiveSampling>) {
+        self.adaptive_sampling = adaptive_sampling;
+        let viewport = &self.thread_data.viewport;
+        self.thread_data.statistics = adaptive_sampling
+            .map(|_| Arc::new(RwLock::new(PixelStatistics::new(viewport.cx, viewport.cy))));
+    }
+
+    /// Activates the rendering of the arbitrary output variables of the pixels
+    pub fn set_aovs(&mut self, aovs: bool) {
+        self.thread_data.aovs = aovs;
+    }
+
+    /// Resumes the rendering at the repetition, which contains `first_pass`. The rough pass 0 is skipped,
+    /// if `first_pass` is greater than 0. Passes of the repetition before `first_pass` are rendered again.
+    pub fn set_first_pass(&mut self, first_pass: usize) {
+        self.first_pass = first_pass;
+    }
+
     pub fn start(&mut self) {
         let repetitions_threads = self.repetitions_threads;
         let repetitions = self.repetitions;
         let samples = self.samples;
+        let adaptive_sampling = self.adaptive_sampling;
+        let first_pass = self.first_pass;
         let thread_data = self.thread_data.clone();
         let finished = self.finished.clone();
         self.thread_handles.push(thread::spawn(move || {
-            let first_rough_thread = RayTraceThread::new(&thread_data, 1);
-            first_rough_thread.handle.join().unwrap();
-            for _ in 0..repetitions / repetitions_threads {
+            if first_pass == 0 {
+                let first_rough_thread = RayTraceThread::new(&thread_data, 0, 1);
+                RayTraceProcess::merge_statistics(&thread_data, first_rough_thread);
+            }
+            let sample_budget =
+                thread_data.viewport.cx * thread_data.viewport.cy * (1 + repetitions * samples);
+            let mut repetition = first_pass.saturating_sub(1) / repetitions_threads;
+            loop {
+                if *thread_data.stop.lock().unwrap() {
+                    break;
+                }
+                match (&adaptive_sampling, &thread_data.statistics) {
+                    (Some(settings), Some(statistics)) => {
+                        let mut statistics = statistics.write().unwrap();
+                        if statistics.update_converged(settings) == 0
+                            || statistics.total_samples() >= sample_budget
+                        {
+                            break;
+                        }
+                    }
+                    _ => {
+                        if repetition >= repetitions / repetitions_threads {
+                            break;
+                        }
+                    }
+                }
                 let mut threads = Vec::default();
-                for _ in 0..repetitions_threads {
-                    threads.push(RayTraceThread::new(&thread_data, samples));
+                for thread_index in 0..repetitions_threads {
+                    let pass = 1 + repetition * repetitions_threads + thread_index;
+                    threads.push(RayTraceThread::new(&thread_data, pass, samples));
                 }
+                // the statistics are merged in the order of the threads, to get reproducible results
                 for thread in threads {
-                    thread.handle.join().unwrap();
+                    RayTraceProcess::merge_statistics(&thread_data, thread);
                 }
+                repetition += 1;
             }
             *finished.lock().unwrap() = true;
         }));
     }
 
+    fn merge_statistics(thread_data: &RayTraceThreadData, thread: RayTraceThread) {
+        let pass_statistics = thread.handle.join().unwrap();
+        if let (Some(statistics), Some(pass_statistics)) =
+            (&thread_data.statistics, pass_statistics)
+        {
+            statistics.write().unwrap().merge(&pass_statistics);
+        }
+    }
+
+    /// Stops the render threads after the pixels, which are currently rendered
+    pub fn stop(&self) {
+        *self.thread_data.stop.lock().unwrap() = true;
+    }
+
     pub fn finished(&self) -> bool {
         *self.finished.lock().unwrap()
     }
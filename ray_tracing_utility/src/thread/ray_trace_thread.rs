@@ -1,28 +1,45 @@
 use crate::iterator::ViewportIterator;
-use ray_tracing_core::core::Scene;
+use crate::thread::PixelStatistics;
+use ray_tracing_core::core::{AovRecord, Scene};
 use ray_tracing_core::random;
+use ray_tracing_core::sampler;
+use ray_tracing_core::sampler::Sampler;
 use ray_tracing_core::types::{ColorRGB, FSize};
+use std::ops::Range;
 use std::sync::mpsc::Sender;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
 use std::thread::JoinHandle;
 
 pub struct RayTraceResult {
     pub x: usize,
     pub y: usize,
+    pub pass: usize,
     pub samples: usize,
     pub size: usize,
     pub color: ColorRGB,
+
+    /// Mean of the arbitrary output variables of the samples, if they are rendered
+    pub aov: Option<AovRecord>,
 }
 
 impl RayTraceResult {
-    pub fn new(x: usize, y: usize, samples: usize, size: usize, color: ColorRGB) -> RayTraceResult {
+    pub fn new(
+        x: usize,
+        y: usize,
+        pass: usize,
+        samples: usize,
+        size: usize,
+        color: ColorRGB,
+    ) -> RayTraceResult {
         RayTraceResult {
             x,
             y,
+            pass,
             samples,
             size,
             color,
+            aov: None,
         }
     }
 }
@@ -39,30 +56,106 @@ pub struct RayTraceThreadData {
     pub scene: Arc<Scene>,
     pub iterator: Arc<Mutex<dyn ViewportIterator>>,
     pub tx: Sender<RayTraceResult>,
+
+    /// If set, each pixel of each pass is rendered with a random sequence derived from this seed
+    pub seed: Option<u64>,
+
+    /// Generator of the samples of the pixels
+    pub sampler: Option<Arc<dyn Sampler>>,
+
+    /// Seed of the scrambling of the sampler, if no `seed` is set
+    pub sampler_seed: u64,
+
+    /// Statistics of the previous passes for adaptive sampling. Converged pixels are skipped.
+    pub statistics: Option<Arc<RwLock<PixelStatistics>>>,
+
+    /// Set to stop the rendering
+    pub stop: Arc<Mutex<bool>>,
+
+    /// If set, the arbitrary output variables of the pixels are rendered
+    pub aovs: bool,
 }
 
 pub struct RayTraceThread {
-    pub handle: JoinHandle<()>,
+    /// Returns the statistics of the samples of the pass, if adaptive sampling is active
+    pub handle: JoinHandle<Option<PixelStatistics>>,
     pub finished: Arc<Mutex<bool>>,
 }
 
 impl RayTraceThread {
-    pub fn new(thread_data: &RayTraceThreadData, samples: usize) -> RayTraceThread {
+    pub fn new(thread_data: &RayTraceThreadData, pass: usize, samples: usize) -> RayTraceThread {
         let iterator = thread_data.iterator.clone();
         let scene = thread_data.scene.clone();
         let viewport = thread_data.viewport.clone();
         let tx = thread_data.tx.clone();
+        let seed = thread_data.seed;
+        let sampler = thread_data.sampler.clone();
+        let sampler_seed = thread_data.seed.unwrap_or(thread_data.sampler_seed);
+        let statistics = thread_data.statistics.clone();
+        let stop = thread_data.stop.clone();
+        let aovs = thread_data.aovs;
+        // the rough pass 0 renders the sample 0, the pass `p` renders the samples following the previous pass
+        let first_sample = if pass == 0 {
+            0
+        } else {
+            1 + (pass - 1) * samples
+        };
         let finished = Arc::new(Mutex::new(false));
         let finished_val = finished.clone();
         let handle = thread::spawn(move || {
             let iterator = iterator.lock().unwrap().create_new();
             let mut iterator = iterator.lock().unwrap();
+            let mut pass_statistics = statistics
+                .as_ref()
+                .map(|_| PixelStatistics::new(viewport.cx, viewport.cy));
             loop {
+                if *stop.lock().unwrap() {
+                    break;
+                }
                 match iterator.next() {
                     Some((x, y, size)) => {
-                        let color = RayTraceThread::render(scene.clone(), &viewport, samples, x, y);
-                        let result = RayTraceResult::new(x, y, samples, size, color);
-                        tx.send(result).unwrap();
+                        if let Some(statistics) = &statistics {
+                            if statistics.read().unwrap().is_converged(x, y) {
+                                let result = RayTraceResult::new(
+                                    x,
+                                    y,
+                                    pass,
+                                    0,
+                                    size,
+                                    ColorRGB::new(0.0, 0.0, 0.0),
+                                );
+                                // the receiver is dropped, if the viewer stops before the threads
+                                if tx.send(result).is_err() {
+                                    break;
+                                }
+                                continue;
+                            }
+                        }
+                        if let Some(seed) = seed {
+                            random::set_seed(Some(random::derive_seed(seed, x, y, pass)));
+                        }
+                        if let Some(sampler) = &sampler {
+                            random::set_sampler(
+                                Some(sampler.clone()),
+                                random::derive_seed(sampler_seed, x, y, 0),
+                            );
+                        }
+                        let (color, aov, sum, sum_squared) = RayTraceThread::render(
+                            scene.clone(),
+                            &viewport,
+                            first_sample..first_sample + samples,
+                            x,
+                            y,
+                            aovs,
+                        );
+                        if let Some(pass_statistics) = pass_statistics.as_mut() {
+                            pass_statistics.add_samples(x, y, samples, sum, sum_squared);
+                        }
+                        let mut result = RayTraceResult::new(x, y, pass, samples, size, color);
+                        result.aov = aov;
+                        if tx.send(result).is_err() {
+                            break;
+                        }
                     }
                     None => {
                         break;
@@ -70,23 +163,47 @@ impl RayTraceThread {
                 };
             }
             *finished_val.lock().unwrap() = true;
+            pass_statistics
         });
         RayTraceThread { handle, finished }
     }
 
+    /// Renders the samples of a pixel and returns the mean color, the mean of the arbitrary output variables
+    /// (if `aovs` is set), the sum and the sum of the squares of the luminance of the samples
     fn render(
         scene: Arc<Scene>,
         viewport: &Viewport,
-        samples: usize,
+        sample_range: Range<usize>,
         x: usize,
         y: usize,
-    ) -> ColorRGB {
+        aovs: bool,
+    ) -> (ColorRGB, Option<AovRecord>, FSize, FSize) {
         let mut c = ColorRGB::new(0.0, 0.0, 0.0);
-        for _ in 0..samples {
+        let mut aov: Option<AovRecord> = None;
+        let mut sum = 0.0;
+        let mut sum_squared = 0.0;
+        for sample in sample_range.clone() {
+            random::start_sample(sample);
+            random::start_dimension(sampler::DIMENSION_PIXEL);
             let u = (x as FSize + random::generate_size()) / viewport.cx as FSize;
             let v = 1.0 - (y as FSize + random::generate_size()) / viewport.cy as FSize;
-            c = c + scene.ray_trace_color(u, v);
+            let sample_color = if aovs {
+                let (sample_color, sample_aov) = scene.ray_trace_color_with_aovs(u, v);
+                // running mean, the ids of the first sample are kept
+                let n = (sample - sample_range.start) as FSize;
+                aov = Some(match aov {
+                    Some(aov) => aov.mix(&sample_aov, 1.0 / (n + 1.0)),
+                    None => sample_aov,
+                });
+                sample_color
+            } else {
+                scene.ray_trace_color(u, v)
+            };
+            let luminance = PixelStatistics::luminance(&sample_color);
+            sum += luminance;
+            sum_squared += luminance * luminance;
+            c = c + sample_color;
         }
-        c / samples as FSize
+        (c / sample_range.len() as FSize, aov, sum, sum_squared)
     }
 }
@@ -1,7 +1,16 @@
 use std::error::Error;
 
 mod viewer;
-pub use self::viewer::{ViewModel, Viewer};
+pub use self::viewer::{SamplerType, ViewModel, Viewer};
+
+mod display_settings;
+pub use self::display_settings::{DisplaySettings, ToneMappingType};
+
+mod checkpoint;
+pub use self::checkpoint::{Checkpoint, CheckpointSettings};
+
+mod headless_view;
+pub use self::headless_view::HeadlessView;
 
 #[derive(PartialEq)]
 pub enum Event {
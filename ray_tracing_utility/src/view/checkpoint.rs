@@ -0,0 +1,282 @@
+use crate::serialization::core;
+use crate::serialization::RayTracingObject;
+use crate::view::{SamplerType, ViewModel};
+use ray_tracing_core::core::{AovRecord, Scene};
+use ray_tracing_core::types::{ColorRGB, FSize, Point3, Vector3};
+use std::error::Error;
+use std::fs;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const MAGIC: &[u8; 4] = b"RTCP";
+const VERSION: u32 = 2;
+
+/// Checkpoints of the [`crate::view::Viewer`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckpointSettings {
+    /// File of the checkpoint
+    pub path: PathBuf,
+
+    /// Time between 2 checkpoints. A checkpoint is also written at the end of the run.
+    pub interval: Duration,
+
+    /// If set, the rendering is resumed from the checkpoint file, if it exists
+    pub resume: bool,
+}
+
+/// State of a render, from which the render can be resumed
+///
+/// The passes of a pixel are accumulated in order, `next_pass` is the first pass of each pixel,
+/// which is not yet contained in the accumulated colors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checkpoint {
+    pub cx: usize,
+    pub cy: usize,
+
+    /// Hash of the serialized scene
+    pub scene_hash: u64,
+
+    /// Hash of the settings of the view model, which change the samples of the pixels
+    pub view_model_hash: u64,
+
+    pub sample_count: Vec<i32>,
+    pub next_pass: Vec<usize>,
+
+    /// Accumulated linear colors of the pixels
+    pub pixel_color: Vec<ColorRGB>,
+
+    /// Accumulated arbitrary output variables, empty if they are not rendered
+    pub aov_records: Vec<AovRecord>,
+}
+
+impl Checkpoint {
+    /// Hash of the serialized scene.
+    /// The ids of the objects are part of the serialization, so the hash of a scene is stable,
+    /// if the scene is loaded from the same file.
+    pub fn scene_hash(scene: &Scene) -> Result<u64, Box<dyn Error>> {
+        let mut scene = core::Scene::from_scene(scene)?;
+        for object in scene.objects.iter_mut() {
+            if let RayTracingObject::Collection(collection) = object {
+                // the order of the nodes of a BVH depends on the random split axes
+                collection
+                    .object_id_list
+                    .sort_by_key(|id| (id.get_range().start, id.get_range().end));
+            }
+        }
+        Ok(Checkpoint::hash(serde_json::to_string(&scene)?.as_bytes()))
+    }
+
+    /// Hash of the settings, which have to be equal to resume a render.
+    /// The number of repetitions (except for the stratified sampler), the number of threads,
+    /// the display and the denoise settings can be changed.
+    pub fn view_model_hash(view_model: &ViewModel) -> u64 {
+        // the strata of the stratified sampler depend on the total number of samples
+        let stratified_repetitions = match view_model.sampler {
+            SamplerType::Stratified => view_model.repetitions,
+            _ => 0,
+        };
+        let settings = format!(
+            "{} {} {} {:?} {:?} {} {}",
+            view_model.cx,
+            view_model.cy,
+            view_model.samples,
+            view_model.seed,
+            view_model.sampler,
+            stratified_repetitions,
+            !view_model.aovs.is_empty() || view_model.denoise.is_some()
+        );
+        Checkpoint::hash(settings.as_bytes())
+    }
+
+    /// 64 bit FNV-1a hash, which is stable across platforms and builds
+    fn hash(bytes: &[u8]) -> u64 {
+        bytes.iter().fold(0xcbf29ce484222325, |h, b| {
+            (h ^ *b as u64).wrapping_mul(0x100000001b3)
+        })
+    }
+
+    /// First pass, which is not complete for all pixels
+    pub fn first_pass(&self) -> usize {
+        self.next_pass.iter().copied().min().unwrap_or(1)
+    }
+
+    /// Checks that the checkpoint belongs to the scene and the view model
+    pub fn check(&self, scene_hash: u64, view_model: &ViewModel) -> Result<(), Box<dyn Error>> {
+        if self.scene_hash != scene_hash {
+            return Err("the checkpoint belongs to a different scene".into());
+        }
+        if self.view_model_hash != Checkpoint::view_model_hash(view_model) {
+            return Err("the checkpoint belongs to a different view model".into());
+        }
+        Ok(())
+    }
+
+    /// Saves the checkpoint. The file is written to a temporary file first and renamed,
+    /// so that an existing checkpoint is not corrupted, if the process dies while saving.
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        let temp_path = PathBuf::from(temp_path);
+        let mut file = BufWriter::new(File::create(&temp_path)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&VERSION.to_le_bytes())?;
+        for value in [
+            self.cx as u64,
+            self.cy as u64,
+            self.scene_hash,
+            self.view_model_hash,
+            self.aov_records.len() as u64,
+        ] {
+            file.write_all(&value.to_le_bytes())?;
+        }
+        for i in 0..self.cx * self.cy {
+            file.write_all(&self.sample_count[i].to_le_bytes())?;
+            file.write_all(&(self.next_pass[i] as u64).to_le_bytes())?;
+            Checkpoint::write_vector(&mut file, &self.pixel_color[i])?;
+        }
+        for aov_record in self.aov_records.iter() {
+            file.write_all(&(aov_record.object_id as u64).to_le_bytes())?;
+            file.write_all(&(aov_record.material_id as u64).to_le_bytes())?;
+            file.write_all(&aov_record.depth.to_le_bytes())?;
+            for v in [
+                &aov_record.albedo,
+                &aov_record.normal,
+                &aov_record.position,
+                &aov_record.direct,
+                &aov_record.indirect,
+                &aov_record.emitted,
+            ] {
+                Checkpoint::write_vector(&mut file, v)?;
+            }
+        }
+        file.flush()?;
+        drop(file);
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Checkpoint, Box<dyn Error>> {
+        let mut file = BufReader::new(File::open(path)?);
+        let mut magic = [0; 4];
+        file.read_exact(&mut magic)?;
+        let mut version = [0; 4];
+        file.read_exact(&mut version)?;
+        if &magic != MAGIC || u32::from_le_bytes(version) != VERSION {
+            return Err(format!("{} is not a checkpoint", path.display()).into());
+        }
+        let cx = Checkpoint::read_u64(&mut file)? as usize;
+        let cy = Checkpoint::read_u64(&mut file)? as usize;
+        let scene_hash = Checkpoint::read_u64(&mut file)?;
+        let view_model_hash = Checkpoint::read_u64(&mut file)?;
+        let aov_records_len = Checkpoint::read_u64(&mut file)? as usize;
+        let mut checkpoint = Checkpoint {
+            cx,
+            cy,
+            scene_hash,
+            view_model_hash,
+            sample_count: Vec::with_capacity(cx * cy),
+            next_pass: Vec::with_capacity(cx * cy),
+            pixel_color: Vec::with_capacity(cx * cy),
+            aov_records: Vec::with_capacity(aov_records_len),
+        };
+        for _ in 0..cx * cy {
+            let mut sample_count = [0; 4];
+            file.read_exact(&mut sample_count)?;
+            checkpoint
+                .sample_count
+                .push(i32::from_le_bytes(sample_count));
+            checkpoint
+                .next_pass
+                .push(Checkpoint::read_u64(&mut file)? as usize);
+            checkpoint
+                .pixel_color
+                .push(Checkpoint::read_vector(&mut file)?);
+        }
+        for _ in 0..aov_records_len {
+            let object_id = Checkpoint::read_u64(&mut file)? as usize;
+            let material_id = Checkpoint::read_u64(&mut file)? as usize;
+            let depth = Checkpoint::read_f64(&mut file)?;
+            checkpoint.aov_records.push(AovRecord {
+                albedo: Checkpoint::read_vector(&mut file)?,
+                normal: Checkpoint::read_vector(&mut file)?,
+                depth,
+                position: Checkpoint::read_vector(&mut file)?,
+                object_id,
+                material_id,
+                direct: Checkpoint::read_vector(&mut file)?,
+                indirect: Checkpoint::read_vector(&mut file)?,
+                emitted: Checkpoint::read_vector(&mut file)?,
+                non_finite_source: None,
+            });
+        }
+        Ok(checkpoint)
+    }
+
+    fn write_vector(file: &mut impl Write, v: &Vector3) -> Result<(), Box<dyn Error>> {
+        for i in 0..3 {
+            file.write_all(&v[i].to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn read_u64(file: &mut impl Read) -> Result<u64, Box<dyn Error>> {
+        let mut bytes = [0; 8];
+        file.read_exact(&mut bytes)?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn read_f64(file: &mut impl Read) -> Result<FSize, Box<dyn Error>> {
+        let mut bytes = [0; 8];
+        file.read_exact(&mut bytes)?;
+        Ok(FSize::from_le_bytes(bytes))
+    }
+
+    fn read_vector(file: &mut impl Read) -> Result<Point3, Box<dyn Error>> {
+        Ok(Point3::new(
+            Checkpoint::read_f64(file)?,
+            Checkpoint::read_f64(file)?,
+            Checkpoint::read_f64(file)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod checkpoint_test {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn save_and_load_test() {
+        let mut aov_record = AovRecord::empty();
+        aov_record.albedo = ColorRGB::new(0.5, 0.25, 0.125);
+        aov_record.depth = 3.0;
+        aov_record.object_id = 7;
+        aov_record.indirect = ColorRGB::new(1.0, 2.0, 3.0);
+        aov_record.emitted = ColorRGB::new(4.0, 5.0, 6.0);
+        let checkpoint = Checkpoint {
+            cx: 2,
+            cy: 1,
+            scene_hash: 1234,
+            view_model_hash: 5678,
+            sample_count: vec![10, -1],
+            next_pass: vec![4, 3],
+            pixel_color: vec![ColorRGB::new(0.1, 0.2, 0.3), ColorRGB::new(4.0, 5.0, 6.0)],
+            aov_records: vec![aov_record, AovRecord::empty()],
+        };
+        assert_eq!(checkpoint.first_pass(), 3);
+        let path = env::temp_dir().join(format!("rt_checkpoint_test_{}.bin", std::process::id()));
+        checkpoint.save(&path).unwrap();
+        let loaded = Checkpoint::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), checkpoint);
+    }
+
+    #[test]
+    fn hash_test() {
+        assert_eq!(Checkpoint::hash(b""), 0xcbf29ce484222325);
+        assert_eq!(Checkpoint::hash(b"a"), 0xaf63dc4c8601ec8c);
+        assert_ne!(Checkpoint::hash(b"scene 1"), Checkpoint::hash(b"scene 2"));
+    }
+}
@@ -0,0 +1,66 @@
+use ray_tracing_core::display::{DisplayPipeline, ToneMapping};
+use ray_tracing_core::types::FSize;
+use serde::{Deserialize, Serialize};
+
+/// Tone mapping operator of the display pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize, Serialize)]
+pub enum ToneMappingType {
+    /// Values above 1.0 are clipped
+    #[default]
+    Clamp,
+
+    Reinhard,
+
+    /// ACES filmic curve
+    AcesFilmic,
+
+    /// Filmic curve of John Hable
+    Hable,
+}
+
+/// Conversion of the rendered linear colors to the 8 bit sRGB images, which are displayed and saved
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize, Serialize)]
+pub struct DisplaySettings {
+    /// Exposure in stops
+    #[serde(default)]
+    pub exposure: FSize,
+
+    #[serde(default)]
+    pub tone_mapping: ToneMappingType,
+
+    /// Adds a noise of +/- 0.5 quantization steps, to avoid banding
+    #[serde(default)]
+    pub dithering: bool,
+}
+
+impl DisplaySettings {
+    pub fn to_display_pipeline(&self) -> DisplayPipeline {
+        DisplayPipeline::new(
+            self.exposure,
+            match self.tone_mapping {
+                ToneMappingType::Clamp => ToneMapping::Clamp,
+                ToneMappingType::Reinhard => ToneMapping::Reinhard,
+                ToneMappingType::AcesFilmic => ToneMapping::AcesFilmic,
+                ToneMappingType::Hable => ToneMapping::Hable,
+            },
+            self.dithering,
+        )
+    }
+}
+
+#[cfg(test)]
+mod display_settings_test {
+    use super::*;
+
+    #[test]
+    fn deserialize_test() {
+        let settings: DisplaySettings =
+            serde_json::from_str(r#"{ "exposure": -1.5, "tone_mapping": "AcesFilmic" }"#).unwrap();
+        assert_eq!(
+            settings.to_display_pipeline(),
+            DisplayPipeline::new(-1.5, ToneMapping::AcesFilmic, false)
+        );
+        let settings: DisplaySettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(settings.to_display_pipeline(), DisplayPipeline::default());
+    }
+}
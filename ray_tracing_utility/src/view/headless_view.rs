@@ -0,0 +1,27 @@
+use crate::view::{Event, View};
+use std::error::Error;
+
+/// View without a window, for rendering on machines without a display
+pub struct HeadlessView {}
+
+impl HeadlessView {
+    pub fn new() -> HeadlessView {
+        HeadlessView {}
+    }
+}
+
+impl Default for HeadlessView {
+    fn default() -> Self {
+        HeadlessView::new()
+    }
+}
+
+impl View for HeadlessView {
+    fn update(&self, _: &Vec<u8>) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn handle_events(&self) -> Result<Event, Box<dyn Error>> {
+        Ok(Event::None)
+    }
+}
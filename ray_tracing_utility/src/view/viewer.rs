@@ -1,15 +1,51 @@
+use crate::aov::{AovBuffers, AovType};
+use crate::denoise;
+use crate::denoise::{DenoiseSettings, Denoiser};
 use crate::iterator::IteratorExp2;
-use crate::thread::RayTraceProcess;
+use crate::thread::{AdaptiveSampling, RayTraceProcess};
 use crate::view;
-use crate::view::View;
-use ray_tracing_core::core::Scene;
+use crate::view::{Checkpoint, CheckpointSettings, DisplaySettings, View};
+use ray_tracing_core::core::{AovRecord, Scene};
+use ray_tracing_core::display::DisplayPipeline;
+use ray_tracing_core::sampler::{HaltonSampler, Sampler, SobolSampler, StratifiedSampler};
 use ray_tracing_core::types::{ColorRGB, FSize};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::error::Error;
 use std::ops::Fn;
 use std::sync::{Arc, Mutex};
+use std::thread;
 use std::time::{Duration, SystemTime};
 
+/// Generator of the samples of a pixel
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize, Serialize)]
+pub enum SamplerType {
+    /// Independent uniform random numbers
+    #[default]
+    Independent,
+
+    /// Stratified (Latin hypercube) samples
+    Stratified,
+
+    /// Randomly rotated Halton sequence
+    Halton,
+
+    /// Owen scrambled Sobol sequence
+    Sobol,
+}
+
+impl SamplerType {
+    /// Creates the sampler for `samples_per_pixel` samples, `None` for independent random numbers
+    pub fn to_sampler(&self, samples_per_pixel: usize) -> Option<Arc<dyn Sampler>> {
+        match self {
+            SamplerType::Independent => None,
+            SamplerType::Stratified => Some(Arc::new(StratifiedSampler::new(samples_per_pixel))),
+            SamplerType::Halton => Some(Arc::new(HaltonSampler::new())),
+            SamplerType::Sobol => Some(Arc::new(SobolSampler::new())),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct ViewModel {
     pub cx: usize,
@@ -17,13 +53,45 @@ pub struct ViewModel {
     pub repetitions_threads: usize,
     pub repetitions: usize,
     pub samples: usize,
+
+    /// Seed of the random number generation. Overrides the seed of the scene configuration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+
+    #[serde(default)]
+    pub sampler: SamplerType,
+
+    /// If set, converged pixels are not sampled further and the samples are spent on the noisy pixels
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adaptive_sampling: Option<AdaptiveSampling>,
+
+    /// Exposure, tone mapping and dithering of the displayed and saved 8 bit images
+    #[serde(default)]
+    pub display: DisplaySettings,
+
+    /// Arbitrary output variables, which are rendered in addition to the colors
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aovs: Vec<AovType>,
+
+    /// If set, the result is denoised with the albedo, normal and depth feature buffers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub denoise: Option<DenoiseSettings>,
 }
 
 pub struct Viewer {
     view_model: ViewModel,
+    scene: Arc<Scene>,
     ray_tracer: RayTraceProcess,
+    display: DisplayPipeline,
     view: Arc<dyn View>,
     fn_save: Box<dyn Fn(usize, usize, usize, &Vec<u8>) -> ()>,
+    time_limit: Option<Duration>,
+    sample_count: Vec<i32>,
+    pixel_color: Vec<ColorRGB>,
+    aov_records: Vec<AovRecord>,
+    checkpoint: Option<CheckpointSettings>,
+    resume_checkpoint: Option<Checkpoint>,
+    scene_hash: u64,
 }
 
 impl Viewer {
@@ -33,7 +101,7 @@ impl Viewer {
         view: Arc<dyn View>,
         fn_save: Box<dyn Fn(usize, usize, usize, &Vec<u8>) -> ()>,
     ) -> Result<Viewer, Box<dyn Error>> {
-        let ray_tracer = RayTraceProcess::new(
+        let mut ray_tracer = RayTraceProcess::new(
             view_model.cx,
             view_model.cy,
             view_model.repetitions_threads,
@@ -42,14 +110,61 @@ impl Viewer {
             Arc::new(scene.from_scene_and_aspect(view_model.cx as FSize / view_model.cy as FSize)?),
             Arc::new(Mutex::new(IteratorExp2::new(view_model.cx, view_model.cy))),
         );
+        ray_tracer.set_seed(view_model.seed.or(scene.configuration.seed));
+        // the first rough pass renders 1 sample per pixel
+        ray_tracer.set_sampler(
+            view_model
+                .sampler
+                .to_sampler(1 + view_model.repetitions * view_model.samples),
+        );
+        ray_tracer.set_adaptive_sampling(view_model.adaptive_sampling);
+        ray_tracer.set_aovs(!view_model.aovs.is_empty() || view_model.denoise.is_some());
         Ok(Viewer {
+            display: view_model.display.to_display_pipeline(),
             view_model,
+            scene,
             ray_tracer,
             view: view.clone(),
             fn_save: Box::new(fn_save),
+            time_limit: None,
+            sample_count: Vec::default(),
+            pixel_color: Vec::default(),
+            aov_records: Vec::default(),
+            checkpoint: None,
+            resume_checkpoint: None,
+            scene_hash: 0,
         })
     }
 
+    /// Limits the render time. When the time is elapsed, the image rendered so far is the result.
+    pub fn set_time_limit(&mut self, time_limit: Option<Duration>) {
+        self.time_limit = time_limit;
+    }
+
+    /// Writes checkpoints of the render at intervals and at the end of the run.
+    /// If `resume` is set and the checkpoint file exists, the render is continued from the checkpoint
+    /// until the samples of all repetitions of the view model are rendered.
+    /// An error is returned, if the checkpoint belongs to a different scene or view model.
+    pub fn set_checkpoint(
+        &mut self,
+        settings: Option<CheckpointSettings>,
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(settings) = &settings {
+            if self.view_model.adaptive_sampling.is_some() {
+                return Err("checkpoints are not supported with adaptive sampling".into());
+            }
+            self.scene_hash = Checkpoint::scene_hash(&self.scene)?;
+            if settings.resume && settings.path.is_file() {
+                let checkpoint = Checkpoint::load(&settings.path)?;
+                checkpoint.check(self.scene_hash, &self.view_model)?;
+                self.ray_tracer.set_first_pass(checkpoint.first_pass());
+                self.resume_checkpoint = Some(checkpoint);
+            }
+        }
+        self.checkpoint = settings;
+        Ok(())
+    }
+
     pub fn run(&mut self) -> Result<(usize, usize, Vec<u8>), Box<dyn Error>> {
         let mut pixel_data: Vec<u8> =
             Vec::with_capacity(self.view_model.cx * self.view_model.cy * 4);
@@ -63,17 +178,44 @@ impl Viewer {
             self.view_model.cx * self.view_model.cy,
             ColorRGB::new(0.0, 0.0, 0.0),
         );
+        let mut aov_records =
+            if self.view_model.aovs.is_empty() && self.view_model.denoise.is_none() {
+                Vec::default()
+            } else {
+                vec![AovRecord::empty(); self.view_model.cx * self.view_model.cy]
+            };
+
+        // The passes of a pixel are accumulated in the order of the pass index,
+        // so that the result does not depend on the scheduling of the threads.
+        let mut next_pass: Vec<usize> = vec![1; self.view_model.cx * self.view_model.cy];
+        let mut pending_results: HashMap<(usize, usize), (usize, ColorRGB, Option<AovRecord>)> =
+            HashMap::default();
+
+        if let Some(checkpoint) = self.resume_checkpoint.take() {
+            sample_count = checkpoint.sample_count;
+            next_pass = checkpoint.next_pass;
+            pixel_color = checkpoint.pixel_color;
+            if !checkpoint.aov_records.is_empty() {
+                aov_records = checkpoint.aov_records;
+            }
+            pixel_data = self.display.to_pixel_data(self.view_model.cx, &pixel_color);
+        }
 
         let start_time = SystemTime::now();
         let mut update_duration = Duration::from_secs(1);
+        let mut checkpoint_duration = match &self.checkpoint {
+            Some(settings) => settings.interval,
+            None => Duration::MAX,
+        };
 
         self.ray_tracer.start();
 
         let expected_results =
             self.view_model.cx * self.view_model.cy * self.view_model.repetitions;
-        let mut received_results = 0;
+        let mut received_results = next_pass.iter().map(|pass| pass - 1).sum::<usize>();
         let mut image_number = 0;
         let mut exit = false;
+        let mut finished = false;
         loop {
             match self.ray_tracer.next() {
                 Some(result) => {
@@ -86,34 +228,71 @@ impl Viewer {
                                 let inner_i = (iy * self.view_model.cx) + ix;
                                 if sample_count[inner_i] < 0 {
                                     pixel_color[inner_i] = result.color;
+                                    if let Some(aov) = result.aov {
+                                        aov_records[inner_i] = aov;
+                                    }
                                     sample_count[i] = if i == inner_i { 1 } else { 0 };
-                                    pixel_data[inner_i * 4] =
-                                        (result.color[0].sqrt() * 255.0).round() as u8;
-                                    pixel_data[inner_i * 4 + 1] =
-                                        (result.color[1].sqrt() * 255.0).round() as u8;
-                                    pixel_data[inner_i * 4 + 2] =
-                                        (result.color[2].sqrt() * 255.0).round() as u8;
-                                    pixel_data[inner_i * 4 + 3] = 255;
+                                    pixel_data[inner_i * 4..inner_i * 4 + 4].copy_from_slice(
+                                        &self.display.to_rgba8(&result.color, ix, iy),
+                                    );
                                 }
                             }
                         }
-                    } else {
-                        if sample_count[i] < 0 {
-                            sample_count[i] = 0;
+                    } else if result.pass == 0 {
+                        Viewer::accumulate_aov(
+                            i,
+                            result.samples,
+                            &result.aov,
+                            sample_count[i],
+                            &mut aov_records,
+                        );
+                        self.accumulate(
+                            i,
+                            result.samples,
+                            result.color,
+                            &mut sample_count,
+                            &mut pixel_color,
+                            &mut pixel_data,
+                        );
+                    } else if result.pass >= next_pass[i] {
+                        // passes before `next_pass` are contained in a resumed checkpoint
+                        pending_results
+                            .insert((i, result.pass), (result.samples, result.color, result.aov));
+                        while let Some((samples, color, aov)) =
+                            pending_results.remove(&(i, next_pass[i]))
+                        {
+                            next_pass[i] += 1;
+                            Viewer::accumulate_aov(
+                                i,
+                                samples,
+                                &aov,
+                                sample_count[i],
+                                &mut aov_records,
+                            );
+                            self.accumulate(
+                                i,
+                                samples,
+                                color,
+                                &mut sample_count,
+                                &mut pixel_color,
+                                &mut pixel_data,
+                            );
+                            received_results += 1;
                         }
-                        let w = result.samples as FSize
-                            / (sample_count[i] + result.samples as i32) as FSize;
-                        let c = pixel_color[i] * (1.0 - w) + result.color * w;
-                        pixel_color[i] = c;
-                        sample_count[i] += result.samples as i32;
-                        pixel_data[i * 4] = (c[0].sqrt() * 255.0).round() as u8;
-                        pixel_data[i * 4 + 1] = (c[1].sqrt() * 255.0).round() as u8;
-                        pixel_data[i * 4 + 2] = (c[2].sqrt() * 255.0).round() as u8;
-                        pixel_data[i * 4 + 3] = 255;
-                        received_results += 1;
                     }
                 }
-                None => (),
+                None => {
+                    // all threads have finished and all results are received
+                    if finished {
+                        self.update_view(&pixel_data, &pixel_color, &aov_records)?;
+                        break;
+                    }
+                    // the results, which are sent before the threads have finished, are received in the next iterations
+                    finished = self.ray_tracer.finished();
+                    if !finished {
+                        thread::sleep(Duration::from_millis(1));
+                    }
+                }
             }
 
             match self.view.handle_events() {
@@ -133,21 +312,174 @@ impl Viewer {
             if exit {
                 break;
             }
-            if start_time.elapsed().unwrap() >= update_duration {
-                let finished = self.ray_tracer.finished();
-                update_duration += Duration::from_secs(1);
-                self.view.update(&pixel_data)?;
-                if finished {
+            if let Some(time_limit) = self.time_limit {
+                if start_time.elapsed().unwrap() >= time_limit {
+                    self.ray_tracer.stop();
+                    self.update_view(&pixel_data, &pixel_color, &aov_records)?;
+                    println!("time limit reached");
                     break;
                 }
-                println!("{}", received_results as f32 / expected_results as f32);
+            }
+            if !finished && start_time.elapsed().unwrap() >= update_duration {
+                finished = self.ray_tracer.finished();
+                update_duration += Duration::from_secs(1);
+                self.update_view(&pixel_data, &pixel_color, &aov_records)?;
+                println!(
+                    "{}",
+                    f32::min(1.0, received_results as f32 / expected_results as f32)
+                );
+            }
+            if let Some(settings) = &self.checkpoint {
+                if start_time.elapsed().unwrap() >= checkpoint_duration {
+                    checkpoint_duration = start_time.elapsed().unwrap() + settings.interval;
+                    self.save_checkpoint(&sample_count, &next_pass, &pixel_color, &aov_records)?;
+                }
             }
         }
+        self.save_checkpoint(&sample_count, &next_pass, &pixel_color, &aov_records)?;
 
+        self.sample_count = sample_count;
+        if let Some(settings) = self.view_model.denoise {
+            pixel_color = self.denoise(&settings, &pixel_color, &aov_records);
+            pixel_data = self.display.to_pixel_data(self.view_model.cx, &pixel_color);
+        }
+        self.pixel_color = pixel_color;
+        self.aov_records = aov_records;
         if exit {
             Err("abort".into())
         } else {
             Ok((self.view_model.cx, self.view_model.cy, pixel_data))
         }
     }
+
+    /// Saves the state of the render, if checkpoints are active and the rough pass is complete
+    fn save_checkpoint(
+        &self,
+        sample_count: &[i32],
+        next_pass: &[usize],
+        pixel_color: &[ColorRGB],
+        aov_records: &[AovRecord],
+    ) -> Result<(), Box<dyn Error>> {
+        match &self.checkpoint {
+            Some(settings) if sample_count.iter().all(|count| *count >= 0) => Checkpoint {
+                cx: self.view_model.cx,
+                cy: self.view_model.cy,
+                scene_hash: self.scene_hash,
+                view_model_hash: Checkpoint::view_model_hash(&self.view_model),
+                sample_count: sample_count.to_vec(),
+                next_pass: next_pass.to_vec(),
+                pixel_color: pixel_color.to_vec(),
+                aov_records: aov_records.to_vec(),
+            }
+            .save(&settings.path),
+            _ => Ok(()),
+        }
+    }
+
+    /// Updates the view, with the denoised image if the denoise preview is active
+    fn update_view(
+        &self,
+        pixel_data: &Vec<u8>,
+        pixel_color: &[ColorRGB],
+        aov_records: &[AovRecord],
+    ) -> Result<(), Box<dyn Error>> {
+        match self.view_model.denoise {
+            Some(settings) if settings.preview => {
+                let denoised = self.denoise(&settings, pixel_color, aov_records);
+                self.view
+                    .update(&self.display.to_pixel_data(self.view_model.cx, &denoised))
+            }
+            _ => self.view.update(pixel_data),
+        }
+    }
+
+    fn denoise(
+        &self,
+        settings: &DenoiseSettings,
+        pixel_color: &[ColorRGB],
+        aov_records: &[AovRecord],
+    ) -> Vec<ColorRGB> {
+        let features = AovBuffers::new(
+            self.view_model.cx,
+            self.view_model.cy,
+            &denoise::FEATURE_AOVS,
+            aov_records,
+        );
+        Denoiser::new(*settings).denoise(pixel_color, &features)
+    }
+
+    /// Linear colors of the pixels of the last run, before the gamma correction and the quantization to 8 bit.
+    /// If the view model requests denoising, the colors are denoised.
+    pub fn pixel_color(&self) -> &Vec<ColorRGB> {
+        &self.pixel_color
+    }
+
+    /// Buffers of the arbitrary output variables of the view model of the last run,
+    /// `None` if no arbitrary output variables are rendered
+    pub fn aov_buffers(&self) -> Option<AovBuffers> {
+        if self.view_model.aovs.is_empty() {
+            None
+        } else {
+            Some(AovBuffers::new(
+                self.view_model.cx,
+                self.view_model.cy,
+                &self.view_model.aovs,
+                &self.aov_records,
+            ))
+        }
+    }
+
+    /// Heat map of the number of samples of each pixel of the last run, as RGBA pixel data.
+    /// Pixels with few samples are blue, pixels with the most samples are red.
+    pub fn sample_count_heat_map(&self) -> Vec<u8> {
+        let max_count = self.sample_count.iter().fold(1, |m, c| i32::max(m, *c));
+        let mut pixel_data = Vec::with_capacity(self.sample_count.len() * 4);
+        for count in self.sample_count.iter() {
+            let t = i32::max(0, *count) as FSize / max_count as FSize;
+            pixel_data.push((FSize::clamp(2.0 * t - 1.0, 0.0, 1.0) * 255.0).round() as u8);
+            pixel_data.push(((1.0 - FSize::abs(2.0 * t - 1.0)) * 255.0).round() as u8);
+            pixel_data.push((FSize::clamp(1.0 - 2.0 * t, 0.0, 1.0) * 255.0).round() as u8);
+            pixel_data.push(255);
+        }
+        pixel_data
+    }
+
+    fn accumulate_aov(
+        i: usize,
+        samples: usize,
+        aov: &Option<AovRecord>,
+        sample_count: i32,
+        aov_records: &mut [AovRecord],
+    ) {
+        if samples == 0 {
+            return;
+        }
+        if let Some(aov) = aov {
+            let w = samples as FSize / (i32::max(0, sample_count) + samples as i32) as FSize;
+            aov_records[i] = aov_records[i].mix(aov, w);
+        }
+    }
+
+    fn accumulate(
+        &self,
+        i: usize,
+        samples: usize,
+        color: ColorRGB,
+        sample_count: &mut [i32],
+        pixel_color: &mut [ColorRGB],
+        pixel_data: &mut [u8],
+    ) {
+        if samples == 0 {
+            return;
+        }
+        if sample_count[i] < 0 {
+            sample_count[i] = 0;
+        }
+        let w = samples as FSize / (sample_count[i] + samples as i32) as FSize;
+        let c = pixel_color[i] * (1.0 - w) + color * w;
+        pixel_color[i] = c;
+        sample_count[i] += samples as i32;
+        let (x, y) = (i % self.view_model.cx, i / self.view_model.cx);
+        pixel_data[i * 4..i * 4 + 4].copy_from_slice(&self.display.to_rgba8(&c, x, y));
+    }
 }